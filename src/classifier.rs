@@ -0,0 +1,407 @@
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::confession::Confession;
+use crate::config::{ClassifyConfig, Config};
+use crate::error::Result;
+use crate::llm;
+use crate::prompts::PromptTemplate;
+
+/// Sensitive topics a confession can touch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContentCategory {
+    SelfHarm,
+    Abuse,
+    SexualContent,
+    Violence,
+    SubstanceUse,
+    EatingDisorder,
+    Death,
+}
+
+impl ContentCategory {
+    pub const ALL: [ContentCategory; 7] = [
+        ContentCategory::SelfHarm,
+        ContentCategory::Abuse,
+        ContentCategory::SexualContent,
+        ContentCategory::Violence,
+        ContentCategory::SubstanceUse,
+        ContentCategory::EatingDisorder,
+        ContentCategory::Death,
+    ];
+
+    /// The name used in config files and LLM answers, e.g. `self_harm`.
+    pub fn key(self) -> &'static str {
+        match self {
+            ContentCategory::SelfHarm => "self_harm",
+            ContentCategory::Abuse => "abuse",
+            ContentCategory::SexualContent => "sexual_content",
+            ContentCategory::Violence => "violence",
+            ContentCategory::SubstanceUse => "substance_use",
+            ContentCategory::EatingDisorder => "eating_disorder",
+            ContentCategory::Death => "death",
+        }
+    }
+
+    fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|category| category.key() == key)
+    }
+}
+
+/// How the category reads in a spoken warning.
+impl fmt::Display for ContentCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            ContentCategory::SelfHarm => "self-harm",
+            ContentCategory::Abuse => "abuse",
+            ContentCategory::SexualContent => "sexual content",
+            ContentCategory::Violence => "violence",
+            ContentCategory::SubstanceUse => "drug or alcohol use",
+            ContentCategory::EatingDisorder => "eating disorders",
+            ContentCategory::Death => "death",
+        };
+        write!(f, "{}", text)
+    }
+}
+
+/// Phrases that mark a category, matched case-insensitively on word boundaries.
+/// Words with everyday senses ("killed it", "beat me at chess", "high on life")
+/// only appear inside phrases that pin down the sensitive one.
+const LEXICON: &[(ContentCategory, &[&str])] = &[
+    (
+        ContentCategory::SelfHarm,
+        &[
+            "self harm",
+            "self-harm",
+            "cut myself",
+            "cutting myself",
+            "suicide",
+            "suicidal",
+            "kill myself",
+            "killing myself",
+            "end my life",
+            "want to die",
+        ],
+    ),
+    (
+        ContentCategory::Abuse,
+        &[
+            "abuse",
+            "abused",
+            "abusive",
+            "abuser",
+            "molest",
+            "molested",
+            "groomed",
+            "grooming",
+            "rape",
+            "raped",
+            "sexually assaulted",
+            "domestic violence",
+        ],
+    ),
+    (
+        ContentCategory::SexualContent,
+        &[
+            "had sex",
+            "have sex",
+            "having sex",
+            "sexual",
+            "naked",
+            "nude",
+            "nudes",
+            "porn",
+            "hooked up",
+            "orgasm",
+            "horny",
+            "threesome",
+        ],
+    ),
+    (
+        ContentCategory::Violence,
+        &[
+            "murder",
+            "murdered",
+            "stabbed",
+            "shot him",
+            "shot her",
+            "shot me",
+            "pulled a gun",
+            "beat him up",
+            "beat her up",
+            "beat me up",
+            "killed him",
+            "killed her",
+            "killed someone",
+        ],
+    ),
+    (
+        ContentCategory::SubstanceUse,
+        &[
+            "cocaine",
+            "heroin",
+            "meth",
+            "overdose",
+            "overdosed",
+            "got high",
+            "getting high",
+            "high on drugs",
+            "drug addiction",
+            "addicted to drugs",
+            "relapse",
+            "relapsed",
+        ],
+    ),
+    (
+        ContentCategory::EatingDisorder,
+        &[
+            "anorexia",
+            "anorexic",
+            "bulimia",
+            "bulimic",
+            "purging",
+            "starving myself",
+            "eating disorder",
+        ],
+    ),
+    (
+        ContentCategory::Death,
+        &["died", "passed away", "funeral", "found dead"],
+    ),
+];
+
+/// Tags confessions with [`ContentCategory`]s and decides what each tag means
+/// for the video: blocked, warned about, or age-restricted.
+pub struct Classifier {
+    config: ClassifyConfig,
+    lexicon: Vec<(ContentCategory, Regex)>,
+}
+
+impl Classifier {
+    pub fn from_config(config: &ClassifyConfig) -> Self {
+        let lexicon = ContentCategory::ALL
+            .into_iter()
+            .filter_map(|category| {
+                let built_in = LEXICON
+                    .iter()
+                    .filter(|(c, _)| *c == category)
+                    .flat_map(|(_, words)| words.iter().map(|word| word.to_string()));
+                let extra = config
+                    .keywords
+                    .get(&category)
+                    .into_iter()
+                    .flatten()
+                    .cloned();
+                let alternatives = built_in
+                    .chain(extra)
+                    .map(|word| regex::escape(word.trim()))
+                    .filter(|word| !word.is_empty())
+                    .collect::<Vec<_>>();
+                if alternatives.is_empty() {
+                    return None;
+                }
+                let pattern = format!(r"\b(?:{})\b", alternatives.join("|"));
+                // Escaped literals joined by `|` always compile.
+                RegexBuilder::new(&pattern)
+                    .case_insensitive(true)
+                    .build()
+                    .ok()
+                    .map(|regex| (category, regex))
+            })
+            .collect();
+
+        Self {
+            config: config.clone(),
+            lexicon,
+        }
+    }
+
+    /// Categories found by the keyword lexicon alone.
+    pub fn lexicon_categories(&self, text: &str) -> Vec<ContentCategory> {
+        if !self.config.enabled {
+            return Vec::new();
+        }
+        self.lexicon
+            .iter()
+            .filter(|(_, regex)| regex.is_match(text))
+            .map(|(category, _)| *category)
+            .collect()
+    }
+
+    /// The configured `block` categories among `categories`.
+    pub fn blocked(&self, categories: &[ContentCategory]) -> Vec<ContentCategory> {
+        matching(categories, &self.config.block)
+    }
+
+    /// The categories a content warning should name.
+    pub fn warnings(&self, categories: &[ContentCategory]) -> Vec<ContentCategory> {
+        matching(categories, &self.config.warn)
+    }
+
+    pub fn age_restricted(&self, categories: &[ContentCategory]) -> bool {
+        !matching(categories, &self.config.age_restrict).is_empty()
+    }
+
+    /// The spoken and captioned warning for `categories`, if any needs one.
+    pub fn warning_text(&self, categories: &[ContentCategory]) -> Option<String> {
+        let warnings = self.warnings(categories);
+        if warnings.is_empty() {
+            return None;
+        }
+        Some(
+            self.config
+                .warning
+                .replace("{topics}", &spoken_list(&warnings)),
+        )
+    }
+}
+
+fn matching(categories: &[ContentCategory], wanted: &[ContentCategory]) -> Vec<ContentCategory> {
+    categories
+        .iter()
+        .filter(|category| wanted.contains(category))
+        .copied()
+        .collect()
+}
+
+/// `a`, `a and b`, `a, b and c`.
+fn spoken_list(categories: &[ContentCategory]) -> String {
+    let names = categories.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+    match names.split_last() {
+        Some((last, rest)) if !rest.is_empty() => format!("{} and {}", rest.join(", "), last),
+        Some((last, _)) => last.clone(),
+        None => String::new(),
+    }
+}
+
+/// Tags `confession` with the lexicon's categories and, with `classify.use_llm`,
+/// the model's via the `prompts.classify` template. Categories already on the
+/// confession are kept.
+pub async fn classify(config: &Config, confession: &mut Confession) -> Result<()> {
+    let classifier = Classifier::from_config(&config.classify);
+    let script = confession.script();
+    let mut categories = confession.content.clone();
+    categories.extend(classifier.lexicon_categories(&script));
+
+    if config.classify.enabled && config.classify.use_llm {
+        let template = PromptTemplate::load(&config.prompts, &config.prompts.classify)?;
+        let keys = ContentCategory::ALL.map(ContentCategory::key).join(", ");
+        let prompt = template.render(&[("script", &script), ("categories", &keys)])?;
+        let answer = llm::complete(config, &prompt.system, &prompt.user).await?;
+        categories.extend(parse_labels(&answer));
+    }
+
+    categories.sort();
+    categories.dedup();
+    confession.content = categories;
+    Ok(())
+}
+
+/// Reads the labels out of a model answer, ignoring anything that is not one.
+fn parse_labels(answer: &str) -> Vec<ContentCategory> {
+    answer
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .map(|label| {
+            label
+                .trim_matches(|c: char| !c.is_alphanumeric() && c != '_')
+                .to_lowercase()
+        })
+        .filter_map(|label| ContentCategory::from_key(&label))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn classifier() -> Classifier {
+        Classifier::from_config(&ClassifyConfig::default())
+    }
+
+    #[test]
+    fn everyday_wording_is_not_flagged() {
+        let text = "I killed it at the interview, my brother beat me at chess, I was high on \
+                    life, we asked the sex of the baby, I'm gun-shy and dead tired, and I'm \
+                    addicted to this show.";
+        assert!(classifier().lexicon_categories(text).is_empty());
+    }
+
+    #[test]
+    fn lexicon_phrases_tag_their_categories() {
+        let text = "My dad BEAT ME UP after he got high, and I had thoughts of self-harm.";
+        assert_eq!(
+            classifier().lexicon_categories(text),
+            [
+                ContentCategory::SelfHarm,
+                ContentCategory::Violence,
+                ContentCategory::SubstanceUse
+            ]
+        );
+        // Phrases only match whole words.
+        assert!(classifier()
+            .lexicon_categories("Some pornography-free Essex news")
+            .is_empty());
+    }
+
+    #[test]
+    fn configured_keywords_extend_the_lexicon_and_disabling_skips_it() {
+        let mut config = ClassifyConfig {
+            keywords: BTreeMap::from([(ContentCategory::Death, vec!["wake".to_string()])]),
+            ..ClassifyConfig::default()
+        };
+        let text = "We went to his wake on Sunday.";
+        assert_eq!(
+            Classifier::from_config(&config).lexicon_categories(text),
+            [ContentCategory::Death]
+        );
+
+        config.enabled = false;
+        assert!(Classifier::from_config(&config)
+            .lexicon_categories(text)
+            .is_empty());
+    }
+
+    #[test]
+    fn parse_labels_keeps_known_keys_only() {
+        assert_eq!(
+            parse_labels("Labels: self_harm, `Violence`\n- abuse.\nnone, gore"),
+            [
+                ContentCategory::SelfHarm,
+                ContentCategory::Violence,
+                ContentCategory::Abuse
+            ]
+        );
+        assert!(parse_labels("none").is_empty());
+    }
+
+    #[test]
+    fn spoken_list_joins_names_like_speech() {
+        use ContentCategory::*;
+        assert_eq!(spoken_list(&[]), "");
+        assert_eq!(spoken_list(&[SelfHarm]), "self-harm");
+        assert_eq!(spoken_list(&[Abuse, Death]), "abuse and death");
+        assert_eq!(
+            spoken_list(&[Abuse, SubstanceUse, EatingDisorder]),
+            "abuse, drug or alcohol use and eating disorders"
+        );
+    }
+
+    #[test]
+    fn warning_text_names_only_warned_categories() {
+        let classifier = classifier();
+        assert_eq!(classifier.warning_text(&[ContentCategory::Death]), None);
+        assert_eq!(
+            classifier
+                .warning_text(&[
+                    ContentCategory::Abuse,
+                    ContentCategory::Death,
+                    ContentCategory::Violence
+                ])
+                .as_deref(),
+            Some("Content warning: this story mentions abuse and violence.")
+        );
+    }
+}
@@ -0,0 +1,157 @@
+use clap::{Parser, Subcommand, ValueEnum};
+use py_rs_project::experiment::Grouping;
+
+/// Generate and upload Reddit confession videos.
+///
+/// Every stage reads its inputs from the files written by the previous stage
+/// (by default inside `paths.outputs_folder`), so a single failing stage can be
+/// rerun on its own.
+#[derive(Debug, Parser)]
+#[command(version, about)]
+pub struct Cli {
+    /// Path to the TOML config file (defaults to `config.toml` when present)
+    #[arg(long, global = true)]
+    pub config: Option<String>,
+
+    /// Override a config key, e.g. `--set upload.privacy=unlisted` (repeatable)
+    #[arg(long = "set", value_name = "KEY=VALUE", global = true)]
+    pub overrides: Vec<String>,
+
+    /// Ask the LLM again instead of reusing cached replies (same as
+    /// `--set llm.cache_bypass=true`); the fresh replies are cached
+    #[arg(long, global = true)]
+    pub no_cache: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Pick a random confession long enough for a video and save it as JSON,
+    /// with its hook-first rewrite and outro when `rewrite` and `outro` are on
+    Pick {
+        /// Where to write the confession (defaults to `<outputs>/confession.json`)
+        #[arg(long)]
+        out: Option<String>,
+    },
+    /// Generate title, description and hashtags for a saved confession
+    Metadata {
+        /// Confession JSON written by `pick`
+        #[arg(long)]
+        confession: Option<String>,
+        /// Where to write the metadata (defaults to `<outputs>/metadata.json`)
+        #[arg(long)]
+        out: Option<String>,
+    },
+    /// Synthesize the voice-over for a saved confession
+    Tts {
+        /// Confession JSON written by `pick`
+        #[arg(long)]
+        confession: Option<String>,
+        /// Where to write the audio (defaults to `paths.audio_output`)
+        #[arg(long)]
+        out: Option<String>,
+    },
+    /// Render the captioned video from a confession and its voice-over
+    Render {
+        /// Confession JSON written by `pick`
+        #[arg(long)]
+        confession: Option<String>,
+        /// Voice-over written by `tts` (defaults to `paths.audio_output`)
+        #[arg(long)]
+        audio: Option<String>,
+        /// Where to write the video (defaults to `paths.video_output`)
+        #[arg(long)]
+        out: Option<String>,
+    },
+    /// Split a rendered video into parts no longer than `video.max_duration`
+    Split {
+        /// Video written by `render` (defaults to `paths.video_output`)
+        #[arg(long)]
+        video: Option<String>,
+        /// Directory for the parts (defaults to `paths.outputs_folder`)
+        #[arg(long)]
+        out_dir: Option<String>,
+    },
+    /// Upload an already rendered video or part
+    Upload {
+        /// Video to upload (defaults to `paths.video_output`)
+        #[arg(long)]
+        video: Option<String>,
+        /// Metadata JSON written by `metadata`
+        #[arg(long)]
+        metadata: Option<String>,
+        /// Upload as part N of a multi-part episode (requires `--total`)
+        #[arg(long, requires = "total")]
+        part: Option<usize>,
+        /// Total number of parts in the episode
+        #[arg(long, requires = "part")]
+        total: Option<usize>,
+        /// Episode number to use instead of the one in `paths.episode_file`
+        #[arg(long)]
+        episode: Option<u32>,
+        /// Advance the episode counter after a successful upload
+        #[arg(long)]
+        increment_episode: bool,
+    },
+    /// Run the whole pipeline once, resuming an unfinished job if there is one
+    /// (the default when no subcommand is given)
+    Run,
+    /// Continue an unfinished job from its last completed step
+    Resume {
+        /// Job id to resume (defaults to the oldest unfinished job)
+        #[arg(long)]
+        job: Option<String>,
+        /// Upload again parts that were interrupted mid-upload. Check the channel
+        /// first: the interrupted upload may already have gone through.
+        #[arg(long)]
+        retry_interrupted: bool,
+    },
+    /// List recorded jobs and their progress
+    Jobs,
+    /// Compare the variants of an experiment using the video stats in
+    /// `experiment.stats_path`
+    Report {
+        /// Experiment to report on (defaults to `experiment.name`)
+        #[arg(long, conflicts_with = "by")]
+        experiment: Option<String>,
+        /// Compare all videos grouped by mood, category or language instead
+        #[arg(long, value_enum)]
+        by: Option<ReportBy>,
+    },
+    /// Keep running, uploading videos at the `schedule` slots and rendering the
+    /// next ones ahead of time. Stops cleanly on Ctrl-C or SIGTERM.
+    Daemon,
+    /// Run the whole pipeline several times in a row
+    Batch {
+        /// Number of videos to produce
+        #[arg(long, default_value_t = 1)]
+        count: usize,
+    },
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum ReportBy {
+    Mood,
+    Category,
+    Language,
+}
+
+impl ReportBy {
+    pub fn grouping(self) -> Grouping {
+        match self {
+            ReportBy::Mood => Grouping::Mood,
+            ReportBy::Category => Grouping::Category,
+            ReportBy::Language => Grouping::Language,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ReportBy::Mood => "mood",
+            ReportBy::Category => "category",
+            ReportBy::Language => "language",
+        }
+    }
+}
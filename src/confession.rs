@@ -1,83 +1,191 @@
-use csv::ReaderBuilder;
-use rand::prelude::*;
-use std::error::Error;
-use std::fs::File;
-use std::io::BufReader;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+use crate::classifier::ContentCategory;
+use crate::config::ColumnsConfig;
+use crate::error::{Error, Result};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Confession {
     pub selftext: String,
     pub title: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_utc: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subreddit: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub permalink: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub over_18: Option<bool>,
+    /// Sensitive topics found by the [`classifier`](crate::classifier).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub content: Vec<ContentCategory>,
+    /// Hook-first retelling from the [`rewriter`](crate::rewriter), spoken and
+    /// captioned instead of the title and body.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub narration: Option<String>,
+    /// Closing reaction and call to action from the [`outro`](crate::outro)
+    /// stage, spoken after the story.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outro: Option<String>,
 }
 
-const CSV_PATH: &str = "data/inputs/confessions.csv";
-const NUM_SAMPLES: usize = 100;
-const APPROX_RECORDS_PER_SAMPLE: usize = 100_000;
-const RECORDS_TO_TAKE: usize = 10;
-
-fn is_valid_text(text: &str) -> bool {
-    !text.is_empty() && !text.contains("[removed]") && !text.contains("[deleted]")
-}
-
-fn extract_confession_from_record(record: &csv::StringRecord) -> Option<Confession> {
-    let selftext = record.get(9).unwrap_or("");
-    let title = record.get(10).unwrap_or("");
-
-    if is_valid_text(selftext) && is_valid_text(title) {
-        let selftext = selftext
-            .replace(r"\n", " ")
-            .replace("\n", " ")
-            .replace("\\", "")
-            .trim()
-            .to_string();
-        let title = title
-            .replace(r"\n", " ")
-            .replace("\n", " ")
-            .replace("\\", "")
-            .trim()
-            .to_string();
-
-        Some(Confession {
-            selftext: selftext.to_string(),
-            title: title.to_string(),
-        })
-    } else {
-        None
+impl Confession {
+    /// The raw title followed by the body. What is actually spoken and captioned
+    /// is this after the [`Normalizer`](crate::normalizer::Normalizer) pass.
+    pub fn script(&self) -> String {
+        format!("{} {}", self.title, self.selftext)
     }
 }
 
-pub fn read_random_valid_confession() -> Result<Confession, Box<dyn Error>> {
-    let mut rng = rand::rng();
+/// Every field a source can provide, by its key in [`ColumnsConfig`]. The first
+/// two are required.
+pub(crate) const FIELDS: [&str; 9] = [
+    "selftext",
+    "title",
+    "id",
+    "author",
+    "score",
+    "created_utc",
+    "subreddit",
+    "permalink",
+    "over_18",
+];
+
+/// Bump when the rules deciding which records are usable change, so existing
+/// CSV indexes are rebuilt.
+const INDEX_VERSION: u64 = 3;
 
-    for _ in 0..NUM_SAMPLES {
-        let records_to_skip = rng.random_range(0..APPROX_RECORDS_PER_SAMPLE);
+/// Column positions of [`FIELDS`], resolved once from a header row or table schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ColumnMap {
+    positions: [Option<usize>; FIELDS.len()],
+}
 
-        let file = File::open(CSV_PATH)?;
-        let buf_reader = BufReader::new(file);
-        let mut rdr_sample = ReaderBuilder::new()
-            .has_headers(true)
-            .from_reader(buf_reader);
+impl ColumnMap {
+    /// Matches column names against the configured aliases, case-insensitively and
+    /// in alias order. `selftext` and `title` are required; the rest are optional.
+    pub(crate) fn resolve<S: AsRef<str>>(names: &[S], columns: &ColumnsConfig) -> Result<Self> {
+        let mut positions = [None; FIELDS.len()];
+        for (slot, field) in positions.iter_mut().zip(FIELDS) {
+            *slot = columns.aliases(field).iter().find_map(|alias| {
+                names
+                    .iter()
+                    .position(|name| name.as_ref().trim().eq_ignore_ascii_case(alias.trim()))
+            });
+        }
 
-        for _ in 0..records_to_skip {
-            if rdr_sample.records().next().is_none() {
-                return Err("No valid confession found (reached EOF while skipping)".into());
+        for (position, field) in positions.iter().zip(FIELDS).take(2) {
+            if position.is_none() {
+                return Err(Error::Source(format!(
+                    "No {} column found (tried {}; columns are {})",
+                    field,
+                    columns.aliases(field).join(", "),
+                    names
+                        .iter()
+                        .map(|name| name.as_ref())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )));
             }
         }
 
-        for result in rdr_sample.records().take(RECORDS_TO_TAKE) {
-            match result {
-                Ok(record) => {
-                    if let Some(confession) = extract_confession_from_record(&record) {
-                        return Ok(confession);
-                    }
-                }
-                Err(e) => {
-                    eprintln!("CSV parsing error: {}", e);
-                    continue;
-                }
+        Ok(Self { positions })
+    }
+
+    pub(crate) fn position(&self, field: &str) -> Option<usize> {
+        FIELDS
+            .iter()
+            .position(|candidate| *candidate == field)
+            .and_then(|i| self.positions[i])
+    }
+
+    /// Identifies this mapping in the CSV index, so changing which columns are
+    /// read rebuilds it. `extra` covers other settings that affect usability.
+    pub(crate) fn fingerprint(&self, extra: &[String]) -> u64 {
+        // FNV-1a, which unlike `DefaultHasher` is stable across Rust releases.
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325 ^ INDEX_VERSION;
+        let mut feed = |bytes: &[u8]| {
+            for byte in bytes {
+                hash ^= *byte as u64;
+                hash = hash.wrapping_mul(0x0100_0000_01b3);
             }
+        };
+        for position in self.positions {
+            feed(&position.map_or(u64::MAX, |p| p as u64).to_le_bytes());
+        }
+        for value in extra {
+            feed(value.as_bytes());
+            feed(&[0]);
         }
+        hash
+    }
+}
+
+pub(crate) fn is_valid_text(text: &str) -> bool {
+    !text.is_empty() && !text.contains("[removed]") && !text.contains("[deleted]")
+}
+
+/// Turns escaped `\n` sequences into real line breaks and drops stray
+/// backslashes. Line breaks are kept for the normalizer's Markdown pass.
+pub(crate) fn clean_text(text: &str) -> String {
+    text.replace(r"\n", "\n")
+        .replace("\\", "")
+        .trim()
+        .to_string()
+}
+
+/// Dumps store booleans as `True`/`true`/`1` and numbers sometimes as `12.0`.
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "true" | "1" | "yes" => Some(true),
+        "false" | "0" | "no" => Some(false),
+        _ => None,
+    }
+}
+
+fn parse_int(value: &str) -> Option<i64> {
+    value
+        .parse::<i64>()
+        .ok()
+        .or_else(|| value.parse::<f64>().ok().map(|f| f as i64))
+}
+
+/// Builds a confession from raw source values, where `get` returns the value of
+/// one of [`FIELDS`]. Returns `None` when the title or body is missing or removed.
+pub(crate) fn confession_from_fields<F>(get: F) -> Option<Confession>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let text = |field| {
+        get(field)
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+
+    let selftext = text("selftext").unwrap_or_default();
+    let title = text("title").unwrap_or_default();
+    if !is_valid_text(&selftext) || !is_valid_text(&title) {
+        return None;
     }
 
-    Err("No valid confession found after sampling".into())
+    Some(Confession {
+        selftext: clean_text(&selftext),
+        title: clean_text(&title),
+        id: text("id"),
+        author: text("author"),
+        score: text("score").as_deref().and_then(parse_int),
+        created_utc: text("created_utc").as_deref().and_then(parse_int),
+        subreddit: text("subreddit"),
+        permalink: text("permalink"),
+        over_18: text("over_18").as_deref().and_then(parse_bool),
+        content: Vec::new(),
+        narration: None,
+        outro: None,
+    })
 }
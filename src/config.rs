@@ -0,0 +1,1470 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use crate::classifier::ContentCategory;
+use crate::localize;
+use crate::metadata::{Mood, StoryCategory};
+
+pub const DEFAULT_CONFIG_PATH: &str = "config.toml";
+const ENV_PREFIX: &str = "BRAINROT_";
+const ENV_SECTION_SEPARATOR: &str = "__";
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(String),
+    Parse(String),
+    Override(String),
+    Invalid(Vec<String>),
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(msg) => write!(f, "Config IO error: {msg}"),
+            Self::Parse(msg) => write!(f, "Config parse error: {msg}"),
+            Self::Override(msg) => write!(f, "Invalid config override: {msg}"),
+            Self::Invalid(problems) => {
+                writeln!(f, "Invalid configuration:")?;
+                for problem in problems {
+                    writeln!(f, "  - {problem}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub debug: bool,
+    pub paths: PathsConfig,
+    pub source: SourceConfig,
+    pub rules: RulesConfig,
+    pub normalize: NormalizeConfig,
+    pub profanity: ProfanityConfig,
+    pub classify: ClassifyConfig,
+    pub validate: ValidateConfig,
+    pub prompts: PromptsConfig,
+    pub rewrite: RewriteConfig,
+    pub outro: OutroConfig,
+    pub localize: LocalizeConfig,
+    pub style: StyleConfig,
+    pub experiment: ExperimentConfig,
+    pub audio: AudioConfig,
+    pub video: VideoConfig,
+    pub upload: UploadConfig,
+    pub llm: LlmConfig,
+    pub notify: NotifyConfig,
+    pub schedule: ScheduleConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PathsConfig {
+    pub outputs_folder: String,
+    pub audio_output: String,
+    pub video_input: String,
+    pub video_output: String,
+    pub episode_file: String,
+    pub jobs_folder: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SourceConfig {
+    pub kind: SourceKind,
+    pub csv_path: String,
+    /// Where the byte-offset index of `csv_path` is kept (defaults to `<csv_path>.idx`).
+    pub index_path: Option<String>,
+    /// Pushshift-style submission dumps, one JSON object per line; `.zst` files
+    /// are decompressed on the fly.
+    pub ndjson_paths: Vec<String>,
+    /// Folder of hand-written `.txt`/`.md` scripts: first line is the title.
+    pub directory: String,
+    pub sqlite_path: String,
+    pub sqlite_table: String,
+    /// Only use records from these subreddits (case-insensitive); empty means all.
+    pub subreddits: Vec<String>,
+    /// Confessions already turned into videos, which are never picked again.
+    pub ledger_path: String,
+    /// Reject confessions at least this similar to a used one (0-1); above 1
+    /// disables near-duplicate detection.
+    pub near_duplicate_threshold: f32,
+    pub columns: ColumnsConfig,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SourceKind {
+    Csv,
+    Ndjson,
+    Directory,
+    Sqlite,
+}
+
+/// Header names tried, in order, for each confession field. Matching ignores case.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ColumnsConfig {
+    pub selftext: Vec<String>,
+    pub title: Vec<String>,
+    pub id: Vec<String>,
+    pub author: Vec<String>,
+    pub score: Vec<String>,
+    pub created_utc: Vec<String>,
+    pub subreddit: Vec<String>,
+    pub permalink: Vec<String>,
+    pub over_18: Vec<String>,
+}
+
+/// Which confessions are acceptable, and how to choose among them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RulesConfig {
+    pub min_words: Option<usize>,
+    pub max_words: Option<usize>,
+    pub min_score: Option<i64>,
+    /// Reject confessions without a score when `min_score` is set.
+    pub require_score: bool,
+    pub allow_nsfw: bool,
+    /// ISO 639-3 codes such as `eng`; empty allows any language.
+    pub languages: Vec<String>,
+    /// Case-insensitive substrings that reject a confession.
+    pub banned_keywords: Vec<String>,
+    /// Case-insensitive regular expressions that reject a confession.
+    pub banned_patterns: Vec<String>,
+    /// Most links per word before a confession reads like spam.
+    pub max_url_density: f32,
+    /// Most capital letters as a share of all letters.
+    pub max_caps_ratio: f32,
+    /// Reject confessions containing one of `edit_markers`; off by default since
+    /// many good posts end with an edit note.
+    pub reject_edit_markers: bool,
+    pub edit_markers: Vec<String>,
+    pub selection: Selection,
+    /// Passing confessions drawn per pick in `ranked` selection.
+    pub candidates: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Selection {
+    /// Every passing confession is equally likely.
+    Uniform,
+    /// Draw `candidates` passing confessions and keep the highest scored.
+    Ranked,
+}
+
+/// How raw post text is turned into the spoken and captioned script.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NormalizeConfig {
+    /// When off, the text is only whitespace-collapsed.
+    pub enabled: bool,
+    pub remove_emoji: bool,
+    pub expand_abbreviations: bool,
+    pub verbalize_numbers: bool,
+    /// Extra or overriding expansions, matched case-insensitively.
+    pub abbreviations: BTreeMap<String, String>,
+}
+
+/// Per-channel profanity handling for each place confession text ends up.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProfanityConfig {
+    pub level: Strictness,
+    /// `allow` or `reject` confessions containing profanity.
+    pub confession: FilterAction,
+    /// `allow`, `mask` or `substitute` in the burned-in captions.
+    pub captions: FilterAction,
+    /// `allow`, `mask` or `substitute` in the spoken script.
+    pub speech: FilterAction,
+    /// `allow`, `mask` or `reject` generated titles, descriptions and tags.
+    pub metadata: FilterAction,
+    /// Extra words per tier; each also matches common suffixes (`-s`, `-ing`, ...).
+    pub mild_words: Vec<String>,
+    pub moderate_words: Vec<String>,
+    pub severe_words: Vec<String>,
+    /// Case-insensitive whole words or phrases that reject a confession at any level.
+    pub blocked_terms: Vec<String>,
+    /// Extra or overriding softer words for the spoken script.
+    pub substitutions: BTreeMap<String, String>,
+}
+
+/// Which tiers of the word lists count as profanity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Strictness {
+    Off,
+    /// Severe words only.
+    Lenient,
+    /// Moderate and severe words.
+    Moderate,
+    /// Every listed word, mild ones included.
+    Strict,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterAction {
+    Allow,
+    /// Keep the first letter: `f***`.
+    Mask,
+    /// Replace with a softer word: `frick`.
+    Substitute,
+    Reject,
+}
+
+/// Content-warning classification of confessions.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClassifyConfig {
+    pub enabled: bool,
+    /// Also ask `llm.model` for categories, on top of the keyword lexicon.
+    pub use_llm: bool,
+    /// Categories that reject a confession outright.
+    pub block: Vec<ContentCategory>,
+    /// Categories announced by a spoken and captioned content warning.
+    pub warn: Vec<ContentCategory>,
+    /// Categories that ask for an age restriction on upload.
+    pub age_restrict: Vec<ContentCategory>,
+    /// The warning read before the story; `{topics}` lists the categories.
+    pub warning: String,
+    /// Extra lexicon phrases per category.
+    pub keywords: BTreeMap<ContentCategory, Vec<String>>,
+}
+
+/// What generated metadata must look like; replies that fail are asked again.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ValidateConfig {
+    /// ISO 639-3 code the description and hook must be written in; empty skips
+    /// the check.
+    pub language: String,
+    /// Hashtags every video must carry, without the `#`.
+    pub required_hashtags: Vec<String>,
+    pub max_description_words: usize,
+    pub max_hook_words: usize,
+    /// Extra case-insensitive phrases that mark a refusal.
+    pub refusal_phrases: Vec<String>,
+}
+
+/// Which prompt templates to use, and the values they are filled in with.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PromptsConfig {
+    /// Folder of `<name>.toml` templates; names not found there use the built-in ones.
+    pub directory: String,
+    /// Template for the upload metadata.
+    pub metadata: String,
+    /// Template for LLM content-warning labels.
+    pub classify: String,
+    /// Template for the hook-first narration.
+    pub rewrite: String,
+    /// Template for the closing reaction.
+    pub outro: String,
+    /// Template for translating the narration into a `[[localize.languages]]` language.
+    pub translate: String,
+    /// Template for translating the upload metadata.
+    pub translate_metadata: String,
+    /// Fills `{{persona}}`: whose point of view the metadata is written from.
+    pub persona: String,
+    /// Fills `{{max_chars}}`: how long a generated title may be.
+    pub title_chars: usize,
+}
+
+/// Narrating an LLM rewrite of the confession that opens with a hook, instead
+/// of the post as written.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RewriteConfig {
+    pub enabled: bool,
+    /// Seconds the voice-over should run at most, content warning and outro
+    /// included; sets the narration's word budget.
+    pub target_duration: f32,
+    /// Seconds the opening sentence may take.
+    pub hook_seconds: f32,
+    /// Share (0-1) of the rewrite's content words that must come from the
+    /// confession; below it the rewrite is taken to invent content.
+    pub min_overlap: f32,
+}
+
+/// A closing segment spoken after the story, so videos do not end abruptly.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OutroConfig {
+    pub enabled: bool,
+    /// Start with a generated reaction to the story; otherwise only the call to
+    /// action is spoken.
+    pub reaction: bool,
+    /// Calls to action, one picked at random per video and spoken as written.
+    pub cta: Vec<String>,
+    /// Seconds the whole outro may take.
+    pub max_seconds: f32,
+    /// Captioned between the story and the outro; empty for none.
+    pub caption_marker: String,
+}
+
+/// Producing every video again in other languages. Once a job has its
+/// confession and metadata, one more job per entry of `languages` translates
+/// them, narrates them in that language and uploads to that language's target.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LocalizeConfig {
+    pub enabled: bool,
+    pub languages: Vec<LanguageConfig>,
+}
+
+/// One target language. Unset fields fall back to the built-in voice for the
+/// language or to the rest of the config.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LanguageConfig {
+    /// ISO 639-3 code, e.g. `spa`; the translation is checked against it.
+    pub code: String,
+    /// Fills `{{language}}` in the translation prompts; defaults to the English
+    /// name of the language.
+    pub name: Option<String>,
+    /// Voice and Kokoro language code, as in `[audio]`.
+    pub voice: Option<String>,
+    pub lang_code: Option<String>,
+    /// Replaces `validate.required_hashtags`.
+    pub required_hashtags: Option<Vec<String>>,
+    /// Replaces `upload.series_title` and `upload.part_label`.
+    pub series_title: Option<String>,
+    pub part_label: Option<String>,
+    /// Upload target, replacing the `[upload]` settings of the same name.
+    pub privacy: Option<String>,
+    pub playlist_id: Option<String>,
+    pub credentials: Option<String>,
+    /// Episode counter of this language's channel; defaults to `paths.episode_file`
+    /// with the code appended, e.g. `data/current_episode_spa.json`.
+    pub episode_file: Option<String>,
+}
+
+/// Styling each video by the mood and category the metadata gives its
+/// confession. A mood's pool is tried before the category's, per kind of
+/// asset; anything neither pool has keeps the rest of the config.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StyleConfig {
+    pub enabled: bool,
+    pub moods: BTreeMap<Mood, StylePool>,
+    pub categories: BTreeMap<StoryCategory, StylePool>,
+}
+
+/// Assets for one mood or category; one of each list is picked at random.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StylePool {
+    /// Added to the generated hashtags, without the `#`.
+    pub hashtags: Vec<String>,
+    /// Background clips, replacing `paths.video_input`.
+    pub backgrounds: Vec<String>,
+    /// Music beds, replacing `video.music`.
+    pub music: Vec<String>,
+    /// Kokoro voices, replacing `audio.voice`.
+    pub voices: Vec<String>,
+}
+
+/// An A/B test: every new job is assigned one of `variants`, which overrides
+/// parts of this config for that job.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExperimentConfig {
+    pub enabled: bool,
+    /// Recorded with each assignment; the report only compares jobs of one name.
+    pub name: String,
+    pub assignment: AssignmentMode,
+    /// Video performance exported from YouTube Studio or kept by hand, one row
+    /// per video id.
+    pub stats_path: String,
+    pub variants: Vec<VariantConfig>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AssignmentMode {
+    /// Each job draws a variant at random in proportion to its weight.
+    Weighted,
+    /// Jobs cycle through the variants, each taking `weight` turns in a row.
+    RoundRobin,
+}
+
+/// One arm of an experiment. Unset fields keep the value from the rest of the config.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VariantConfig {
+    pub name: String,
+    /// Relative share of jobs; 0 pauses the variant.
+    #[serde(default = "default_weight")]
+    pub weight: u32,
+    /// Template for the title, description and the rest of the metadata,
+    /// replacing `prompts.metadata`.
+    pub metadata_prompt: Option<String>,
+    pub persona: Option<String>,
+    pub voice: Option<String>,
+    /// Caption style.
+    pub font_size: Option<i32>,
+    pub bg_color: Option<String>,
+}
+
+fn default_weight() -> u32 {
+    1
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AudioConfig {
+    pub engine: TtsEngineKind,
+    /// Voice name for Kokoro and espeak-ng, `.onnx` model file for Piper.
+    pub voice: Option<String>,
+    /// Kokoro language code, e.g. `a` for American English.
+    pub lang_code: Option<String>,
+    /// Interpreter running `kokoro_script`.
+    pub python: String,
+    pub kokoro_script: String,
+    /// Longest wait for the Kokoro worker to narrate one text; a worker that
+    /// takes longer is restarted.
+    pub timeout_secs: u64,
+    /// Piper or espeak-ng executable; `piper` or `espeak-ng` when unset.
+    pub binary: Option<String>,
+    /// WAV file the silent engine copies; without one it writes silence as long
+    /// as the text would take to speak.
+    pub fixture_path: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TtsEngineKind {
+    /// `kokoro_script`, kept running as a worker between requests.
+    Kokoro,
+    Piper,
+    Espeak,
+    /// No speech, for tests and offline runs.
+    Silent,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct VideoConfig {
+    pub font_size: Option<i32>,
+    pub bg_color: Option<String>,
+    pub max_duration: f32,
+    pub min_duration: f32,
+    /// Music mixed under the voice-over.
+    pub music: Option<String>,
+    /// Loudness of the music relative to the voice-over, 0-1.
+    pub music_volume: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UploadConfig {
+    pub category: String,
+    pub privacy: String,
+    pub playlist_id: String,
+    pub max_title_length: usize,
+    /// Title prefix, followed by the episode number.
+    pub series_title: String,
+    /// Labels the part number in titles of split videos.
+    pub part_label: String,
+    /// OAuth token file of the channel to upload to; the upload script's default
+    /// when unset.
+    pub credentials: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LlmConfig {
+    pub backend: LlmBackendKind,
+    pub model: String,
+    pub max_retries: usize,
+    /// Ollama server, for the `ollama` backend.
+    pub host: String,
+    pub port: u16,
+    /// API root such as `http://localhost:8080/v1`, for the `openai` backend.
+    pub base_url: String,
+    /// Sent as a bearer token by the `openai` backend; prefer setting it with
+    /// `BRAINROT_LLM__API_KEY` over writing it into a config file.
+    pub api_key: Option<String>,
+    /// Longest wait for one reply, for either backend.
+    pub timeout_secs: u64,
+    pub temperature: Option<f32>,
+    pub seed: Option<i32>,
+    pub max_tokens: Option<u32>,
+    /// Context window in tokens, for the `ollama` backend.
+    pub context_size: Option<u32>,
+    /// JSON array of `{"match": ..., "response": ...}` answers, for the `mock` backend.
+    pub fixtures_path: Option<String>,
+    /// What the `mock` backend answers when no fixture matches.
+    pub mock_response: String,
+    /// Reuse replies to identical requests from `cache_dir` (never for `mock`).
+    pub cache: bool,
+    pub cache_dir: String,
+    /// Older entries are asked again; 0 keeps them forever.
+    pub cache_ttl_hours: u64,
+    /// Ask the model even when a cached reply exists; the new reply replaces it.
+    pub cache_bypass: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LlmBackendKind {
+    Ollama,
+    /// Any OpenAI-compatible chat completions endpoint.
+    Openai,
+    /// Canned answers from `fixtures_path`, for tests and offline runs.
+    Mock,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NotifyConfig {
+    pub url: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScheduleConfig {
+    /// IANA timezone the slots are expressed in, e.g. `America/New_York`.
+    pub timezone: String,
+    /// Local upload times as `HH:MM`. When empty, `videos_per_day` slots are
+    /// spread evenly over the day starting at midnight.
+    pub slots: Vec<String>,
+    pub videos_per_day: usize,
+    /// How many rendered videos to keep ready ahead of their upload slot.
+    pub queue_size: usize,
+}
+
+impl Default for PathsConfig {
+    fn default() -> Self {
+        Self {
+            outputs_folder: "data/output".to_string(),
+            audio_output: "data/output/output.wav".to_string(),
+            video_input: "data/inputs/input_3.mp4".to_string(),
+            video_output: "data/output/output.mp4".to_string(),
+            episode_file: "data/current_episode.json".to_string(),
+            jobs_folder: "data/jobs".to_string(),
+        }
+    }
+}
+
+impl Default for SourceConfig {
+    fn default() -> Self {
+        Self {
+            kind: SourceKind::Csv,
+            csv_path: "data/inputs/confessions.csv".to_string(),
+            index_path: None,
+            ndjson_paths: Vec::new(),
+            directory: "data/inputs/scripts".to_string(),
+            sqlite_path: "data/inputs/confessions.db".to_string(),
+            sqlite_table: "submissions".to_string(),
+            subreddits: Vec::new(),
+            ledger_path: "data/used_confessions.json".to_string(),
+            near_duplicate_threshold: 0.8,
+            columns: ColumnsConfig::default(),
+        }
+    }
+}
+
+impl Default for ColumnsConfig {
+    fn default() -> Self {
+        let aliases = |names: &[&str]| names.iter().map(|name| name.to_string()).collect();
+        Self {
+            selftext: aliases(&["selftext", "body", "text"]),
+            title: aliases(&["title"]),
+            id: aliases(&["id", "name"]),
+            author: aliases(&["author"]),
+            score: aliases(&["score", "ups"]),
+            created_utc: aliases(&["created_utc", "created"]),
+            subreddit: aliases(&["subreddit"]),
+            permalink: aliases(&["permalink", "url"]),
+            over_18: aliases(&["over_18", "nsfw"]),
+        }
+    }
+}
+
+impl Default for RulesConfig {
+    fn default() -> Self {
+        Self {
+            min_words: None,
+            max_words: None,
+            min_score: None,
+            require_score: false,
+            allow_nsfw: true,
+            languages: Vec::new(),
+            banned_keywords: Vec::new(),
+            banned_patterns: Vec::new(),
+            max_url_density: 0.05,
+            max_caps_ratio: 0.5,
+            reject_edit_markers: false,
+            edit_markers: vec![
+                "EDIT:".to_string(),
+                "UPDATE:".to_string(),
+                "ETA:".to_string(),
+            ],
+            selection: Selection::Uniform,
+            candidates: 5,
+        }
+    }
+}
+
+impl Default for NormalizeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            remove_emoji: true,
+            expand_abbreviations: true,
+            verbalize_numbers: true,
+            abbreviations: BTreeMap::new(),
+        }
+    }
+}
+
+impl Default for ProfanityConfig {
+    fn default() -> Self {
+        Self {
+            level: Strictness::Moderate,
+            confession: FilterAction::Allow,
+            captions: FilterAction::Mask,
+            speech: FilterAction::Substitute,
+            metadata: FilterAction::Mask,
+            mild_words: Vec::new(),
+            moderate_words: Vec::new(),
+            severe_words: Vec::new(),
+            blocked_terms: Vec::new(),
+            substitutions: BTreeMap::new(),
+        }
+    }
+}
+
+impl Default for ClassifyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            use_llm: false,
+            block: Vec::new(),
+            warn: vec![
+                ContentCategory::SelfHarm,
+                ContentCategory::Abuse,
+                ContentCategory::SexualContent,
+                ContentCategory::Violence,
+                ContentCategory::SubstanceUse,
+                ContentCategory::EatingDisorder,
+            ],
+            age_restrict: vec![ContentCategory::SelfHarm, ContentCategory::SexualContent],
+            warning: "Content warning: this story mentions {topics}.".to_string(),
+            keywords: BTreeMap::new(),
+        }
+    }
+}
+
+impl Default for ValidateConfig {
+    fn default() -> Self {
+        Self {
+            language: "eng".to_string(),
+            required_hashtags: vec!["shorts".to_string(), "redditconfessions".to_string()],
+            max_description_words: 40,
+            max_hook_words: 30,
+            refusal_phrases: Vec::new(),
+        }
+    }
+}
+
+impl Default for PromptsConfig {
+    fn default() -> Self {
+        Self {
+            directory: "prompts".to_string(),
+            metadata: "metadata".to_string(),
+            classify: "classify".to_string(),
+            rewrite: "rewrite".to_string(),
+            outro: "outro".to_string(),
+            translate: "translate".to_string(),
+            translate_metadata: "translate_metadata".to_string(),
+            persona: "a naive female college student with no prior knowledge of the topic"
+                .to_string(),
+            title_chars: 50,
+        }
+    }
+}
+
+impl Default for RewriteConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            target_duration: 54.0,
+            hook_seconds: 2.0,
+            min_overlap: 0.7,
+        }
+    }
+}
+
+impl Default for OutroConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            reaction: true,
+            cta: vec![
+                "Comment what you would have done.".to_string(),
+                "Follow for more confessions.".to_string(),
+            ],
+            max_seconds: 6.0,
+            caption_marker: "—".to_string(),
+        }
+    }
+}
+
+impl Default for ExperimentConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            name: "default".to_string(),
+            assignment: AssignmentMode::Weighted,
+            stats_path: "data/stats.csv".to_string(),
+            variants: Vec::new(),
+        }
+    }
+}
+
+impl SourceConfig {
+    pub fn accepts_subreddit(&self, subreddit: Option<&str>) -> bool {
+        self.subreddits.is_empty()
+            || subreddit.is_some_and(|name| {
+                self.subreddits
+                    .iter()
+                    .any(|allowed| allowed.trim().eq_ignore_ascii_case(name.trim()))
+            })
+    }
+}
+
+impl ColumnsConfig {
+    /// The header names configured for `field`, e.g. `"selftext"`.
+    pub fn aliases(&self, field: &str) -> &[String] {
+        match field {
+            "selftext" => &self.selftext,
+            "title" => &self.title,
+            "id" => &self.id,
+            "author" => &self.author,
+            "score" => &self.score,
+            "created_utc" => &self.created_utc,
+            "subreddit" => &self.subreddit,
+            "permalink" => &self.permalink,
+            "over_18" => &self.over_18,
+            _ => &[],
+        }
+    }
+}
+
+impl Default for AudioConfig {
+    fn default() -> Self {
+        Self {
+            engine: TtsEngineKind::Kokoro,
+            voice: Some("af_bella".to_string()),
+            lang_code: Some("a".to_string()),
+            python: "python".to_string(),
+            kokoro_script: "src/tts_generator.py".to_string(),
+            timeout_secs: 300,
+            binary: None,
+            fixture_path: None,
+        }
+    }
+}
+
+impl Default for VideoConfig {
+    fn default() -> Self {
+        Self {
+            font_size: Some(48),
+            bg_color: Some("white".to_string()),
+            max_duration: 60.0,
+            min_duration: 50.0,
+            music: None,
+            music_volume: 0.15,
+        }
+    }
+}
+
+impl Default for UploadConfig {
+    fn default() -> Self {
+        Self {
+            category: "22".to_string(),
+            privacy: "public".to_string(),
+            playlist_id: "PLO-PREE1cmUlkCDaXmkM5WquyKRWEqjJc".to_string(),
+            max_title_length: 100,
+            series_title: "Reddit Confessions".to_string(),
+            part_label: "Part".to_string(),
+            credentials: None,
+        }
+    }
+}
+
+impl Default for LlmConfig {
+    fn default() -> Self {
+        Self {
+            backend: LlmBackendKind::Ollama,
+            model: "artifish/llama3.2-uncensored".to_string(),
+            max_retries: 5,
+            host: "http://localhost".to_string(),
+            port: 11434,
+            base_url: "http://localhost:8080/v1".to_string(),
+            api_key: None,
+            timeout_secs: 120,
+            temperature: None,
+            seed: None,
+            max_tokens: None,
+            context_size: None,
+            fixtures_path: None,
+            mock_response: "Mock response".to_string(),
+            cache: true,
+            cache_dir: "data/llm_cache".to_string(),
+            cache_ttl_hours: 168,
+            cache_bypass: false,
+        }
+    }
+}
+
+impl Default for NotifyConfig {
+    fn default() -> Self {
+        Self {
+            url: "http://127.0.0.1:8080/notify".to_string(),
+        }
+    }
+}
+
+impl Default for ScheduleConfig {
+    fn default() -> Self {
+        Self {
+            timezone: "UTC".to_string(),
+            slots: vec![
+                "09:00".to_string(),
+                "15:00".to_string(),
+                "21:00".to_string(),
+            ],
+            videos_per_day: 3,
+            queue_size: 1,
+        }
+    }
+}
+
+impl Config {
+    /// Builds the configuration in layers: built-in defaults, then the TOML
+    /// file, then `BRAINROT_SECTION__KEY` environment variables, then
+    /// `section.key=value` overrides from the command line.
+    ///
+    /// An explicitly given `path` must exist; the default `config.toml` is optional.
+    pub fn load(path: Option<&str>, overrides: &[String]) -> Result<Self, ConfigError> {
+        let mut layered = toml::Value::try_from(Config::default())
+            .map_err(|e| ConfigError::Parse(e.to_string()))?;
+
+        let file_path = path.unwrap_or(DEFAULT_CONFIG_PATH);
+        if path.is_some() || Path::new(file_path).exists() {
+            let content = fs::read_to_string(file_path)
+                .map_err(|e| ConfigError::Io(format!("{file_path}: {e}")))?;
+            let file_value: toml::Value = toml::from_str(&content)
+                .map_err(|e| ConfigError::Parse(format!("{file_path}: {e}")))?;
+            merge_values(&mut layered, file_value);
+        }
+
+        for (key, value) in std::env::vars() {
+            if let Some(stripped) = key.strip_prefix(ENV_PREFIX) {
+                let dotted = stripped.to_lowercase().replace(ENV_SECTION_SEPARATOR, ".");
+                apply_override(&mut layered, &dotted, &value)?;
+            }
+        }
+
+        for assignment in overrides {
+            let (key, value) = assignment.split_once('=').ok_or_else(|| {
+                ConfigError::Override(format!("expected key=value, got '{assignment}'"))
+            })?;
+            apply_override(&mut layered, key.trim(), value.trim())?;
+        }
+
+        let config: Config = layered
+            .try_into()
+            .map_err(|e: toml::de::Error| ConfigError::Parse(e.to_string()))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Collects every problem at once so a bad config file can be fixed in one pass.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let mut problems = Vec::new();
+
+        if self.paths.outputs_folder.trim().is_empty() {
+            problems.push("paths.outputs_folder must not be empty".to_string());
+        }
+        if self.paths.jobs_folder.trim().is_empty() {
+            problems.push("paths.jobs_folder must not be empty".to_string());
+        }
+        if self.source.columns.selftext.is_empty() {
+            problems.push("source.columns.selftext needs at least one header name".to_string());
+        }
+        if self.source.columns.title.is_empty() {
+            problems.push("source.columns.title needs at least one header name".to_string());
+        }
+        match self.source.kind {
+            SourceKind::Csv if self.source.csv_path.trim().is_empty() => {
+                problems.push("source.csv_path must not be empty".to_string());
+            }
+            SourceKind::Ndjson if self.source.ndjson_paths.is_empty() => {
+                problems.push("source.ndjson_paths needs at least one file".to_string());
+            }
+            SourceKind::Directory if self.source.directory.trim().is_empty() => {
+                problems.push("source.directory must not be empty".to_string());
+            }
+            SourceKind::Sqlite if self.source.sqlite_table.trim().is_empty() => {
+                problems.push("source.sqlite_table must not be empty".to_string());
+            }
+            _ => {}
+        }
+        if self.source.ledger_path.trim().is_empty() {
+            problems.push("source.ledger_path must not be empty".to_string());
+        }
+        if self.source.near_duplicate_threshold <= 0.0 {
+            problems.push("source.near_duplicate_threshold must be greater than 0".to_string());
+        }
+        if let (Some(min), Some(max)) = (self.rules.min_words, self.rules.max_words) {
+            if min > max {
+                problems.push(format!(
+                    "rules.min_words ({min}) must not exceed rules.max_words ({max})"
+                ));
+            }
+        }
+        for pattern in &self.rules.banned_patterns {
+            if let Err(e) = regex::Regex::new(pattern) {
+                problems.push(format!(
+                    "rules.banned_patterns entry /{pattern}/ is invalid: {e}"
+                ));
+            }
+        }
+        if !(0.0..=1.0).contains(&self.rules.max_caps_ratio) {
+            problems.push("rules.max_caps_ratio must be between 0 and 1".to_string());
+        }
+        if self.rules.max_url_density < 0.0 {
+            problems.push("rules.max_url_density must not be negative".to_string());
+        }
+        if self.rules.selection == Selection::Ranked && self.rules.candidates == 0 {
+            problems.push("rules.candidates must be at least 1 for ranked selection".to_string());
+        }
+        use FilterAction::{Allow, Mask, Reject, Substitute};
+        let surfaces = [
+            (
+                "confession",
+                self.profanity.confession,
+                [Allow, Reject].as_slice(),
+            ),
+            (
+                "captions",
+                self.profanity.captions,
+                &[Allow, Mask, Substitute],
+            ),
+            ("speech", self.profanity.speech, &[Allow, Mask, Substitute]),
+            ("metadata", self.profanity.metadata, &[Allow, Mask, Reject]),
+        ];
+        for (surface, action, allowed) in surfaces {
+            if !allowed.contains(&action) {
+                problems.push(format!(
+                    "profanity.{surface} must be one of {} (got {})",
+                    allowed
+                        .iter()
+                        .map(|a| format!("{a:?}").to_lowercase())
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    format!("{action:?}").to_lowercase()
+                ));
+            }
+        }
+        let language = self.validate.language.trim();
+        if !language.is_empty() && whatlang::Lang::from_code(language).is_none() {
+            problems.push(format!(
+                "validate.language '{language}' is not an ISO 639-3 code known to the detector"
+            ));
+        }
+        if self.validate.max_description_words == 0 || self.validate.max_hook_words == 0 {
+            problems.push(
+                "validate.max_description_words and validate.max_hook_words must be at least 1"
+                    .to_string(),
+            );
+        }
+        if [
+            &self.prompts.metadata,
+            &self.prompts.classify,
+            &self.prompts.rewrite,
+            &self.prompts.outro,
+            &self.prompts.translate,
+            &self.prompts.translate_metadata,
+        ]
+        .iter()
+        .any(|name| name.trim().is_empty())
+        {
+            problems.push(
+                "prompts.metadata, prompts.classify, prompts.rewrite, prompts.outro, \
+                 prompts.translate and prompts.translate_metadata must name a template"
+                    .to_string(),
+            );
+        }
+        if self.outro.enabled {
+            if self.outro.max_seconds <= 0.0 {
+                problems.push("outro.max_seconds must be greater than 0".to_string());
+            }
+            if !self.outro.reaction && self.outro.cta.iter().all(|cta| cta.trim().is_empty()) {
+                problems.push("outro needs a reaction or at least one outro.cta line".to_string());
+            }
+        }
+        if self.rewrite.enabled {
+            if self.rewrite.target_duration < self.video.min_duration {
+                problems.push(format!(
+                    "rewrite.target_duration must be at least video.min_duration ({})",
+                    self.video.min_duration
+                ));
+            }
+            if self.rewrite.hook_seconds <= 0.0 {
+                problems.push("rewrite.hook_seconds must be greater than 0".to_string());
+            }
+            if self.outro.enabled
+                && self.rewrite.target_duration + self.outro.max_seconds > self.video.max_duration
+            {
+                problems.push(format!(
+                    "rewrite.target_duration plus outro.max_seconds must not exceed \
+                     video.max_duration ({}), or rewritten videos get split",
+                    self.video.max_duration
+                ));
+            }
+        }
+        if !(0.0..=1.0).contains(&self.rewrite.min_overlap) {
+            problems.push("rewrite.min_overlap must be between 0 and 1".to_string());
+        }
+        if self.prompts.title_chars == 0 || self.prompts.title_chars > self.upload.max_title_length
+        {
+            problems.push(format!(
+                "prompts.title_chars must be between 1 and upload.max_title_length ({})",
+                self.upload.max_title_length
+            ));
+        }
+        if self.localize.enabled {
+            if self.localize.languages.is_empty() {
+                problems.push("localize.languages needs at least one language".to_string());
+            }
+            for (index, language) in self.localize.languages.iter().enumerate() {
+                let code = language.code.trim();
+                if whatlang::Lang::from_code(code).is_none() {
+                    problems.push(format!(
+                        "localize.languages[{index}].code '{code}' is not an ISO 639-3 code \
+                         known to the detector"
+                    ));
+                } else if code == self.validate.language.trim() {
+                    problems.push(format!(
+                        "localize.languages[{index}].code '{code}' is the language of the \
+                         original (validate.language)"
+                    ));
+                } else if self.localize.languages[..index]
+                    .iter()
+                    .any(|other| other.code.trim() == code)
+                {
+                    problems.push(format!(
+                        "localize.languages has more than one language with code '{code}'"
+                    ));
+                } else if self.audio.engine != TtsEngineKind::Silent
+                    && localize::voice(self.audio.engine, language).is_none()
+                {
+                    problems.push(format!(
+                        "localize.languages[{index}] needs a voice: there is no built-in {} \
+                         voice for '{code}'",
+                        format!("{:?}", self.audio.engine).to_lowercase()
+                    ));
+                }
+                if language.privacy.as_ref().is_some_and(|privacy| {
+                    !["public", "private", "unlisted"].contains(&privacy.as_str())
+                }) {
+                    problems.push(format!(
+                        "localize.languages[{index}].privacy must be one of public, private, \
+                         unlisted"
+                    ));
+                }
+                if [
+                    &language.name,
+                    &language.series_title,
+                    &language.episode_file,
+                ]
+                .iter()
+                .any(|value| value.as_ref().is_some_and(|value| value.trim().is_empty()))
+                {
+                    problems.push(format!(
+                        "localize.languages[{index}].name, series_title and episode_file must \
+                         not be empty when set"
+                    ));
+                }
+            }
+        }
+        if self.experiment.enabled {
+            let experiment = &self.experiment;
+            if experiment.name.trim().is_empty() {
+                problems.push("experiment.name must not be empty".to_string());
+            }
+            if experiment
+                .variants
+                .iter()
+                .all(|variant| variant.weight == 0)
+            {
+                problems
+                    .push("experiment.variants needs a variant with a weight above 0".to_string());
+            }
+            for (index, variant) in experiment.variants.iter().enumerate() {
+                if variant.name.trim().is_empty() {
+                    problems.push(format!(
+                        "experiment.variants[{index}].name must not be empty"
+                    ));
+                } else if experiment.variants[..index]
+                    .iter()
+                    .any(|other| other.name == variant.name)
+                {
+                    problems.push(format!(
+                        "experiment.variants has more than one variant named '{}'",
+                        variant.name
+                    ));
+                }
+                if variant
+                    .metadata_prompt
+                    .as_ref()
+                    .is_some_and(|name| name.trim().is_empty())
+                {
+                    problems.push(format!(
+                        "experiment.variants[{index}].metadata_prompt must name a template"
+                    ));
+                }
+                if variant.font_size.is_some_and(|size| size <= 0) {
+                    problems.push(format!(
+                        "experiment.variants[{index}].font_size must be greater than 0"
+                    ));
+                }
+            }
+        }
+        if self.classify.warning.trim().is_empty() {
+            problems.push("classify.warning must not be empty".to_string());
+        }
+        if self.video.max_duration <= 0.0 {
+            problems.push("video.max_duration must be greater than 0".to_string());
+        }
+        if self.video.min_duration < 0.0 {
+            problems.push("video.min_duration must not be negative".to_string());
+        }
+        match self.audio.engine {
+            TtsEngineKind::Kokoro
+                if self.audio.python.trim().is_empty()
+                    || self.audio.kokoro_script.trim().is_empty() =>
+            {
+                problems.push(
+                    "audio.python and audio.kokoro_script must not be empty for the kokoro engine"
+                        .to_string(),
+                );
+            }
+            TtsEngineKind::Piper
+                if self
+                    .audio
+                    .voice
+                    .as_ref()
+                    .is_none_or(|voice| voice.trim().is_empty()) =>
+            {
+                problems.push(
+                    "audio.voice must be the .onnx voice model for the piper engine".to_string(),
+                );
+            }
+            _ => {}
+        }
+        if !(0.0..=1.0).contains(&self.video.music_volume) {
+            problems.push("video.music_volume must be between 0 and 1".to_string());
+        }
+        if self.style.enabled {
+            let pools = self
+                .style
+                .moods
+                .iter()
+                .map(|(mood, pool)| (format!("style.moods.{}", mood.key()), pool))
+                .chain(self.style.categories.iter().map(|(category, pool)| {
+                    (format!("style.categories.{}", category.key()), pool)
+                }));
+            for (name, pool) in pools {
+                for (field, values) in [
+                    ("hashtags", &pool.hashtags),
+                    ("backgrounds", &pool.backgrounds),
+                    ("music", &pool.music),
+                    ("voices", &pool.voices),
+                ] {
+                    if values.iter().any(|value| value.trim().is_empty()) {
+                        problems.push(format!("{name}.{field} must not contain empty entries"));
+                    }
+                }
+                if pool.hashtags.iter().any(|tag| {
+                    tag.trim()
+                        .trim_start_matches('#')
+                        .contains(char::is_whitespace)
+                }) {
+                    problems.push(format!("{name}.hashtags must not contain spaces"));
+                }
+            }
+        }
+        if let Some(size) = self.video.font_size {
+            if size <= 0 {
+                problems.push("video.font_size must be greater than 0".to_string());
+            }
+        }
+        if !["public", "private", "unlisted"].contains(&self.upload.privacy.as_str()) {
+            problems.push(format!(
+                "upload.privacy must be one of public, private, unlisted (got '{}')",
+                self.upload.privacy
+            ));
+        }
+        if self.upload.category.parse::<u32>().is_err() {
+            problems.push(format!(
+                "upload.category must be a numeric YouTube category id (got '{}')",
+                self.upload.category
+            ));
+        }
+        if self.upload.series_title.trim().is_empty() {
+            problems.push("upload.series_title must not be empty".to_string());
+        }
+        if self.upload.max_title_length == 0 {
+            problems.push("upload.max_title_length must be greater than 0".to_string());
+        }
+        if self.llm.model.trim().is_empty() {
+            problems.push("llm.model must not be empty".to_string());
+        }
+        if self.llm.max_retries == 0 {
+            problems.push("llm.max_retries must be at least 1".to_string());
+        }
+        match self.llm.backend {
+            LlmBackendKind::Ollama if reqwest::Url::parse(&self.llm.host).is_err() => {
+                problems.push(format!(
+                    "llm.host must be a URL such as http://localhost (got '{}')",
+                    self.llm.host
+                ));
+            }
+            LlmBackendKind::Openai
+                if !self.llm.base_url.starts_with("http://")
+                    && !self.llm.base_url.starts_with("https://") =>
+            {
+                problems.push(format!(
+                    "llm.base_url must be an http(s) URL (got '{}')",
+                    self.llm.base_url
+                ));
+            }
+            _ => {}
+        }
+        if self.llm.timeout_secs == 0 {
+            problems.push("llm.timeout_secs must be greater than 0".to_string());
+        }
+        if self.audio.timeout_secs == 0 {
+            problems.push("audio.timeout_secs must be greater than 0".to_string());
+        }
+        if let Some(temperature) = self.llm.temperature {
+            if !(0.0..=2.0).contains(&temperature) {
+                problems.push("llm.temperature must be between 0 and 2".to_string());
+            }
+        }
+        if self.llm.cache && self.llm.cache_dir.trim().is_empty() {
+            problems.push("llm.cache_dir must not be empty when llm.cache is on".to_string());
+        }
+        if !self.notify.url.starts_with("http://") && !self.notify.url.starts_with("https://") {
+            problems.push(format!(
+                "notify.url must be an http(s) URL (got '{}')",
+                self.notify.url
+            ));
+        }
+
+        if self.schedule.timezone.parse::<chrono_tz::Tz>().is_err() {
+            problems.push(format!(
+                "schedule.timezone '{}' is not a known IANA timezone",
+                self.schedule.timezone
+            ));
+        }
+        for slot in &self.schedule.slots {
+            if chrono::NaiveTime::parse_from_str(slot, "%H:%M").is_err() {
+                problems.push(format!("schedule.slots entry '{slot}' is not a HH:MM time"));
+            }
+        }
+        if self.schedule.slots.is_empty() && self.schedule.videos_per_day == 0 {
+            problems.push(
+                "schedule.videos_per_day must be at least 1 when schedule.slots is empty"
+                    .to_string(),
+            );
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError::Invalid(problems))
+        }
+    }
+}
+
+fn merge_values(base: &mut toml::Value, overlay: toml::Value) {
+    match (base, overlay) {
+        (toml::Value::Table(base_table), toml::Value::Table(overlay_table)) => {
+            for (key, value) in overlay_table {
+                match base_table.get_mut(&key) {
+                    Some(existing) => merge_values(existing, value),
+                    None => {
+                        base_table.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// Sets one environment or command-line override. Keys without a default (unset
+/// optional fields) have no type to go by, so a value like `24` is kept as text
+/// when only that makes the config deserialize.
+fn apply_override(layered: &mut toml::Value, dotted: &str, raw: &str) -> Result<(), ConfigError> {
+    let mut typed = layered.clone();
+    set_dotted(&mut typed, dotted, raw)?;
+    if typed.clone().try_into::<Config>().is_err() {
+        let mut text = layered.clone();
+        set_dotted_value(&mut text, dotted, toml::Value::String(raw.to_string()))?;
+        if text.clone().try_into::<Config>().is_ok() {
+            *layered = text;
+            return Ok(());
+        }
+    }
+    *layered = typed;
+    Ok(())
+}
+
+/// Parses `raw` as a TOML value when possible so numbers and booleans keep their
+/// type, falling back to a plain string for bare words like `unlisted`.
+fn parse_scalar(raw: &str) -> toml::Value {
+    toml::from_str::<toml::Table>(&format!("v = {raw}"))
+        .ok()
+        .and_then(|mut table| table.remove("v"))
+        .unwrap_or_else(|| toml::Value::String(raw.to_string()))
+}
+
+/// Sets `dotted` to `raw`, kept as text when the value it replaces is text so
+/// ids like `upload.category=24` stay strings.
+fn set_dotted(root: &mut toml::Value, dotted: &str, raw: &str) -> Result<(), ConfigError> {
+    let is_text = dotted
+        .split('.')
+        .try_fold(&*root, |value, part| value.get(part))
+        .is_some_and(toml::Value::is_str);
+    let value = if is_text {
+        toml::Value::String(raw.to_string())
+    } else {
+        parse_scalar(raw)
+    };
+    set_dotted_value(root, dotted, value)
+}
+
+fn set_dotted_value(
+    root: &mut toml::Value,
+    dotted: &str,
+    value: toml::Value,
+) -> Result<(), ConfigError> {
+    let mut current = root;
+    let mut parts = dotted.split('.').peekable();
+
+    while let Some(part) = parts.next() {
+        if part.is_empty() {
+            return Err(ConfigError::Override(format!(
+                "empty segment in key '{dotted}'"
+            )));
+        }
+        let table = current.as_table_mut().ok_or_else(|| {
+            ConfigError::Override(format!("'{dotted}' descends into a non-table value"))
+        })?;
+
+        if parts.peek().is_none() {
+            table.insert(part.to_string(), value);
+            return Ok(());
+        }
+
+        current = table
+            .entry(part.to_string())
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Loads `file` as the config file with `assignments` given as `--set`.
+    fn load(name: &str, file: &str, assignments: &[&str]) -> Result<Config, ConfigError> {
+        let path = std::env::temp_dir().join(format!("{name}.{}.toml", std::process::id()));
+        fs::write(&path, file).unwrap();
+        let overrides: Vec<String> = assignments.iter().map(|a| a.to_string()).collect();
+        let result = Config::load(path.to_str(), &overrides);
+        fs::remove_file(&path).unwrap();
+        result
+    }
+
+    // Environment variables are process-wide, so every layer is checked in this
+    // one test and the others only use `--set`, which outranks the environment.
+    #[test]
+    fn layers_apply_in_order() {
+        std::env::set_var("BRAINROT_UPLOAD__SERIES_TITLE", "Env");
+        std::env::set_var("BRAINROT_UPLOAD__PART_LABEL", "Env");
+        std::env::set_var("BRAINROT_UPLOAD__CATEGORY", "24");
+        let config = load(
+            "brainrot-layers",
+            "[upload]\nprivacy = \"unlisted\"\nseries_title = \"File\"\n\
+             part_label = \"File\"\nmax_title_length = 80\n",
+            &["upload.part_label=Set"],
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.upload.playlist_id,
+            UploadConfig::default().playlist_id
+        );
+        assert_eq!(config.upload.privacy, "unlisted");
+        assert_eq!(config.upload.max_title_length, 80);
+        assert_eq!(config.upload.series_title, "Env");
+        assert_eq!(config.upload.part_label, "Set");
+        assert_eq!(config.upload.category, "24");
+    }
+
+    #[test]
+    fn numeric_looking_values_stay_strings_for_string_settings() {
+        let config = load(
+            "brainrot-numeric",
+            "",
+            &[
+                "upload.category=24",
+                "upload.playlist_id=12345",
+                "upload.credentials=2024",
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(config.upload.category, "24");
+        assert_eq!(config.upload.playlist_id, "12345");
+        assert_eq!(config.upload.credentials.as_deref(), Some("2024"));
+    }
+
+    #[test]
+    fn numeric_settings_still_parse_as_numbers() {
+        let config = load(
+            "brainrot-typed",
+            "",
+            &["upload.max_title_length=70", "video.max_duration=58.5"],
+        )
+        .unwrap();
+
+        assert_eq!(config.upload.max_title_length, 70);
+        assert_eq!(config.video.max_duration, 58.5);
+    }
+
+    #[test]
+    fn mistyped_overrides_are_still_rejected() {
+        let result = load("brainrot-mistyped", "", &["upload.max_title_length=long"]);
+        assert!(matches!(result, Err(ConfigError::Parse(_))));
+    }
+}
@@ -0,0 +1,336 @@
+use csv::{Position, ReaderBuilder, StringRecord};
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::time::UNIX_EPOCH;
+
+use crate::error::{Error, Result};
+
+const MAGIC: &[u8; 8] = b"CSVIDX01";
+/// Magic, CSV length, CSV mtime (secs + nanos), fingerprint, record count.
+const HEADER_LEN: u64 = 8 + 8 + 8 + 4 + 8 + 8;
+
+/// Byte offsets of every usable record in a CSV file, stored next to it so a
+/// random record can be fetched with one seek instead of a linear scan.
+///
+/// The index remembers the size and modification time of the CSV it was built
+/// from plus a caller-supplied fingerprint of the rules that decided which
+/// records are usable, and is rebuilt when any of them change.
+pub struct CsvIndex {
+    index_path: String,
+    count: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct CsvStamp {
+    len: u64,
+    mtime_secs: u64,
+    mtime_nanos: u32,
+}
+
+impl CsvStamp {
+    fn of(csv_path: &str) -> Result<Self> {
+        let metadata =
+            fs::metadata(csv_path).map_err(Error::io(format!("Failed to stat {}", csv_path)))?;
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .unwrap_or_default();
+
+        Ok(Self {
+            len: metadata.len(),
+            mtime_secs: modified.as_secs(),
+            mtime_nanos: modified.subsec_nanos(),
+        })
+    }
+}
+
+impl CsvIndex {
+    /// The default index location: the CSV path with `.idx` appended.
+    pub fn default_path(csv_path: &str) -> String {
+        format!("{}.idx", csv_path)
+    }
+
+    /// The header row of `csv_path`, used to resolve columns before indexing.
+    pub fn headers(csv_path: &str) -> Result<StringRecord> {
+        let file =
+            File::open(csv_path).map_err(Error::io(format!("Failed to open {}", csv_path)))?;
+        ReaderBuilder::new()
+            .has_headers(true)
+            .from_reader(BufReader::new(file))
+            .headers()
+            .cloned()
+            .map_err(|e| Error::Source(format!("Failed to read headers of {}: {}", csv_path, e)))
+    }
+
+    /// Opens the index at `index_path`, rebuilding it first when it is missing,
+    /// stale, or was built with a different `fingerprint`. Only records for
+    /// which `is_usable` returns true are indexed.
+    pub fn open_or_build(
+        csv_path: &str,
+        index_path: &str,
+        fingerprint: u64,
+        is_usable: impl Fn(&StringRecord) -> bool,
+    ) -> Result<Self> {
+        let stamp = CsvStamp::of(csv_path)?;
+
+        if let Some(count) = read_header(index_path, stamp, fingerprint)? {
+            return Ok(Self {
+                index_path: index_path.to_string(),
+                count,
+            });
+        }
+
+        println!("Building confession index {} ...", index_path);
+        let count = build(csv_path, index_path, stamp, fingerprint, is_usable)?;
+        println!("Indexed {} usable records", count);
+
+        Ok(Self {
+            index_path: index_path.to_string(),
+            count,
+        })
+    }
+
+    pub fn len(&self) -> u64 {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Byte offset in the CSV of the `n`th indexed record.
+    pub fn offset(&self, n: u64) -> Result<u64> {
+        if n >= self.count {
+            return Err(Error::Source(format!(
+                "Index entry {} out of range ({} entries)",
+                n, self.count
+            )));
+        }
+
+        let mut file = File::open(&self.index_path)
+            .map_err(Error::io(format!("Failed to open {}", self.index_path)))?;
+        let mut buf = [0u8; 8];
+        file.seek(SeekFrom::Start(HEADER_LEN + n * 8))
+            .and_then(|_| file.read_exact(&mut buf))
+            .map_err(Error::io(format!("Failed to read {}", self.index_path)))?;
+        Ok(u64::from_le_bytes(buf))
+    }
+
+    /// Reads the record starting at `offset` with a single seek.
+    pub fn read_record_at(csv_path: &str, offset: u64) -> Result<StringRecord> {
+        let file =
+            File::open(csv_path).map_err(Error::io(format!("Failed to open {}", csv_path)))?;
+        let mut reader = ReaderBuilder::new()
+            .has_headers(true)
+            .from_reader(BufReader::new(file));
+
+        let mut position = Position::new();
+        position.set_byte(offset);
+        reader
+            .seek(position)
+            .map_err(|e| Error::Source(format!("Failed to seek {}: {}", csv_path, e)))?;
+
+        let mut record = StringRecord::new();
+        match reader.read_record(&mut record) {
+            Ok(true) => Ok(record),
+            Ok(false) => Err(Error::Source(format!(
+                "No record at byte {} of {}",
+                offset, csv_path
+            ))),
+            Err(e) => Err(Error::Source(format!(
+                "CSV parsing error at byte {} of {}: {}",
+                offset, csv_path, e
+            ))),
+        }
+    }
+}
+
+/// Returns the record count when the index exists and matches `stamp` and `fingerprint`.
+fn read_header(index_path: &str, stamp: CsvStamp, fingerprint: u64) -> Result<Option<u64>> {
+    let mut file = match File::open(index_path) {
+        Ok(file) => file,
+        Err(_) => return Ok(None),
+    };
+
+    let mut header = [0u8; HEADER_LEN as usize];
+    if file.read_exact(&mut header).is_err() {
+        return Ok(None);
+    }
+
+    let u64_at = |at: usize| u64::from_le_bytes(header[at..at + 8].try_into().unwrap_or_default());
+    let stored = CsvStamp {
+        len: u64_at(8),
+        mtime_secs: u64_at(16),
+        mtime_nanos: u32::from_le_bytes(header[24..28].try_into().unwrap_or_default()),
+    };
+    let stored_fingerprint = u64_at(28);
+    let count = u64_at(36);
+
+    let file_len = file
+        .metadata()
+        .map_err(Error::io(format!("Failed to stat {}", index_path)))?
+        .len();
+
+    let valid = &header[..8] == MAGIC
+        && stored == stamp
+        && stored_fingerprint == fingerprint
+        && file_len == HEADER_LEN + count * 8;
+    Ok(valid.then_some(count))
+}
+
+fn build(
+    csv_path: &str,
+    index_path: &str,
+    stamp: CsvStamp,
+    fingerprint: u64,
+    is_usable: impl Fn(&StringRecord) -> bool,
+) -> Result<u64> {
+    let file = File::open(csv_path).map_err(Error::io(format!("Failed to open {}", csv_path)))?;
+    let mut reader = ReaderBuilder::new()
+        .has_headers(true)
+        .from_reader(BufReader::new(file));
+
+    // Read the header row first so the first offset points at the first record.
+    reader
+        .headers()
+        .map_err(|e| Error::Source(format!("Failed to read headers of {}: {}", csv_path, e)))?;
+
+    let mut offsets = Vec::new();
+    let mut record = StringRecord::new();
+    loop {
+        let offset = reader.position().byte();
+        match reader.read_record(&mut record) {
+            Ok(true) => {
+                if is_usable(&record) {
+                    offsets.push(offset);
+                }
+            }
+            Ok(false) => break,
+            Err(e) => eprintln!("CSV parsing error: {}", e),
+        }
+    }
+
+    let tmp_path = format!("{}.tmp", index_path);
+    write_index(&tmp_path, stamp, fingerprint, &offsets)
+        .map_err(Error::io(format!("Failed to write {}", tmp_path)))?;
+    fs::rename(&tmp_path, index_path).map_err(Error::io(format!(
+        "Failed to move index into {}",
+        index_path
+    )))?;
+
+    Ok(offsets.len() as u64)
+}
+
+fn write_index(
+    path: &str,
+    stamp: CsvStamp,
+    fingerprint: u64,
+    offsets: &[u64],
+) -> std::io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    writer.write_all(MAGIC)?;
+    writer.write_all(&stamp.len.to_le_bytes())?;
+    writer.write_all(&stamp.mtime_secs.to_le_bytes())?;
+    writer.write_all(&stamp.mtime_nanos.to_le_bytes())?;
+    writer.write_all(&fingerprint.to_le_bytes())?;
+    writer.write_all(&(offsets.len() as u64).to_le_bytes())?;
+    for offset in offsets {
+        writer.write_all(&offset.to_le_bytes())?;
+    }
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    const CSV: &str = "title,selftext\n\
+                       first,short\n\
+                       second,\"spans\ntwo lines, with a comma\"\n\
+                       skip,\n\
+                       third,\"quoted \"\"word\"\"\"\n";
+
+    fn scratch(name: &str, csv: &str) -> (PathBuf, String, String) {
+        let dir = std::env::temp_dir().join(format!("csv-index-{name}-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let csv_path = dir.join("posts.csv").to_string_lossy().into_owned();
+        fs::write(&csv_path, csv).unwrap();
+        let index_path = CsvIndex::default_path(&csv_path);
+        let _ = fs::remove_file(&index_path);
+        (dir, csv_path, index_path)
+    }
+
+    fn has_body(record: &StringRecord) -> bool {
+        record.get(1).is_some_and(|body| !body.is_empty())
+    }
+
+    fn titles(index: &CsvIndex, csv_path: &str) -> Vec<String> {
+        (0..index.len())
+            .map(|n| {
+                let record = CsvIndex::read_record_at(csv_path, index.offset(n).unwrap()).unwrap();
+                record[0].to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn indexes_usable_records_including_multiline_ones() {
+        let (dir, csv_path, index_path) = scratch("build", CSV);
+        let index = CsvIndex::open_or_build(&csv_path, &index_path, 1, has_body).unwrap();
+
+        assert_eq!(index.len(), 3);
+        assert_eq!(titles(&index, &csv_path), ["first", "second", "third"]);
+        let second = CsvIndex::read_record_at(&csv_path, index.offset(1).unwrap()).unwrap();
+        assert_eq!(&second[1], "spans\ntwo lines, with a comma");
+        let third = CsvIndex::read_record_at(&csv_path, index.offset(2).unwrap()).unwrap();
+        assert_eq!(&third[1], "quoted \"word\"");
+        assert!(index.offset(3).is_err());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn reuses_a_fresh_index() {
+        let (dir, csv_path, index_path) = scratch("reuse", CSV);
+        CsvIndex::open_or_build(&csv_path, &index_path, 1, has_body).unwrap();
+
+        // A matching index is read back as is, without consulting the filter.
+        let index = CsvIndex::open_or_build(&csv_path, &index_path, 1, |_| false).unwrap();
+        assert_eq!(index.len(), 3);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn rebuilds_when_the_csv_or_fingerprint_changes() {
+        let (dir, csv_path, index_path) = scratch("stale", CSV);
+        CsvIndex::open_or_build(&csv_path, &index_path, 1, has_body).unwrap();
+
+        fs::write(&csv_path, format!("{CSV}fourth,appended\n")).unwrap();
+        let index = CsvIndex::open_or_build(&csv_path, &index_path, 1, has_body).unwrap();
+        assert_eq!(
+            titles(&index, &csv_path),
+            ["first", "second", "third", "fourth"]
+        );
+
+        let index = CsvIndex::open_or_build(&csv_path, &index_path, 2, |_| true).unwrap();
+        assert_eq!(index.len(), 5);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn rebuilds_a_truncated_index() {
+        let (dir, csv_path, index_path) = scratch("truncated", CSV);
+        CsvIndex::open_or_build(&csv_path, &index_path, 1, has_body).unwrap();
+
+        let bytes = fs::read(&index_path).unwrap();
+        fs::write(&index_path, &bytes[..bytes.len() - 4]).unwrap();
+        let index = CsvIndex::open_or_build(&csv_path, &index_path, 1, has_body).unwrap();
+        assert_eq!(titles(&index, &csv_path), ["first", "second", "third"]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
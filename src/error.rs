@@ -0,0 +1,87 @@
+use crate::config::ConfigError;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// One variant per pipeline stage, each carrying a message that says what was
+/// being done when it failed.
+#[derive(Debug)]
+pub enum Error {
+    Config(ConfigError),
+    Source(String),
+    Llm(String),
+    Tts(String),
+    Render(String),
+    Split(String),
+    Upload(String),
+    Notify(String),
+    State(String),
+    Io {
+        context: String,
+        source: std::io::Error,
+    },
+}
+
+/// What the orchestrator should do after a stage failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Recovery {
+    /// Try again, e.g. with another confession or on the next slot.
+    Retry,
+    /// Log it and carry on; the failure does not affect the video.
+    Skip,
+    /// Stop: retrying cannot succeed without a human fixing something.
+    Abort,
+}
+
+impl Error {
+    /// Wraps an IO error with what was being attempted:
+    /// `fs::read(path).map_err(Error::io(format!("Failed to read {path}")))`.
+    pub fn io(context: impl Into<String>) -> impl FnOnce(std::io::Error) -> Self {
+        let context = context.into();
+        move |source| Self::Io { context, source }
+    }
+
+    pub fn recovery(&self) -> Recovery {
+        match self {
+            Self::Source(_) | Self::Llm(_) | Self::Tts(_) | Self::Upload(_) => Recovery::Retry,
+            Self::Notify(_) => Recovery::Skip,
+            Self::Config(_)
+            | Self::Render(_)
+            | Self::Split(_)
+            | Self::State(_)
+            | Self::Io { .. } => Recovery::Abort,
+        }
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Config(e) => write!(f, "{e}"),
+            Self::Source(msg) => write!(f, "Confession source error: {msg}"),
+            Self::Llm(msg) => write!(f, "LLM error: {msg}"),
+            Self::Tts(msg) => write!(f, "TTS error: {msg}"),
+            Self::Render(msg) => write!(f, "Render error: {msg}"),
+            Self::Split(msg) => write!(f, "Split error: {msg}"),
+            Self::Upload(msg) => write!(f, "Upload error: {msg}"),
+            Self::Notify(msg) => write!(f, "Notification error: {msg}"),
+            Self::State(msg) => write!(f, "State error: {msg}"),
+            Self::Io { context, source } => write!(f, "{context}: {source}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Config(e) => Some(e),
+            Self::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<ConfigError> for Error {
+    fn from(e: ConfigError) -> Self {
+        Self::Config(e)
+    }
+}
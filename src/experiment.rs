@@ -0,0 +1,357 @@
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use crate::config::{AssignmentMode, Config, VariantConfig};
+use crate::error::{Error, Result};
+use crate::job::{Job, UploadStatus};
+
+/// The variant a job was given, stored in its record so a resumed job keeps it
+/// and the report can find it after the config changed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Assignment {
+    pub experiment: String,
+    pub variant: String,
+}
+
+/// Header names tried, in order and ignoring case, for each stats column. The
+/// second name of each is what YouTube Studio exports use.
+const VIDEO_ID_COLUMNS: [&str; 2] = ["video_id", "content"];
+const VIEWS_COLUMNS: [&str; 1] = ["views"];
+const LIKES_COLUMNS: [&str; 1] = ["likes"];
+const COMMENTS_COLUMNS: [&str; 2] = ["comments", "comments added"];
+const VIEWED_COLUMNS: [&str; 2] = ["average_view_percentage", "average percentage viewed (%)"];
+
+/// Picks a variant for a new job, or `None` when no experiment is running.
+pub fn assign(config: &Config) -> Result<Option<Assignment>> {
+    let experiment = &config.experiment;
+    if !experiment.enabled {
+        return Ok(None);
+    }
+
+    let total: u32 = experiment
+        .variants
+        .iter()
+        .map(|variant| variant.weight)
+        .sum();
+    let slot = match experiment.assignment {
+        AssignmentMode::Weighted => rand::rng().random_range(0..total),
+        AssignmentMode::RoundRobin => {
+            let assigned = Job::list(&config.paths.jobs_folder)?
+                .iter()
+                .filter(|job| {
+                    job.experiment
+                        .as_ref()
+                        .is_some_and(|assignment| assignment.experiment == experiment.name)
+                })
+                .count();
+            (assigned % total as usize) as u32
+        }
+    };
+
+    let mut remaining = slot;
+    let variant = experiment
+        .variants
+        .iter()
+        .find(|variant| {
+            if remaining < variant.weight {
+                return true;
+            }
+            remaining -= variant.weight;
+            false
+        })
+        .ok_or_else(|| Error::State("experiment.variants has no weight".to_string()))?;
+
+    Ok(Some(Assignment {
+        experiment: experiment.name.clone(),
+        variant: variant.name.clone(),
+    }))
+}
+
+/// The configured variant `assignment` refers to, if the experiment still has it.
+pub fn variant<'a>(config: &'a Config, assignment: &Assignment) -> Option<&'a VariantConfig> {
+    if assignment.experiment != config.experiment.name {
+        return None;
+    }
+    config
+        .experiment
+        .variants
+        .iter()
+        .find(|variant| variant.name == assignment.variant)
+}
+
+/// Overrides the parts of `config` the variant sets.
+pub fn apply(variant: &VariantConfig, config: &mut Config) {
+    if let Some(template) = &variant.metadata_prompt {
+        config.prompts.metadata = template.clone();
+    }
+    if let Some(persona) = &variant.persona {
+        config.prompts.persona = persona.clone();
+    }
+    if let Some(voice) = &variant.voice {
+        config.audio.voice = Some(voice.clone());
+        // The voice is what this variant tests, so styles must not swap it.
+        let pools = config.style.moods.values_mut();
+        for pool in pools.chain(config.style.categories.values_mut()) {
+            pool.voices.clear();
+        }
+    }
+    if let Some(font_size) = variant.font_size {
+        config.video.font_size = Some(font_size);
+    }
+    if let Some(bg_color) = &variant.bg_color {
+        config.video.bg_color = Some(bg_color.clone());
+    }
+}
+
+/// How one uploaded video performed.
+#[derive(Debug, Clone, Default)]
+pub struct VideoStats {
+    pub views: f64,
+    pub likes: Option<f64>,
+    pub comments: Option<f64>,
+    /// Average percentage of the video watched.
+    pub viewed: Option<f64>,
+}
+
+/// Reads the `experiment.stats_path` CSV, keyed by video id. A missing file
+/// means no stats have been collected yet.
+pub fn load_stats(path: &str) -> Result<HashMap<String, VideoStats>> {
+    let mut stats = HashMap::new();
+    if !Path::new(path).exists() {
+        return Ok(stats);
+    }
+
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_path(path)
+        .map_err(|e| Error::State(format!("Failed to open stats {}: {}", path, e)))?;
+    let headers = reader
+        .headers()
+        .map_err(|e| Error::State(format!("Failed to read stats {}: {}", path, e)))?
+        .clone();
+    let column = |names: &[&str]| {
+        names.iter().find_map(|name| {
+            headers
+                .iter()
+                .position(|header| header.trim().eq_ignore_ascii_case(name))
+        })
+    };
+
+    let (Some(id_column), Some(views_column)) = (column(&VIDEO_ID_COLUMNS), column(&VIEWS_COLUMNS))
+    else {
+        return Err(Error::State(format!(
+            "Stats {} needs a {} and a {} column",
+            path, VIDEO_ID_COLUMNS[0], VIEWS_COLUMNS[0]
+        )));
+    };
+    let likes_column = column(&LIKES_COLUMNS);
+    let comments_column = column(&COMMENTS_COLUMNS);
+    let viewed_column = column(&VIEWED_COLUMNS);
+
+    for (line, record) in reader.records().enumerate() {
+        let record =
+            record.map_err(|e| Error::State(format!("Failed to read stats {}: {}", path, e)))?;
+        let number = |column: Option<usize>| column.and_then(|c| parse_number(record.get(c)?));
+        let Some(video_id) = record
+            .get(id_column)
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+        else {
+            continue;
+        };
+        // Studio exports start with a "Total" row.
+        if video_id.eq_ignore_ascii_case("total") {
+            continue;
+        }
+        let Some(views) = number(Some(views_column)) else {
+            eprintln!("Skipping stats row {} of {}: no views", line + 2, path);
+            continue;
+        };
+        stats.insert(
+            video_id.to_string(),
+            VideoStats {
+                views,
+                likes: number(likes_column),
+                comments: number(comments_column),
+                viewed: number(viewed_column),
+            },
+        );
+    }
+
+    Ok(stats)
+}
+
+/// Accepts the thousands separators and percent signs found in exports.
+fn parse_number(text: &str) -> Option<f64> {
+    text.trim()
+        .trim_end_matches('%')
+        .replace(',', "")
+        .parse()
+        .ok()
+}
+
+/// How the videos of one variant, or one group of [`report_by`], did.
+#[derive(Debug, Clone, Default)]
+pub struct VariantReport {
+    /// The variant or group name.
+    pub variant: String,
+    /// Whether the variant is still in `experiment.variants`.
+    pub configured: bool,
+    pub jobs: usize,
+    pub videos: usize,
+    /// Uploaded videos with a row in the stats file.
+    pub measured: usize,
+    pub mean_views: Option<f64>,
+    pub median_views: Option<f64>,
+    /// Likes per 100 views, over all measured videos together.
+    pub like_rate: Option<f64>,
+    /// Comments per 1000 views, over all measured videos together.
+    pub comment_rate: Option<f64>,
+    pub mean_viewed: Option<f64>,
+}
+
+/// Compares the variants of experiment `name` over the recorded jobs, every
+/// uploaded part counting as its own video. Configured variants without jobs
+/// are listed too.
+pub fn report(config: &Config, name: &str) -> Result<Vec<VariantReport>> {
+    let stats = load_stats(&config.experiment.stats_path)?;
+    let jobs = Job::list(&config.paths.jobs_folder)?;
+
+    let mut variants: Vec<String> = Vec::new();
+    if config.experiment.name == name {
+        variants.extend(config.experiment.variants.iter().map(|v| v.name.clone()));
+    }
+    for assignment in jobs.iter().filter_map(|job| job.experiment.as_ref()) {
+        if assignment.experiment == name && !variants.contains(&assignment.variant) {
+            variants.push(assignment.variant.clone());
+        }
+    }
+
+    let reports = variants
+        .into_iter()
+        .map(|variant| {
+            let assigned: Vec<&Job> = jobs
+                .iter()
+                .filter(|job| {
+                    job.experiment.as_ref().is_some_and(|assignment| {
+                        assignment.experiment == name && assignment.variant == variant
+                    })
+                })
+                .collect();
+            let configured = config.experiment.name == name
+                && config.experiment.variants.iter().any(|v| v.name == variant);
+            measure(&stats, variant, configured, &assigned)
+        })
+        .collect();
+
+    Ok(reports)
+}
+
+/// What [`report_by`] groups the jobs by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Grouping {
+    /// The mood in the metadata.
+    Mood,
+    /// The category in the metadata.
+    Category,
+    /// The `[[localize.languages]]` code, `original` for untranslated jobs.
+    Language,
+}
+
+impl Grouping {
+    fn key(self, job: &Job) -> String {
+        let metadata = job.metadata.as_ref();
+        let key = match self {
+            Grouping::Mood => metadata.and_then(|m| m.mood).map(|mood| mood.key()),
+            Grouping::Category => metadata
+                .and_then(|m| m.category)
+                .map(|category| category.key()),
+            Grouping::Language => Some(job.language.as_deref().unwrap_or("original")),
+        };
+        key.unwrap_or("unknown").to_string()
+    }
+}
+
+/// Compares the videos of all recorded jobs grouped by `grouping`, in the same
+/// way [`report`] compares variants. Groups are sorted by name.
+pub fn report_by(config: &Config, grouping: Grouping) -> Result<Vec<VariantReport>> {
+    let stats = load_stats(&config.experiment.stats_path)?;
+    let jobs = Job::list(&config.paths.jobs_folder)?;
+
+    let mut groups: BTreeMap<String, Vec<&Job>> = BTreeMap::new();
+    for job in &jobs {
+        groups.entry(grouping.key(job)).or_default().push(job);
+    }
+
+    Ok(groups
+        .into_iter()
+        .map(|(group, jobs)| measure(&stats, group, true, &jobs))
+        .collect())
+}
+
+/// Summarizes the uploaded parts of `jobs`, every part counting as its own video.
+fn measure(
+    stats: &HashMap<String, VideoStats>,
+    name: String,
+    configured: bool,
+    jobs: &[&Job],
+) -> VariantReport {
+    let uploaded: Vec<Option<&String>> = jobs
+        .iter()
+        .flat_map(|job| &job.parts)
+        .filter_map(|part| match &part.status {
+            UploadStatus::Uploaded { video_id } => Some(video_id.as_ref()),
+            _ => None,
+        })
+        .collect();
+    let measured: Vec<&VideoStats> = uploaded
+        .iter()
+        .filter_map(|video_id| stats.get((*video_id)?))
+        .collect();
+    summarize(name, configured, jobs.len(), uploaded.len(), &measured)
+}
+
+fn summarize(
+    variant: String,
+    configured: bool,
+    jobs: usize,
+    videos: usize,
+    measured: &[&VideoStats],
+) -> VariantReport {
+    let mut views: Vec<f64> = measured.iter().map(|stats| stats.views).collect();
+    views.sort_by(f64::total_cmp);
+    let total_views: f64 = views.iter().sum();
+
+    let median_views = match views.len() {
+        0 => None,
+        n if n % 2 == 1 => Some(views[n / 2]),
+        n => Some((views[n / 2 - 1] + views[n / 2]) / 2.0),
+    };
+    // Rates only count videos that report both numbers, so a missing column
+    // does not drag them down.
+    let rate = |field: fn(&VideoStats) -> Option<f64>, per: f64| {
+        let (count, seen) = measured
+            .iter()
+            .filter_map(|stats| Some((field(stats)?, stats.views)))
+            .fold((0.0, 0.0), |(count, seen), (n, views)| {
+                (count + n, seen + views)
+            });
+        (seen > 0.0).then(|| count / seen * per)
+    };
+    let viewed: Vec<f64> = measured.iter().filter_map(|stats| stats.viewed).collect();
+
+    VariantReport {
+        variant,
+        configured,
+        jobs,
+        videos,
+        measured: measured.len(),
+        mean_views: (!views.is_empty()).then(|| total_views / views.len() as f64),
+        median_views,
+        like_rate: rate(|stats| stats.likes, 100.0),
+        comment_rate: rate(|stats| stats.comments, 1000.0),
+        mean_viewed: (!viewed.is_empty()).then(|| viewed.iter().sum::<f64>() / viewed.len() as f64),
+    }
+}
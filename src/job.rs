@@ -0,0 +1,321 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::confession::Confession;
+use crate::config::Config;
+use crate::error::{Error, Result};
+use crate::experiment::{self, Assignment};
+use crate::localize;
+use crate::metadata::VideoMetadata;
+use crate::{style, utils};
+
+/// Steps of a job in the order they complete. A resumed job continues from
+/// the step after the one recorded here.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStage {
+    Created,
+    AudioReady,
+    Rendered,
+    Split,
+    Uploaded,
+    Done,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum UploadStatus {
+    Pending,
+    /// The upload was started but the process stopped before it reported back,
+    /// so the video may or may not be on the channel.
+    InProgress,
+    Uploaded {
+        video_id: Option<String>,
+    },
+    Failed {
+        error: String,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PartUpload {
+    pub path: String,
+    pub part: Option<(usize, usize)>,
+    pub status: UploadStatus,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Job {
+    pub id: String,
+    pub stage: JobStage,
+    pub created_at: u64,
+    pub work_dir: String,
+    pub confession: Option<Confession>,
+    pub metadata: Option<VideoMetadata>,
+    pub audio_path: Option<String>,
+    pub video_path: Option<String>,
+    pub episode: Option<u32>,
+    pub parts: Vec<PartUpload>,
+    /// The `[experiment]` variant this job runs with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub experiment: Option<Assignment>,
+    /// ISO 639-3 code of the `[[localize.languages]]` entry this job translates
+    /// its parent into; `None` for the original.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// The job whose confession and metadata this one translates.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+    /// Ids of the jobs translating this one, once they are queued.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub translations: Vec<String>,
+}
+
+impl Job {
+    pub fn new(config: &Config) -> Self {
+        let created_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        let id = format!("job-{}-{:04x}", created_at, rand::random::<u16>());
+        let work_dir = Path::new(&config.paths.outputs_folder)
+            .join(&id)
+            .to_string_lossy()
+            .into_owned();
+
+        Self {
+            id,
+            stage: JobStage::Created,
+            created_at,
+            work_dir,
+            confession: None,
+            metadata: None,
+            audio_path: None,
+            video_path: None,
+            episode: None,
+            parts: Vec::new(),
+            experiment: None,
+            language: None,
+            parent: None,
+            translations: Vec::new(),
+        }
+    }
+
+    /// Starts a new job, assigned a variant when an experiment is running, and
+    /// saves its record.
+    pub fn create(config: &Config) -> Result<Self> {
+        let mut job = Self::new(config);
+        job.experiment = experiment::assign(config)?;
+        if let Some(assignment) = &job.experiment {
+            println!(
+                "{} runs variant {} of experiment {}",
+                job.id, assignment.variant, assignment.experiment
+            );
+        }
+        job.save(&config.paths.jobs_folder)?;
+        Ok(job)
+    }
+
+    /// Starts a job producing `parent` in `language`, with the parent's confession
+    /// and metadata still to be translated, and saves its record.
+    pub fn translation(config: &Config, parent: &Job, language: &str) -> Result<Self> {
+        let mut job = Self::new(config);
+        job.language = Some(language.to_string());
+        job.parent = Some(parent.id.clone());
+        job.confession = parent.confession.clone();
+        job.metadata = parent.metadata.clone();
+        job.save(&config.paths.jobs_folder)?;
+        Ok(job)
+    }
+
+    /// A copy of `config` whose output paths point into this job's work directory,
+    /// so artifacts of different jobs never overwrite each other, with the job's
+    /// style, experiment variant and language applied, in that order, so what a
+    /// variant tests is never replaced by a style pick.
+    pub fn scoped_config(&self, config: &Config) -> Config {
+        let mut scoped = config.clone();
+        if let Some(style) = self.metadata.as_ref().and_then(|m| m.style.as_ref()) {
+            style::apply(style, &mut scoped);
+        }
+        if let Some(assignment) = &self.experiment {
+            match experiment::variant(config, assignment) {
+                Some(variant) => experiment::apply(variant, &mut scoped),
+                None => eprintln!(
+                    "{} was assigned variant {} of experiment {}, which is no longer \
+                     configured; using the base settings",
+                    self.id, assignment.variant, assignment.experiment
+                ),
+            }
+        }
+        if let Some(code) = &self.language {
+            match localize::language(config, code) {
+                Some(language) => localize::apply(language, &mut scoped),
+                None => eprintln!(
+                    "{} is in language {}, which is no longer in localize.languages; using \
+                     the base settings",
+                    self.id, code
+                ),
+            }
+        }
+        let work_dir = Path::new(&self.work_dir);
+        scoped.paths.outputs_folder = self.work_dir.clone();
+        scoped.paths.audio_output = scoped_file(work_dir, &config.paths.audio_output);
+        scoped.paths.video_output = scoped_file(work_dir, &config.paths.video_output);
+        scoped
+    }
+
+    pub fn record_path(jobs_folder: &str, id: &str) -> PathBuf {
+        Path::new(jobs_folder).join(format!("{}.json", id))
+    }
+
+    pub fn load(jobs_folder: &str, id: &str) -> Result<Self> {
+        utils::read_json(&Self::record_path(jobs_folder, id).to_string_lossy())
+    }
+
+    /// Writes the record to a temporary file first and renames it over the old one,
+    /// so a crash mid-write never leaves a truncated record behind.
+    pub fn save(&self, jobs_folder: &str) -> Result<()> {
+        fs::create_dir_all(jobs_folder)
+            .map_err(Error::io(format!("Failed to create {}", jobs_folder)))?;
+        let path = Self::record_path(jobs_folder, &self.id);
+        let tmp_path = path.with_extension("json.tmp");
+        utils::write_json(&tmp_path.to_string_lossy(), self)?;
+        fs::rename(&tmp_path, &path)
+            .map_err(Error::io(format!("Failed to save job {}", path.display())))
+    }
+
+    pub fn list(jobs_folder: &str) -> Result<Vec<Self>> {
+        let mut jobs = Vec::new();
+        if !Path::new(jobs_folder).exists() {
+            return Ok(jobs);
+        }
+
+        let entries = fs::read_dir(jobs_folder)
+            .map_err(Error::io(format!("Failed to list {}", jobs_folder)))?;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            match utils::read_json::<Job>(&path.to_string_lossy()) {
+                Ok(job) => jobs.push(job),
+                Err(e) => eprintln!("Skipping unreadable job record {}: {}", path.display(), e),
+            }
+        }
+
+        jobs.sort_by_key(|job| job.created_at);
+        Ok(jobs)
+    }
+
+    /// The oldest job that has not reached [`JobStage::Done`].
+    pub fn find_unfinished(jobs_folder: &str) -> Result<Option<Self>> {
+        Ok(Self::list(jobs_folder)?
+            .into_iter()
+            .find(|job| job.stage != JobStage::Done))
+    }
+
+    pub fn advance(&mut self, stage: JobStage, jobs_folder: &str) -> Result<()> {
+        self.stage = stage;
+        self.save(jobs_folder)
+    }
+
+    /// Parts that were interrupted mid-upload and need a manual check before retrying.
+    pub fn interrupted_parts(&self) -> Vec<&PartUpload> {
+        self.parts
+            .iter()
+            .filter(|part| part.status == UploadStatus::InProgress)
+            .collect()
+    }
+
+    pub fn all_parts_uploaded(&self) -> bool {
+        !self.parts.is_empty()
+            && self
+                .parts
+                .iter()
+                .all(|part| matches!(part.status, UploadStatus::Uploaded { .. }))
+    }
+}
+
+fn scoped_file(work_dir: &Path, original: &str) -> String {
+    let file_name = Path::new(original)
+        .file_name()
+        .map(|name| name.to_os_string())
+        .unwrap_or_else(|| original.into());
+    work_dir.join(file_name).to_string_lossy().into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{StylePool, VariantConfig};
+    use crate::metadata::Mood;
+    use crate::style::VideoStyle;
+
+    fn config_with_voice_variant() -> Config {
+        let mut config = Config::default();
+        config.experiment.name = "voices".to_string();
+        config.experiment.variants = vec![VariantConfig {
+            name: "heart".to_string(),
+            weight: 1,
+            metadata_prompt: None,
+            persona: None,
+            voice: Some("af_heart".to_string()),
+            font_size: None,
+            bg_color: None,
+        }];
+        config.style.enabled = true;
+        config.style.moods.insert(
+            Mood::Funny,
+            StylePool {
+                music: vec!["upbeat.mp3".to_string()],
+                voices: vec!["af_nicole".to_string()],
+                ..StylePool::default()
+            },
+        );
+        config
+    }
+
+    fn styled_job(config: &Config, style: VideoStyle) -> Job {
+        let mut job = Job::new(config);
+        job.experiment = Some(Assignment {
+            experiment: "voices".to_string(),
+            variant: "heart".to_string(),
+        });
+        let mut metadata: VideoMetadata = serde_json::from_value(serde_json::json!({
+            "title": "Title",
+            "description": "Description",
+            "keywords": [],
+            "mood": "funny"
+        }))
+        .unwrap();
+        metadata.style = Some(style);
+        job.metadata = Some(metadata);
+        job
+    }
+
+    #[test]
+    fn the_variant_voice_wins_over_the_style() {
+        let config = config_with_voice_variant();
+        // A style picked before the variant cleared the voice pools.
+        let job = styled_job(
+            &config,
+            VideoStyle {
+                music: Some("upbeat.mp3".to_string()),
+                voice: Some("af_nicole".to_string()),
+                ..VideoStyle::default()
+            },
+        );
+
+        let scoped = job.scoped_config(&config);
+        assert_eq!(scoped.audio.voice.as_deref(), Some("af_heart"));
+        assert_eq!(scoped.video.music.as_deref(), Some("upbeat.mp3"));
+
+        // Styles picked with the variant applied no longer choose a voice.
+        let metadata = job.metadata.as_ref().unwrap();
+        let picked = style::pick(&scoped.style, metadata);
+        assert_eq!(picked.voice, None);
+        assert_eq!(picked.music.as_deref(), Some("upbeat.mp3"));
+    }
+}
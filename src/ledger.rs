@@ -0,0 +1,281 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::confession::Confession;
+use crate::error::{Error, Result};
+use crate::utils;
+
+/// Number of hash functions in a MinHash signature; similarity estimates are
+/// accurate to roughly `1 / sqrt(SIGNATURE_LEN)`.
+const SIGNATURE_LEN: usize = 64;
+/// Words per shingle. Three words survive small edits while still telling
+/// different stories apart.
+const SHINGLE_WORDS: usize = 3;
+
+/// A confession that has already been turned into a video.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LedgerEntry {
+    /// SHA-256 of the normalized title and body.
+    pub hash: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub signature: Vec<u64>,
+    pub job_id: String,
+    pub used_at: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Duplicate {
+    SameId,
+    SameText,
+    /// Estimated Jaccard similarity of the word shingles.
+    Similar(f32),
+}
+
+impl std::fmt::Display for Duplicate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::SameId => write!(f, "same post id"),
+            Self::SameText => write!(f, "same text"),
+            Self::Similar(similarity) => write!(f, "{:.0}% similar", similarity * 100.0),
+        }
+    }
+}
+
+/// Persistent record of every confession already used, so none is picked twice.
+///
+/// Besides exact matches on post id and content hash, near-duplicates (reposts
+/// with small edits) are caught by comparing MinHash signatures of the text.
+pub struct Ledger {
+    path: String,
+    entries: Vec<LedgerEntry>,
+}
+
+impl Ledger {
+    /// Loads the ledger at `path`; a missing file is an empty ledger.
+    pub fn load(path: &str) -> Result<Self> {
+        let entries = if Path::new(path).exists() {
+            utils::read_json(path)?
+        } else {
+            Vec::new()
+        };
+        Ok(Self {
+            path: path.to_string(),
+            entries,
+        })
+    }
+
+    pub fn entries(&self) -> &[LedgerEntry] {
+        &self.entries
+    }
+
+    /// The first entry `confession` duplicates. Near-duplicates count when their
+    /// estimated similarity reaches `threshold`; a threshold above 1 disables them.
+    pub fn find_duplicate(
+        &self,
+        confession: &Confession,
+        threshold: f32,
+    ) -> Option<(Duplicate, &LedgerEntry)> {
+        let words = normalized_words(confession);
+        let hash = content_hash(&words);
+        let signature = minhash(&words);
+
+        self.entries.iter().find_map(|entry| {
+            let duplicate = if confession.id.is_some() && entry.id == confession.id {
+                Duplicate::SameId
+            } else if entry.hash == hash {
+                Duplicate::SameText
+            } else {
+                let similarity = similarity(&signature, &entry.signature);
+                if similarity < threshold {
+                    return None;
+                }
+                Duplicate::Similar(similarity)
+            };
+            Some((duplicate, entry))
+        })
+    }
+
+    pub fn record(&mut self, confession: &Confession, job_id: &str) {
+        let words = normalized_words(confession);
+        let used_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+
+        self.entries.push(LedgerEntry {
+            hash: content_hash(&words),
+            id: confession.id.clone(),
+            signature: minhash(&words),
+            job_id: job_id.to_string(),
+            used_at,
+        });
+    }
+
+    /// Writes to a temporary file and renames it, like job records.
+    pub fn save(&self) -> Result<()> {
+        if let Some(parent) = Path::new(&self.path).parent() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent)
+                    .map_err(Error::io(format!("Failed to create {}", parent.display())))?;
+            }
+        }
+        let tmp_path = format!("{}.tmp", self.path);
+        utils::write_json(&tmp_path, &self.entries)?;
+        fs::rename(&tmp_path, &self.path)
+            .map_err(Error::io(format!("Failed to save {}", self.path)))
+    }
+}
+
+/// Lowercase alphanumeric words of the title and body, so whitespace, case and
+/// punctuation edits do not change the hash.
+fn normalized_words(confession: &Confession) -> Vec<String> {
+    confession
+        .script()
+        .split(|c: char| !c.is_alphanumeric() && c != '\'')
+        .map(|word| word.replace('\'', "").to_lowercase())
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+fn content_hash(words: &[String]) -> String {
+    Sha256::digest(words.join(" ").as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// FNV-1a; the ledger is persisted, so the hash must not change between builds.
+fn stable_hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// SplitMix64 finalizer, used to derive the independent hash functions.
+fn mix(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+fn minhash(words: &[String]) -> Vec<u64> {
+    let shingles = words
+        .windows(SHINGLE_WORDS.min(words.len()).max(1))
+        .map(|window| stable_hash(window.join(" ").as_bytes()));
+
+    let mut signature = vec![u64::MAX; SIGNATURE_LEN];
+    for shingle in shingles {
+        for (i, slot) in signature.iter_mut().enumerate() {
+            *slot = (*slot).min(mix(shingle ^ mix(i as u64)));
+        }
+    }
+    signature
+}
+
+fn similarity(a: &[u64], b: &[u64]) -> f32 {
+    if a.is_empty() || a.len() != b.len() {
+        return 0.0;
+    }
+    let equal = a.iter().zip(b).filter(|(x, y)| x == y).count();
+    equal as f32 / a.len() as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::confession;
+
+    const STORY: &str = "I borrowed my roommate's car for a weekend trip and scraped the whole \
+                         side against a pillar in the parking garage. I paid a shop to fix it \
+                         before she got back and she still has no idea it ever happened.";
+
+    fn post(id: Option<&str>, title: &str, body: &str) -> Confession {
+        confession::confession_from_fields(|field| match field {
+            "id" => id.map(String::from),
+            "title" => Some(title.to_string()),
+            "selftext" => Some(body.to_string()),
+            _ => None,
+        })
+        .unwrap()
+    }
+
+    fn ledger_with(used: &Confession) -> Ledger {
+        let mut ledger = Ledger {
+            path: String::new(),
+            entries: Vec::new(),
+        };
+        ledger.record(used, "job-1");
+        ledger
+    }
+
+    #[test]
+    fn minhash_similarity_tracks_shared_wording() {
+        let words = normalized_words(&post(None, "My secret", STORY));
+        let edited = normalized_words(&post(None, "My secret", &STORY.replace("weekend", "week")));
+        let other = normalized_words(&post(
+            None,
+            "Work",
+            "I have been pretending to understand spreadsheets at my new job for a whole \
+             year and my manager keeps giving me more of them to maintain every month.",
+        ));
+
+        assert_eq!(similarity(&minhash(&words), &minhash(&words)), 1.0);
+        assert!(similarity(&minhash(&words), &minhash(&edited)) > 0.7);
+        assert!(similarity(&minhash(&words), &minhash(&other)) < 0.2);
+        assert_eq!(similarity(&minhash(&words), &[]), 0.0);
+    }
+
+    #[test]
+    fn finds_duplicates_by_id_text_and_similarity() {
+        let ledger = ledger_with(&post(Some("abc"), "My secret", STORY));
+
+        let same_id = post(Some("abc"), "Another", "Entirely different words here.");
+        assert_eq!(
+            ledger.find_duplicate(&same_id, 0.8).unwrap().0,
+            Duplicate::SameId
+        );
+
+        let reformatted = post(None, "MY SECRET!", &STORY.replace(". ", "...  "));
+        assert_eq!(
+            ledger.find_duplicate(&reformatted, 0.8).unwrap().0,
+            Duplicate::SameText
+        );
+
+        let repost = post(Some("xyz"), "My secret", &STORY.replace("weekend", "week"));
+        assert!(matches!(
+            ledger.find_duplicate(&repost, 0.7),
+            Some((Duplicate::Similar(_), _))
+        ));
+        assert!(ledger.find_duplicate(&repost, 1.1).is_none());
+    }
+
+    #[test]
+    fn saved_ledgers_load_back() {
+        let dir = std::env::temp_dir().join(format!("ledger-{}", std::process::id()));
+        let path = dir.join("nested").join("used.json");
+        let path = path.to_string_lossy().into_owned();
+        let used = post(Some("abc"), "My secret", STORY);
+
+        let mut ledger = Ledger::load(&path).unwrap();
+        assert!(ledger.entries().is_empty());
+        ledger.record(&used, "job-1");
+        ledger.save().unwrap();
+
+        let loaded = Ledger::load(&path).unwrap();
+        fs::remove_dir_all(dir).unwrap();
+        assert_eq!(loaded.entries().len(), 1);
+        let entry = &loaded.entries()[0];
+        assert_eq!(entry.job_id, "job-1");
+        assert_eq!(entry.id.as_deref(), Some("abc"));
+        assert_eq!(entry.signature, ledger.entries()[0].signature);
+        assert_eq!(
+            loaded.find_duplicate(&used, 2.0).unwrap().0,
+            Duplicate::SameId
+        );
+    }
+}
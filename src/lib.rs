@@ -0,0 +1,46 @@
+//! Turns Reddit confessions into narrated, captioned short videos and uploads them.
+//!
+//! [`Pipeline`] runs the whole flow or any single stage of it; the modules below
+//! expose the individual operations for tools that need finer control.
+
+pub mod classifier;
+pub mod confession;
+pub mod config;
+pub mod csv_index;
+pub mod error;
+pub mod experiment;
+pub mod job;
+pub mod ledger;
+pub mod llm;
+pub mod llm_cache;
+pub mod localize;
+pub mod metadata;
+pub mod ndjson_source;
+pub mod normalizer;
+pub mod ollama;
+pub mod openai;
+pub mod outro;
+pub mod pipeline;
+pub mod profanity;
+pub mod prompts;
+pub mod rewriter;
+pub mod rules;
+pub mod scheduler;
+pub mod source;
+pub mod splitter;
+pub mod sqlite_source;
+pub mod style;
+pub mod tts;
+pub mod upload;
+pub mod utils;
+pub mod validator;
+pub mod video;
+// mod video_generator;
+
+pub use confession::Confession;
+pub use config::Config;
+pub use error::{Error, Recovery, Result};
+pub use job::Job;
+pub use metadata::VideoMetadata;
+pub use pipeline::{Pipeline, PipelineBuilder, RunOptions};
+pub use source::ConfessionSource;
@@ -0,0 +1,317 @@
+use futures::future::BoxFuture;
+use serde::Deserialize;
+use std::fs;
+
+use crate::config::{Config, LlmBackendKind, LlmConfig};
+use crate::error::{Error, Result};
+use crate::llm_cache::CachedBackend;
+use crate::metadata::{MetadataResponse, Mood, StoryCategory, VideoMetadata};
+use crate::ollama::OllamaBackend;
+use crate::openai::OpenAiBackend;
+use crate::prompts::PromptTemplate;
+use crate::validator::Validator;
+
+/// Something that answers a single-turn chat: a system prompt and a user prompt.
+pub trait LlmBackend: Send + Sync {
+    fn chat<'a>(&'a self, system_prompt: &'a str, prompt: &'a str)
+        -> BoxFuture<'a, Result<String>>;
+
+    /// Like [`chat`](Self::chat), but asks for a JSON object matching `schema`.
+    /// Backends without structured output only get the prompt, which should
+    /// describe the object too.
+    fn chat_json<'a>(
+        &'a self,
+        system_prompt: &'a str,
+        prompt: &'a str,
+        schema: &'a serde_json::Value,
+    ) -> BoxFuture<'a, Result<String>> {
+        let _ = schema;
+        self.chat(system_prompt, prompt)
+    }
+
+    /// Marks `reply` as one the caller accepted. Caching backends only keep
+    /// accepted replies, so a rejected one is asked for again on the next try.
+    fn commit(&self, reply: &str) {
+        let _ = reply;
+    }
+
+    /// Identifies the backend and model in logs, e.g. `ollama/llama3.2`.
+    fn describe(&self) -> String;
+}
+
+/// Opens the backend selected by `llm.backend`, behind the reply cache when
+/// `llm.cache` is on.
+pub fn open(config: &LlmConfig) -> Result<Box<dyn LlmBackend>> {
+    let backend: Box<dyn LlmBackend> = match config.backend {
+        LlmBackendKind::Ollama => Box::new(OllamaBackend::new(config)?),
+        LlmBackendKind::Openai => Box::new(OpenAiBackend::new(config)?),
+        // Fixtures are already instant and deterministic.
+        LlmBackendKind::Mock => return Ok(Box::new(MockBackend::open(config)?)),
+    };
+    if !config.cache {
+        return Ok(backend);
+    }
+    Ok(Box::new(CachedBackend::new(backend, config)))
+}
+
+/// One canned answer of a [`MockBackend`] fixture file.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Fixture {
+    /// Case-insensitive substring of the system prompt or the user prompt;
+    /// empty matches everything.
+    #[serde(default, rename = "match")]
+    pub pattern: String,
+    pub response: String,
+}
+
+/// Answers from fixtures instead of a model, so runs are deterministic and need
+/// no server. The first fixture matching the prompts wins; without a match the
+/// answer is `llm.mock_response`.
+pub struct MockBackend {
+    fixtures: Vec<Fixture>,
+    fallback: String,
+}
+
+impl MockBackend {
+    pub fn new(fixtures: Vec<Fixture>, fallback: impl Into<String>) -> Self {
+        Self {
+            fixtures,
+            fallback: fallback.into(),
+        }
+    }
+
+    /// Reads `llm.fixtures_path`, a JSON array of `{"match": ..., "response": ...}`.
+    pub fn open(config: &LlmConfig) -> Result<Self> {
+        let fixtures = match &config.fixtures_path {
+            Some(path) => {
+                let text = fs::read_to_string(path)
+                    .map_err(Error::io(format!("Failed to read LLM fixtures {}", path)))?;
+                serde_json::from_str(&text)
+                    .map_err(|e| Error::Llm(format!("Invalid LLM fixtures {}: {}", path, e)))?
+            }
+            None => Vec::new(),
+        };
+        Ok(Self::new(fixtures, config.mock_response.clone()))
+    }
+
+    fn answer(&self, system_prompt: &str, prompt: &str) -> String {
+        let system_prompt = system_prompt.to_lowercase();
+        let prompt = prompt.to_lowercase();
+        self.fixtures
+            .iter()
+            .find(|fixture| {
+                let pattern = fixture.pattern.to_lowercase();
+                system_prompt.contains(&pattern) || prompt.contains(&pattern)
+            })
+            .map_or_else(|| self.fallback.clone(), |fixture| fixture.response.clone())
+    }
+}
+
+impl LlmBackend for MockBackend {
+    fn chat<'a>(
+        &'a self,
+        system_prompt: &'a str,
+        prompt: &'a str,
+    ) -> BoxFuture<'a, Result<String>> {
+        let answer = self.answer(system_prompt, prompt);
+        Box::pin(async move { Ok(answer) })
+    }
+
+    fn describe(&self) -> String {
+        format!("mock ({} fixtures)", self.fixtures.len())
+    }
+}
+
+/// Sends one prompt to the configured backend. Any answer but a refusal is
+/// accepted.
+pub async fn complete(config: &Config, system_prompt: &str, prompt: &str) -> Result<String> {
+    let backend = open(&config.llm)?;
+    let reply = backend.chat(system_prompt, prompt).await?;
+    if !Validator::new(config, system_prompt)
+        .check_reply(&reply)
+        .refused()
+    {
+        backend.commit(&reply);
+    }
+    Ok(reply)
+}
+
+/// Generates all the upload metadata in one structured call with the
+/// `prompts.metadata` template, recording its version. Malformed replies
+/// and ones the [`Validator`] finds fault with are retried up to
+/// `llm.max_retries` times, with the problem fed back to the model. A refusal
+/// fails at once, since asking again about the same confession rarely helps.
+pub async fn generate_metadata(config: &Config, script: &str) -> Result<VideoMetadata> {
+    let backend = open(&config.llm)?;
+    let template = PromptTemplate::load(&config.prompts, &config.prompts.metadata)?;
+    let required_tags = config
+        .validate
+        .required_hashtags
+        .iter()
+        .map(|tag| format!("#{}", tag.trim_start_matches('#')))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let categories = StoryCategory::ALL.map(StoryCategory::key).join(", ");
+    let moods = Mood::ALL.map(Mood::key).join(", ");
+    let max_chars = config.prompts.title_chars.to_string();
+    let rendered = template.render(&[
+        ("script", script),
+        ("persona", &config.prompts.persona),
+        ("max_chars", &max_chars),
+        ("required_tags", &required_tags),
+        ("categories", &categories),
+        ("moods", &moods),
+    ])?;
+
+    let validator = Validator::new(config, &rendered.system);
+    let schema = MetadataResponse::schema();
+    let mut prompt = rendered.user.clone();
+    let mut last_problem = String::new();
+
+    for attempt in 1..=config.llm.max_retries {
+        let reply = backend
+            .chat_json(&rendered.system, &prompt, &schema)
+            .await?;
+        let metadata = MetadataResponse::parse(&reply).and_then(MetadataResponse::into_metadata);
+        let report = match &metadata {
+            Ok(metadata) => validator.check(metadata),
+            Err(_) => validator.check_reply(&reply),
+        };
+        if report.refused() {
+            return Err(Error::Llm(format!(
+                "{} refused to describe this confession: {}",
+                backend.describe(),
+                report
+            )));
+        }
+        let problem = match metadata {
+            Ok(mut metadata) if report.passed() => {
+                backend.commit(&reply);
+                metadata.prompt_version = Some(template.version_id());
+                return Ok(metadata);
+            }
+            Ok(_) => report.to_string(),
+            Err(problem) => problem,
+        };
+
+        eprintln!(
+            "Metadata reply {} of {} was invalid: {}",
+            attempt, config.llm.max_retries, problem
+        );
+        prompt = format!(
+            "{}\n\nYour previous reply was:\n{}\n\nIt was invalid: {}. Reply again with \
+             only the corrected JSON object.",
+            rendered.user, reply, problem
+        );
+        last_problem = problem;
+    }
+
+    Err(Error::Llm(format!(
+        "No valid metadata after {} replies from {}: {}",
+        config.llm.max_retries,
+        backend.describe(),
+        last_problem
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const GOOD: &str = r#"```json
+{"title": "I Hid My Brother's Car Keys For A Week",
+ "description": "A guilty secret about a missing set of keys and the brother who never found out.",
+ "hashtags": ["shorts", "RedditConfessions", "secrets"],
+ "tags": ["confession", "siblings", "keys"],
+ "category": "family", "mood": "funny",
+ "hook": "My brother still thinks he lost his keys that week."}
+```"#;
+
+    /// A mock-backed config answering from `fixtures`, or `fallback` otherwise.
+    fn mock_config(name: &str, fixtures: serde_json::Value, fallback: &str) -> Config {
+        let path = std::env::temp_dir().join(format!("llm-{name}-{}.json", std::process::id()));
+        fs::write(&path, fixtures.to_string()).unwrap();
+        let mut config = Config::default();
+        config.llm.backend = LlmBackendKind::Mock;
+        config.llm.fixtures_path = Some(path.to_string_lossy().into_owned());
+        config.llm.mock_response = fallback.to_string();
+        config
+    }
+
+    fn remove_fixtures(config: &Config) {
+        fs::remove_file(config.llm.fixtures_path.as_ref().unwrap()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn complete_answers_from_the_first_matching_fixture() {
+        let config = mock_config(
+            "complete",
+            json!([
+                { "match": "TRANSLATE", "response": "Bonjour" },
+                { "match": "", "response": "Anything else" }
+            ]),
+            "unused",
+        );
+        let translated = complete(&config, "You translate.", "Hello").await.unwrap();
+        let other = complete(&config, "You summarize.", "Hello").await.unwrap();
+        remove_fixtures(&config);
+
+        assert_eq!(translated, "Bonjour");
+        assert_eq!(other, "Anything else");
+    }
+
+    #[tokio::test]
+    async fn invalid_metadata_is_asked_for_again() {
+        // Only the retry prompt quotes the previous reply.
+        let config = mock_config(
+            "retry",
+            json!([{ "match": "your previous reply was", "response": GOOD }]),
+            r#"{"title": "", "description": "Oops #tag"}"#,
+        );
+        let metadata = generate_metadata(&config, "I hid my brother's keys.").await;
+        remove_fixtures(&config);
+
+        let metadata = metadata.unwrap();
+        assert_eq!(metadata.title, "I Hid My Brother's Car Keys For A Week");
+        assert_eq!(
+            metadata.hashtags,
+            ["shorts", "redditconfessions", "secrets"]
+        );
+        assert_eq!(metadata.category, Some(StoryCategory::Family));
+        assert!(metadata
+            .prompt_version
+            .is_some_and(|version| version.starts_with("metadata@")));
+    }
+
+    #[tokio::test]
+    async fn metadata_gives_up_after_max_retries() {
+        let mut config = mock_config("exhausted", json!([]), "Here you go: no JSON today.");
+        config.llm.max_retries = 2;
+        let error = generate_metadata(&config, "I hid my brother's keys.").await;
+        remove_fixtures(&config);
+
+        let error = error.unwrap_err().to_string();
+        assert!(
+            error.contains("No valid metadata after 2 replies"),
+            "{error}"
+        );
+        assert!(error.contains("no JSON object"), "{error}");
+    }
+
+    #[tokio::test]
+    async fn a_refusal_fails_without_retrying() {
+        // Were it retried, the retry fixture would have produced valid metadata.
+        let config = mock_config(
+            "refusal",
+            json!([{ "match": "your previous reply was", "response": GOOD }]),
+            "I'm sorry, but I can't help with that request.",
+        );
+        let error = generate_metadata(&config, "I hid my brother's keys.").await;
+        remove_fixtures(&config);
+
+        let error = error.unwrap_err().to_string();
+        assert!(error.contains("refused"), "{error}");
+    }
+}
@@ -0,0 +1,279 @@
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config::LlmConfig;
+use crate::error::{Error, Result};
+use crate::llm::LlmBackend;
+
+/// Bumped when the key layout changes, so old entries are never misread.
+const KEY_VERSION: &str = "1";
+
+/// Wraps a backend and keeps its replies in `llm.cache_dir`, one JSON file per
+/// request. The key covers the backend and model, the sampling options, both
+/// prompts and the schema; the system prompt is the rendered template, so a new
+/// template version never hits an old entry.
+///
+/// Fresh replies are only written once the caller accepts them with
+/// [`commit`](LlmBackend::commit), so a refusal or malformed reply is never
+/// replayed to a retry.
+///
+/// The cache is best effort: unreadable or unwritable entries are reported and
+/// the backend is asked instead.
+pub struct CachedBackend {
+    inner: Box<dyn LlmBackend>,
+    directory: PathBuf,
+    options: String,
+    ttl_secs: u64,
+    bypass: bool,
+    /// Entry paths and replies fetched from the backend but not yet committed.
+    pending: Mutex<Vec<(PathBuf, String)>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Entry {
+    created_at: u64,
+    backend: String,
+    reply: String,
+}
+
+impl CachedBackend {
+    pub fn new(inner: Box<dyn LlmBackend>, config: &LlmConfig) -> Self {
+        let options = serde_json::json!({
+            "temperature": config.temperature,
+            "seed": config.seed,
+            "max_tokens": config.max_tokens,
+            "context_size": config.context_size,
+        });
+        Self {
+            inner,
+            directory: PathBuf::from(&config.cache_dir),
+            options: options.to_string(),
+            ttl_secs: config.cache_ttl_hours * 3600,
+            bypass: config.cache_bypass,
+            pending: Mutex::new(Vec::new()),
+        }
+    }
+
+    fn key(&self, system_prompt: &str, prompt: &str, schema: Option<&serde_json::Value>) -> String {
+        let schema = schema.map(|schema| schema.to_string()).unwrap_or_default();
+        let mut hasher = Sha256::new();
+        for part in [
+            KEY_VERSION,
+            &self.inner.describe(),
+            &self.options,
+            system_prompt,
+            prompt,
+            &schema,
+        ] {
+            // Length prefixes keep ("ab", "c") and ("a", "bc") apart.
+            hasher.update((part.len() as u64).to_le_bytes());
+            hasher.update(part.as_bytes());
+        }
+        hasher
+            .finalize()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
+    /// Entries are spread over subfolders named after the first two hex digits.
+    fn entry_path(&self, key: &str) -> PathBuf {
+        self.directory.join(&key[..2]).join(format!("{}.json", key))
+    }
+
+    fn lookup(&self, path: &Path) -> Option<String> {
+        if self.bypass {
+            return None;
+        }
+        let text = fs::read_to_string(path).ok()?;
+        let entry: Entry = match serde_json::from_str(&text) {
+            Ok(entry) => entry,
+            Err(e) => {
+                eprintln!(
+                    "Ignoring unreadable LLM cache entry {}: {}",
+                    path.display(),
+                    e
+                );
+                return None;
+            }
+        };
+        let expired = self.ttl_secs > 0 && now().saturating_sub(entry.created_at) > self.ttl_secs;
+        (!expired).then_some(entry.reply)
+    }
+
+    /// Writes a temporary file and renames it, so a concurrent reader never
+    /// sees half an entry.
+    fn store(&self, path: &Path, reply: &str) -> Result<()> {
+        let folder = path.parent().unwrap_or(&self.directory);
+        fs::create_dir_all(folder)
+            .map_err(Error::io(format!("Failed to create {}", folder.display())))?;
+        let entry = Entry {
+            created_at: now(),
+            backend: self.inner.describe(),
+            reply: reply.to_string(),
+        };
+        let content = serde_json::to_string_pretty(&entry)
+            .map_err(|e| Error::State(format!("Failed to serialize LLM cache entry: {}", e)))?;
+        let tmp_path = path.with_extension(format!("json.{}.tmp", std::process::id()));
+        fs::write(&tmp_path, content)
+            .map_err(Error::io(format!("Failed to write {}", tmp_path.display())))?;
+        fs::rename(&tmp_path, path).map_err(Error::io(format!("Failed to save {}", path.display())))
+    }
+
+    async fn cached(
+        &self,
+        system_prompt: &str,
+        prompt: &str,
+        schema: Option<&serde_json::Value>,
+    ) -> Result<String> {
+        let path = self.entry_path(&self.key(system_prompt, prompt, schema));
+        if let Some(reply) = self.lookup(&path) {
+            return Ok(reply);
+        }
+
+        let reply = match schema {
+            Some(schema) => self.inner.chat_json(system_prompt, prompt, schema).await?,
+            None => self.inner.chat(system_prompt, prompt).await?,
+        };
+        self.pending
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push((path, reply.clone()));
+        Ok(reply)
+    }
+}
+
+impl LlmBackend for CachedBackend {
+    fn chat<'a>(
+        &'a self,
+        system_prompt: &'a str,
+        prompt: &'a str,
+    ) -> BoxFuture<'a, Result<String>> {
+        Box::pin(self.cached(system_prompt, prompt, None))
+    }
+
+    fn chat_json<'a>(
+        &'a self,
+        system_prompt: &'a str,
+        prompt: &'a str,
+        schema: &'a serde_json::Value,
+    ) -> BoxFuture<'a, Result<String>> {
+        Box::pin(self.cached(system_prompt, prompt, Some(schema)))
+    }
+
+    /// Writes the pending entries with this reply. Replies served from the
+    /// cache are already stored and keep their original age.
+    fn commit(&self, reply: &str) {
+        let accepted = {
+            let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+            let (accepted, rest) = pending
+                .drain(..)
+                .partition::<Vec<_>, _>(|(_, pending_reply)| pending_reply == reply);
+            *pending = rest;
+            accepted
+        };
+        for (path, reply) in accepted {
+            if let Err(e) = self.store(&path, &reply) {
+                eprintln!("{}. Continuing without caching the reply", e);
+            }
+        }
+    }
+
+    fn describe(&self) -> String {
+        format!("{} (cached)", self.inner.describe())
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    /// Answers `reply 1`, `reply 2`, ... so each real call is visible.
+    struct Counting(Arc<AtomicUsize>);
+
+    impl LlmBackend for Counting {
+        fn chat<'a>(&'a self, _: &'a str, _: &'a str) -> BoxFuture<'a, Result<String>> {
+            let call = self.0.fetch_add(1, Ordering::SeqCst) + 1;
+            Box::pin(async move { Ok(format!("reply {}", call)) })
+        }
+
+        fn describe(&self) -> String {
+            "counting".to_string()
+        }
+    }
+
+    fn cached(name: &str) -> (CachedBackend, Arc<AtomicUsize>, PathBuf) {
+        let directory =
+            std::env::temp_dir().join(format!("llm-cache-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        let config = LlmConfig {
+            cache_dir: directory.to_string_lossy().into_owned(),
+            ..LlmConfig::default()
+        };
+        let calls = Arc::new(AtomicUsize::new(0));
+        let backend = CachedBackend::new(Box::new(Counting(calls.clone())), &config);
+        (backend, calls, directory)
+    }
+
+    #[tokio::test]
+    async fn uncommitted_replies_are_not_stored() {
+        let (backend, calls, directory) = cached("uncommitted");
+        assert_eq!(backend.chat("system", "prompt").await.unwrap(), "reply 1");
+        assert_eq!(backend.chat("system", "prompt").await.unwrap(), "reply 2");
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert!(!directory.exists());
+    }
+
+    #[tokio::test]
+    async fn committed_replies_are_served_to_identical_requests() {
+        let (backend, calls, directory) = cached("committed");
+        let reply = backend.chat("system", "prompt").await.unwrap();
+        backend.commit(&reply);
+
+        assert_eq!(backend.chat("system", "prompt").await.unwrap(), "reply 1");
+        assert_eq!(
+            backend.chat("system", "other prompt").await.unwrap(),
+            "reply 2"
+        );
+        let schema = serde_json::json!({ "type": "object" });
+        assert_eq!(
+            backend
+                .chat_json("system", "prompt", &schema)
+                .await
+                .unwrap(),
+            "reply 3"
+        );
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn expired_entries_are_fetched_again() {
+        let (backend, calls, directory) = cached("expired");
+        let reply = backend.chat("system", "prompt").await.unwrap();
+        backend.commit(&reply);
+
+        let path = backend.entry_path(&backend.key("system", "prompt", None));
+        let mut entry: Entry = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        entry.created_at -= backend.ttl_secs + 1;
+        fs::write(&path, serde_json::to_string(&entry).unwrap()).unwrap();
+
+        assert_eq!(backend.chat("system", "prompt").await.unwrap(), "reply 2");
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
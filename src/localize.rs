@@ -0,0 +1,318 @@
+use std::path::Path;
+
+use crate::config::{Config, LanguageConfig, TtsEngineKind};
+use crate::error::{Error, Result};
+use crate::llm;
+use crate::metadata::{MetadataResponse, VideoMetadata};
+use crate::prompts::PromptTemplate;
+use crate::validator::{Field, Validator};
+
+/// Kokoro voice and language code for each language it speaks, by ISO 639-3 code.
+const KOKORO_VOICES: &[(&str, &str, &str)] = &[
+    ("eng", "af_bella", "a"),
+    ("spa", "ef_dora", "e"),
+    ("fra", "ff_siwis", "f"),
+    ("ita", "if_sara", "i"),
+    ("por", "pf_dora", "p"),
+    ("hin", "hf_alpha", "h"),
+    ("jpn", "jf_alpha", "j"),
+    ("cmn", "zf_xiaobei", "z"),
+];
+
+/// espeak-ng voice for each language, by ISO 639-3 code.
+const ESPEAK_VOICES: &[(&str, &str)] = &[
+    ("eng", "en-us"),
+    ("spa", "es"),
+    ("fra", "fr"),
+    ("ita", "it"),
+    ("por", "pt"),
+    ("deu", "de"),
+    ("nld", "nl"),
+    ("pol", "pl"),
+    ("rus", "ru"),
+    ("tur", "tr"),
+    ("hin", "hi"),
+    ("jpn", "ja"),
+    ("cmn", "cmn"),
+];
+
+/// A translation shorter than this share of the original's characters was most
+/// likely cut off.
+const MIN_LENGTH_RATIO: f32 = 0.25;
+
+/// The `localize.languages` entry with ISO 639-3 `code`.
+pub fn language<'a>(config: &'a Config, code: &str) -> Option<&'a LanguageConfig> {
+    config
+        .localize
+        .languages
+        .iter()
+        .find(|language| language.code.trim() == code)
+}
+
+/// The language's name for the prompts, e.g. `Spanish`.
+pub fn name(language: &LanguageConfig) -> String {
+    match &language.name {
+        Some(name) => name.trim().to_string(),
+        None => whatlang::Lang::from_code(language.code.trim())
+            .map_or_else(|| language.code.clone(), |lang| lang.eng_name().to_string()),
+    }
+}
+
+/// The voice and, for Kokoro, the language code `engine` narrates the language
+/// in, from the entry or else the engine's built-in table. `None` when neither
+/// covers the language; Piper voices are model files, so they always come from
+/// the entry, and the silent engine needs none.
+pub fn voice(engine: TtsEngineKind, language: &LanguageConfig) -> Option<(String, Option<String>)> {
+    let code = language.code.trim();
+    match engine {
+        TtsEngineKind::Kokoro => {
+            let built_in = KOKORO_VOICES.iter().find(|(known, _, _)| *known == code);
+            let voice = language
+                .voice
+                .clone()
+                .or_else(|| built_in.map(|(_, voice, _)| voice.to_string()))?;
+            let lang_code = language
+                .lang_code
+                .clone()
+                .or_else(|| built_in.map(|(_, _, lang_code)| lang_code.to_string()))?;
+            Some((voice, Some(lang_code)))
+        }
+        TtsEngineKind::Espeak => {
+            let voice = language.voice.clone().or_else(|| {
+                ESPEAK_VOICES
+                    .iter()
+                    .find(|(known, _)| *known == code)
+                    .map(|(_, voice)| voice.to_string())
+            })?;
+            Some((voice, None))
+        }
+        TtsEngineKind::Piper => Some((language.voice.clone()?, None)),
+        TtsEngineKind::Silent => language.voice.clone().map(|voice| (voice, None)),
+    }
+}
+
+/// The language's episode counter: `episode_file`, or `paths.episode_file` with
+/// the code appended to the file name.
+pub fn episode_file(config: &Config, language: &LanguageConfig) -> String {
+    if let Some(file) = &language.episode_file {
+        return file.clone();
+    }
+    let original = Path::new(&config.paths.episode_file);
+    let stem = original
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let file_name = match original.extension() {
+        Some(extension) => format!(
+            "{}_{}.{}",
+            stem,
+            language.code.trim(),
+            extension.to_string_lossy()
+        ),
+        None => format!("{}_{}", stem, language.code.trim()),
+    };
+    original
+        .with_file_name(file_name)
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Narrates, validates and uploads in `language` instead of the original one.
+///
+/// The translated narration is final: it is not normalized again, since the
+/// `[normalize]` rules are English, and it already carries the translated
+/// content warning, so none is prepended.
+pub fn apply(language: &LanguageConfig, config: &mut Config) {
+    if let Some((voice, lang_code)) = voice(config.audio.engine, language) {
+        config.audio.voice = Some(voice);
+        config.audio.lang_code = lang_code;
+    }
+    config.paths.episode_file = episode_file(config, language);
+    config.validate.language = language.code.trim().to_string();
+    if let Some(hashtags) = &language.required_hashtags {
+        config.validate.required_hashtags = hashtags.clone();
+    }
+    if let Some(series_title) = &language.series_title {
+        config.upload.series_title = series_title.clone();
+    }
+    if let Some(part_label) = &language.part_label {
+        config.upload.part_label = part_label.clone();
+    }
+    if let Some(privacy) = &language.privacy {
+        config.upload.privacy = privacy.clone();
+    }
+    if let Some(playlist_id) = &language.playlist_id {
+        config.upload.playlist_id = playlist_id.clone();
+    }
+    if language.credentials.is_some() {
+        config.upload.credentials = language.credentials.clone();
+    }
+    config.normalize.enabled = false;
+    config.classify.warn.clear();
+}
+
+/// Translates spoken text with the `prompts.translate` template. `config` is
+/// the config with [`apply`] done, so the [`Validator`] checks the translation
+/// is in the target language. Faulty translations are retried up to
+/// `llm.max_retries` times with the problem fed back; a refusal fails at once.
+pub async fn translate_script(
+    config: &Config,
+    language: &LanguageConfig,
+    field: Field,
+    text: &str,
+) -> Result<String> {
+    let template = PromptTemplate::load(&config.prompts, &config.prompts.translate)?;
+    let rendered = template.render(&[("language", &name(language)), ("script", text)])?;
+
+    let backend = llm::open(&config.llm)?;
+    let validator = Validator::new(config, &rendered.system);
+    let min_chars = (text.chars().count() as f32 * MIN_LENGTH_RATIO) as usize;
+    let mut prompt = rendered.user.clone();
+    let mut last_problem = String::new();
+
+    for attempt in 1..=config.llm.max_retries {
+        let reply = backend.chat(&rendered.system, &prompt).await?;
+        let translation = reply.split_whitespace().collect::<Vec<_>>().join(" ");
+
+        let report = validator.check_script(field, &translation, text);
+        if report.refused() {
+            return Err(Error::Llm(format!(
+                "{} refused to translate into {}: {}",
+                backend.describe(),
+                name(language),
+                report
+            )));
+        }
+        let mut problems: Vec<String> = report.issues.iter().map(|i| i.to_string()).collect();
+        if translation.is_empty() {
+            problems.push("it is empty".to_string());
+        } else if translation.chars().count() < min_chars {
+            problems.push("it is much shorter than the original; translate all of it".to_string());
+        }
+        if problems.is_empty() {
+            backend.commit(&reply);
+            return Ok(translation);
+        }
+
+        let problem = problems.join("; ");
+        eprintln!(
+            "{} translation {} of {} was rejected: {}",
+            name(language),
+            attempt,
+            config.llm.max_retries,
+            problem
+        );
+        prompt = format!(
+            "{}\n\nYour previous translation was:\n{}\n\nIt was rejected: {}. Translate it again.",
+            rendered.user, translation, problem
+        );
+        last_problem = problem;
+    }
+
+    Err(Error::Llm(format!(
+        "No acceptable {} translation after {} replies from {}: {}",
+        name(language),
+        config.llm.max_retries,
+        backend.describe(),
+        last_problem
+    )))
+}
+
+/// Translates the upload metadata with the `prompts.translate_metadata`
+/// template, keeping the category, mood, style and content labels of the
+/// original. Retried like [`generate_metadata`](crate::llm::generate_metadata).
+pub async fn translate_metadata(
+    config: &Config,
+    language: &LanguageConfig,
+    metadata: &VideoMetadata,
+) -> Result<VideoMetadata> {
+    let template = PromptTemplate::load(&config.prompts, &config.prompts.translate_metadata)?;
+    let original = serde_json::json!({
+        "title": metadata.title,
+        "description": metadata.description,
+        "hashtags": metadata.hashtags,
+        "tags": metadata.keywords,
+        "category": metadata.category,
+        "mood": metadata.mood,
+        "hook": metadata.hook,
+    });
+    let original = serde_json::to_string_pretty(&original)
+        .map_err(|e| Error::State(format!("Failed to serialize metadata: {}", e)))?;
+    let required_tags = config
+        .validate
+        .required_hashtags
+        .iter()
+        .map(|tag| format!("#{}", tag.trim_start_matches('#')))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let max_chars = config.prompts.title_chars.to_string();
+    let rendered = template.render(&[
+        ("language", &name(language)),
+        ("metadata", &original),
+        ("max_chars", &max_chars),
+        ("required_tags", &required_tags),
+    ])?;
+
+    let backend = llm::open(&config.llm)?;
+    let validator = Validator::new(config, &rendered.system);
+    let schema = MetadataResponse::schema();
+    let mut prompt = rendered.user.clone();
+    let mut last_problem = String::new();
+
+    for attempt in 1..=config.llm.max_retries {
+        let reply = backend
+            .chat_json(&rendered.system, &prompt, &schema)
+            .await?;
+        let translated = MetadataResponse::parse(&reply).and_then(MetadataResponse::into_metadata);
+        let report = match &translated {
+            Ok(translated) => validator.check(translated),
+            Err(_) => validator.check_reply(&reply),
+        };
+        if report.refused() {
+            return Err(Error::Llm(format!(
+                "{} refused to translate the metadata into {}: {}",
+                backend.describe(),
+                name(language),
+                report
+            )));
+        }
+        let problem = match translated {
+            Ok(translated) if report.passed() => {
+                backend.commit(&reply);
+                return Ok(VideoMetadata {
+                    category: metadata.category.or(translated.category),
+                    mood: metadata.mood.or(translated.mood),
+                    style: metadata.style.clone(),
+                    content_warnings: metadata.content_warnings.clone(),
+                    age_restricted: metadata.age_restricted,
+                    prompt_version: Some(template.version_id()),
+                    ..translated
+                });
+            }
+            Ok(_) => report.to_string(),
+            Err(problem) => problem,
+        };
+
+        eprintln!(
+            "{} metadata reply {} of {} was invalid: {}",
+            name(language),
+            attempt,
+            config.llm.max_retries,
+            problem
+        );
+        prompt = format!(
+            "{}\n\nYour previous reply was:\n{}\n\nIt was invalid: {}. Reply again with \
+             only the corrected JSON object.",
+            rendered.user, reply, problem
+        );
+        last_problem = problem;
+    }
+
+    Err(Error::Llm(format!(
+        "No valid {} metadata after {} replies from {}: {}",
+        name(language),
+        config.llm.max_retries,
+        backend.describe(),
+        last_problem
+    )))
+}
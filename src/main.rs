@@ -1,297 +1,214 @@
-mod confession;
-mod constants;
-mod ollama;
-mod splitter;
-mod tts;
-mod upload;
-mod utils;
-mod video;
-// mod video_generator;
-
-use std::time::Duration;
-use tokio::task;
-use futures::future::join_all;
-
-use confession::Confession;
-
-#[derive(Debug, Clone)]
-struct VideoMetadata {
-    title: String,
-    description: String,
-    keywords: Vec<String>,
-}
-
-impl VideoMetadata {
-    fn format_title(&self, episode: u32, is_part: Option<(usize, usize)>) -> String {
-        match is_part {
-            Some((part, total)) => format!(
-                "Reddit Confessions #{} | {} (Part {}/{})",
-                episode, self.title, part, total
-            ),
-            None => format!("Reddit Confessions #{} | {} | #shorts", episode, self.title),
+mod cli;
+
+use clap::Parser;
+
+use cli::{Cli, Command};
+use py_rs_project::job::UploadStatus;
+use py_rs_project::pipeline::{artifact_path, CONFESSION_FILE, METADATA_FILE};
+use py_rs_project::{utils, Confession, Pipeline, Result, VideoMetadata};
+
+async fn run_command(pipeline: &Pipeline, command: Command) -> Result<()> {
+    let config = pipeline.config();
+
+    match command {
+        Command::Pick { out } => {
+            let out = out.unwrap_or_else(|| artifact_path(config, CONFESSION_FILE));
+            let mut confession_result = pipeline.pick_confession()?;
+            pipeline.classify(&mut confession_result).await?;
+            pipeline.rewrite(&mut confession_result).await?;
+            pipeline.outro(&mut confession_result).await?;
+            utils::write_json(&out, &confession_result)?;
+            println!("Confession written to {}", out);
         }
-    }
-
-    fn get_keywords_string(&self) -> String {
-        self.keywords.join(",")
-    }
-}
-
-fn estimate_duration_from_text(text: &str) -> f32 {
-    // Average speaking rate is about 150 words per minute
-    // So each word takes approximately 0.4 seconds
-    const SECONDS_PER_WORD: f32 = 0.4;
-    
-    let word_count = text.split_whitespace().count();
-    word_count as f32 * SECONDS_PER_WORD
-}
-
-async fn generate_metadata(
-    formatted_text: &str,
-) -> Result<VideoMetadata, Box<dyn std::error::Error>> {
-    let movie_title = ollama::generate_title(formatted_text).await?;
-    let description = ollama::generate_description(formatted_text).await?;
-    let hashtags: Vec<String> = description
-        .split_whitespace()
-        .filter(|word| word.starts_with('#'))
-        .map(|tag| tag[1..].to_string())
-        .collect();
-
-    Ok(VideoMetadata {
-        title: movie_title,
-        description,
-        keywords: hashtags,
-    })
-}
-
-async fn generate_base_video(formatted_text: &str) -> Result<(), Box<dyn std::error::Error>> {
-    // video_generator::generate_video_from_args(
-    //     constants::VIDEO_INPUT_PATH,
-    //         constants::AUDIO_OUTPUT_PATH,
-    //         formatted_text,
-    //         constants::VIDEO_OUTPUT_PATH,
-    //         constants::VIDEO_FONT_SIZE,
-    //         constants::VIDEO_BG_COLOR,
-    // )?;
-
-    video::execute_python_video_generator(
-        constants::VIDEO_INPUT_PATH,
-        constants::AUDIO_OUTPUT_PATH,
-        formatted_text,
-        constants::VIDEO_OUTPUT_PATH,
-        constants::VIDEO_FONT_SIZE,
-        constants::VIDEO_BG_COLOR,
-    )?;
-
-    tokio::time::sleep(Duration::from_secs(2)).await;
-    Ok(())
-}
-
-async fn upload_video(
-    video_path: &str,
-    metadata: &VideoMetadata,
-    episode: u32,
-    is_part: Option<(usize, usize)>,
-) -> Result<(), Box<dyn std::error::Error>> {
-    if constants::IS_DEBUGGING {
-        return Ok(());
-    }
-
-    let formatted_title = metadata.format_title(episode, is_part);
-    let keywords_joined = metadata.get_keywords_string();
-
-    upload::handle_upload(
-        video_path,
-        &formatted_title,
-        &metadata.description,
-        &keywords_joined,
-        constants::UPLOAD_CATEGORY,
-        constants::UPLOAD_PRIVACY,
-    )?;
-
-    Ok(())
-}
-
-async fn process_short_video(
-    formatted_text: &str,
-    metadata: &VideoMetadata,
-) -> Result<(), Box<dyn std::error::Error>> {
-    generate_base_video(formatted_text).await?;
-
-    let episode = utils::get_current_episode()?;
-    upload_video(constants::VIDEO_OUTPUT_PATH, metadata, episode, None).await?;
-    if !constants::IS_DEBUGGING {
-        utils::increment_episode()?;
-    }
-
-    Ok(())
-}
-
-async fn process_long_video(
-    metadata: &VideoMetadata,
-    formatted_confession: &str,
-) -> Result<(), Box<dyn std::error::Error + Send>> {
-    generate_base_video(formatted_confession).await.unwrap();
-
-    let split_result = 
-        splitter::split_media(constants::VIDEO_OUTPUT_PATH, constants::OUTPUTS_FOLDER).unwrap();
-
-    let total_parts = split_result.video_paths.len();
-    let episode = utils::get_current_episode().unwrap();
-
-    // Create a vector to hold all upload tasks
-    let mut upload_tasks = Vec::new();
-
-    for (i, video_path) in split_result.video_paths.iter().enumerate() {
-        let part_number = i + 1;
-        
-        if !video_path.to_str().unwrap().contains("original_") {
-            // Clone necessary values for the async task
-            let video_path = video_path.to_str().unwrap().to_string();
-            let metadata = metadata.clone();  // Requires #[derive(Clone)] on VideoMetadata
-
-            // Spawn a new task for each upload
-            let upload_task = task::spawn(async move {
-                upload_video(
-                    &video_path,
-                    &metadata,
-                    episode,
-                    Some((part_number, total_parts)),
-                ).await
-                .map_err(|e| -> Box<dyn std::error::Error + Send> {
-                    Box::new(std::io::Error::new(
-                        std::io::ErrorKind::Other,
-                        format!("Upload failed: {}", e)
-                    ))
-                })
-            });
-
-            upload_tasks.push(upload_task);
+        Command::Metadata { confession, out } => {
+            let confession_path =
+                confession.unwrap_or_else(|| artifact_path(config, CONFESSION_FILE));
+            let out = out.unwrap_or_else(|| artifact_path(config, METADATA_FILE));
+            let confession_result: Confession = utils::read_json(&confession_path)?;
+
+            let metadata = pipeline.generate_metadata(&confession_result).await?;
+            utils::write_json(&out, &metadata)?;
+            println!("Metadata written to {}", out);
         }
-    }
-
-    // Wait for all uploads to complete
-    join_all(upload_tasks).await
-        .into_iter()
-        .collect::<Result<Vec<_>, _>>().unwrap()
-        .into_iter()
-        .collect::<Result<Vec<_>, _>>()?;
-
-    if !constants::IS_DEBUGGING {
-        utils::increment_episode().unwrap();
-    }
-
-    Ok(())
-}
-
-async fn notify_with_sound(
-    message: &str,
-    sound_path: &str,
-) -> Result<(), Box<dyn std::error::Error>> {
-    utils::notify(message, sound_path).await?;
-    tokio::time::sleep(Duration::from_secs(2)).await;
-    Ok(())
-}
-
-async fn get_valid_confession_and_metadata(
-) -> Result<(Confession, VideoMetadata), Box<dyn std::error::Error>> {
-    for attempt in 0..constants::MAX_RETRIES {
-        let confession_result = confession::read_random_valid_confession()?;
-        let formatted_confession =
-            format!("{} {}", confession_result.title, confession_result.selftext);
-
-        match generate_metadata(&formatted_confession).await {
-            Ok(metadata) => {
-                if !metadata
-                    .title
-                    .to_lowercase()
-                    .contains("cannot create content")
-                    && !metadata.title.to_lowercase().contains("i cannot")
-                    && !metadata.title.to_lowercase().contains("unable to process")
-                {
-                    // Estimate duration before generating TTS
-                    let estimated_duration = estimate_duration_from_text(&formatted_confession);
-
-                    if estimated_duration >= constants::MINIMUM_VIDEO_DURATION {
-                        println!(
-                            "Valid confession found on attempt {} with estimated duration {:.2}s",
-                            attempt + 1,
-                            estimated_duration
-                        );
-                        
-                        // Generate TTS only after we know the estimated duration is acceptable
-                        tts::generate_tts(
-                            &formatted_confession,
-                            constants::AUDIO_OUTPUT_PATH,
-                            constants::AUDIO_VOICE,
-                            constants::AUDIO_MODEL,
-                        )?;
+        Command::Tts { confession, out } => {
+            let confession_path =
+                confession.unwrap_or_else(|| artifact_path(config, CONFESSION_FILE));
+            let out = out.unwrap_or_else(|| config.paths.audio_output.clone());
+            let confession_result: Confession = utils::read_json(&confession_path)?;
+
+            pipeline.generate_tts(&confession_result, &out)?;
+            println!("Audio written to {}", out);
+        }
+        Command::Render {
+            confession,
+            audio,
+            out,
+        } => {
+            let confession_path =
+                confession.unwrap_or_else(|| artifact_path(config, CONFESSION_FILE));
+            let audio = audio.unwrap_or_else(|| config.paths.audio_output.clone());
+            let out = out.unwrap_or_else(|| config.paths.video_output.clone());
+            let confession_result: Confession = utils::read_json(&confession_path)?;
+
+            pipeline.render(&confession_result, &audio, &out).await?;
+            println!("Video written to {}", out);
+        }
+        Command::Split { video, out_dir } => {
+            let video = video.unwrap_or_else(|| config.paths.video_output.clone());
+            let out_dir = out_dir.unwrap_or_else(|| config.paths.outputs_folder.clone());
 
-                        return Ok((confession_result, metadata));
-                    } else {
-                        println!(
-                            "Estimated confession duration {:.2}s too short (minimum {:.2}s). Retrying...",
-                            estimated_duration,
-                            constants::MINIMUM_VIDEO_DURATION
-                        );
-                    }
-                }
+            for path in pipeline.split(&video, &out_dir)? {
+                println!("{}", path);
+            }
+        }
+        Command::Upload {
+            video,
+            metadata,
+            part,
+            total,
+            episode,
+            increment_episode,
+        } => {
+            let video = video.unwrap_or_else(|| config.paths.video_output.clone());
+            let metadata_path = metadata.unwrap_or_else(|| artifact_path(config, METADATA_FILE));
+            let metadata: VideoMetadata = utils::read_json(&metadata_path)?;
+
+            pipeline
+                .upload(&video, &metadata, episode, part.zip(total))
+                .await?;
+            if increment_episode && !config.debug {
+                utils::increment_episode(&config.paths.episode_file)?;
             }
-            Err(e) => {
-                eprintln!(
-                    "Metadata generation failed on attempt {}: {}",
-                    attempt + 1,
-                    e
+        }
+        Command::Run => pipeline.run().await?,
+        Command::Batch { count } => {
+            for index in 0..count {
+                println!("Batch video {}/{}", index + 1, count);
+                pipeline.run().await?;
+            }
+        }
+        Command::Daemon => pipeline.run_daemon().await?,
+        Command::Resume { job, .. } => match job {
+            Some(id) => pipeline.resume(&id).await?,
+            None => pipeline.run().await?,
+        },
+        Command::Jobs => {
+            for job in pipeline.jobs()? {
+                let uploaded = job
+                    .parts
+                    .iter()
+                    .filter(|part| matches!(part.status, UploadStatus::Uploaded { .. }))
+                    .count();
+                let title = job
+                    .metadata
+                    .as_ref()
+                    .map(|metadata| metadata.title.as_str())
+                    .unwrap_or("-");
+                println!(
+                    "{}  {:?}  {}  {}/{} parts uploaded  {}",
+                    job.id,
+                    job.stage,
+                    job.language.as_deref().unwrap_or("-"),
+                    uploaded,
+                    job.parts.len(),
+                    title
                 );
             }
         }
+        Command::Report { experiment, by } => {
+            let (heading, column, reports) = match by {
+                Some(by) => {
+                    let grouping = by.grouping();
+                    let reports = pipeline.grouped_report(grouping)?;
+                    if reports.is_empty() {
+                        println!("No jobs have run yet");
+                        return Ok(());
+                    }
+                    (format!("Videos by {}", by.name()), by.name(), reports)
+                }
+                None => {
+                    let name = experiment.unwrap_or_else(|| config.experiment.name.clone());
+                    let reports = pipeline.experiment_report(&name)?;
+                    if reports.is_empty() {
+                        println!("No jobs have run in experiment {}", name);
+                        return Ok(());
+                    }
+                    (format!("Experiment {}", name), "variant", reports)
+                }
+            };
 
-        if attempt < constants::MAX_RETRIES - 1 {
+            let number = |value: Option<f64>, decimals: usize| match value {
+                Some(value) => format!("{:.*}", decimals, value),
+                None => "-".to_string(),
+            };
+            println!("{} (stats from {})", heading, config.experiment.stats_path);
             println!(
-                "Retrying with new confession... ({}/{})",
-                attempt + 1,
-                constants::MAX_RETRIES
+                "{:<20} {:>5} {:>7} {:>9} {:>11} {:>12} {:>8} {:>11} {:>9}",
+                column,
+                "jobs",
+                "videos",
+                "measured",
+                "mean views",
+                "median views",
+                "likes %",
+                "comments ‰",
+                "viewed %"
             );
+            for report in reports {
+                let variant = if report.configured {
+                    report.variant
+                } else {
+                    format!("{} (removed)", report.variant)
+                };
+                println!(
+                    "{:<20} {:>5} {:>7} {:>9} {:>11} {:>12} {:>8} {:>11} {:>9}",
+                    variant,
+                    report.jobs,
+                    report.videos,
+                    report.measured,
+                    number(report.mean_views, 0),
+                    number(report.median_views, 0),
+                    number(report.like_rate, 2),
+                    number(report.comment_rate, 2),
+                    number(report.mean_viewed, 1)
+                );
+            }
         }
     }
 
-    Err("Failed to find acceptable confession after maximum retries".into())
+    Ok(())
 }
 
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    utils::clear_output_folder(constants::OUTPUTS_FOLDER).await?;
-    tokio::time::sleep(Duration::from_secs(2)).await;
-    notify_with_sound("Gathering Data ...", "data/sounds/Ani_Alert.wav").await?;
-
-    let (confession_result, metadata) = get_valid_confession_and_metadata().await?;
-    let formatted_confession =
-        format!("{} {}", confession_result.title, confession_result.selftext);
-
-    println!(
-        "Metadata: {} \n {} \n {:?}",
-        metadata.title, metadata.description, metadata.keywords
+async fn main() {
+    let cli = Cli::parse();
+    let command = cli.command.unwrap_or(Command::Run);
+    let retry_interrupted = matches!(
+        command,
+        Command::Resume {
+            retry_interrupted: true,
+            ..
+        }
     );
 
-    notify_with_sound("Audio Created !!!", "data/sounds/Ani_Success.wav").await?;
-
-    let video_duration = video::get_duration_from_audio(constants::AUDIO_OUTPUT_PATH)?;
-    println!("Video Duration: {} seconds", video_duration);
-
-    if video_duration <= constants::MAX_VIDEO_DURATION {
-        notify_with_sound("Short Video ...", "data/sounds/Ani_Alert.wav").await?;
-        process_short_video(&formatted_confession, &metadata).await?;
-    } else {
-        notify_with_sound("Long Video ...", "data/sounds/Ani_Alert.wav").await?;
-        process_long_video(&metadata, &formatted_confession).await.unwrap();
+    let mut builder = Pipeline::builder().retry_interrupted(retry_interrupted);
+    if let Some(path) = cli.config {
+        builder = builder.config_file(path);
+    }
+    for assignment in cli.overrides {
+        builder = builder.set(assignment);
+    }
+    if cli.no_cache {
+        builder = builder.set("llm.cache_bypass=true");
     }
 
-    tokio::time::sleep(Duration::from_secs(2)).await;
-    notify_with_sound(
-        "Video Created & Uploaded !!!",
-        "data/sounds/Ani_Success.wav",
-    )
-    .await?;
+    let result = match builder.build() {
+        Ok(pipeline) => run_command(&pipeline, command).await,
+        Err(e) => Err(e),
+    };
 
-    Ok(())
+    if let Err(e) = result {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
 }
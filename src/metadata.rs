@@ -0,0 +1,365 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::classifier::ContentCategory;
+use crate::config::UploadConfig;
+use crate::style::VideoStyle;
+use crate::utils;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VideoMetadata {
+    pub title: String,
+    pub description: String,
+    /// YouTube tags.
+    pub keywords: Vec<String>,
+    /// Without the `#`; appended to the description on upload.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hashtags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<StoryCategory>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mood: Option<Mood>,
+    /// One line that opens the video and keeps viewers watching.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hook: Option<String>,
+    /// Sensitive topics the video warns about; such videos are declared not made
+    /// for kids on upload.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub content_warnings: Vec<ContentCategory>,
+    /// Asks for an age restriction on upload.
+    #[serde(default)]
+    pub age_restricted: bool,
+    /// The [`PromptTemplate`](crate::prompts::PromptTemplate) version that
+    /// generated this, e.g. `metadata@1+5f3c09ab`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt_version: Option<String>,
+    /// Background, music and voice picked for the mood and category.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub style: Option<VideoStyle>,
+}
+
+impl VideoMetadata {
+    /// The upload title, e.g. `Reddit Confessions #12 | <title> (Part 1/2)`, with
+    /// the series name and part label from `upload.series_title` and `upload.part_label`.
+    pub fn format_title(
+        &self,
+        upload: &UploadConfig,
+        episode: u32,
+        is_part: Option<(usize, usize)>,
+    ) -> String {
+        let series = &upload.series_title;
+        match is_part {
+            Some((part, total)) => format!(
+                "{} #{} | {} ({} {}/{})",
+                series, episode, self.title, upload.part_label, part, total
+            ),
+            None => format!("{} #{} | {} | #shorts", series, episode, self.title),
+        }
+    }
+
+    pub fn get_keywords_string(&self) -> String {
+        self.keywords.join(",")
+    }
+
+    /// The description as uploaded, hashtags last.
+    pub fn full_description(&self) -> String {
+        if self.hashtags.is_empty() {
+            return self.description.clone();
+        }
+        let hashtags = self
+            .hashtags
+            .iter()
+            .map(|tag| format!("#{}", tag))
+            .collect::<Vec<_>>()
+            .join(" ");
+        format!("{}\n\n{}", self.description, hashtags)
+    }
+}
+
+/// What a confession is mostly about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StoryCategory {
+    Relationships,
+    Family,
+    Friendship,
+    Work,
+    School,
+    Money,
+    Health,
+    Secrets,
+    Other,
+}
+
+impl StoryCategory {
+    pub const ALL: [StoryCategory; 9] = [
+        StoryCategory::Relationships,
+        StoryCategory::Family,
+        StoryCategory::Friendship,
+        StoryCategory::Work,
+        StoryCategory::School,
+        StoryCategory::Money,
+        StoryCategory::Health,
+        StoryCategory::Secrets,
+        StoryCategory::Other,
+    ];
+
+    pub fn key(self) -> &'static str {
+        match self {
+            StoryCategory::Relationships => "relationships",
+            StoryCategory::Family => "family",
+            StoryCategory::Friendship => "friendship",
+            StoryCategory::Work => "work",
+            StoryCategory::School => "school",
+            StoryCategory::Money => "money",
+            StoryCategory::Health => "health",
+            StoryCategory::Secrets => "secrets",
+            StoryCategory::Other => "other",
+        }
+    }
+}
+
+/// How a confession feels to listen to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Mood {
+    Funny,
+    Wholesome,
+    Creepy,
+    Dramatic,
+    Sad,
+}
+
+impl Mood {
+    pub const ALL: [Mood; 5] = [
+        Mood::Funny,
+        Mood::Wholesome,
+        Mood::Creepy,
+        Mood::Dramatic,
+        Mood::Sad,
+    ];
+
+    pub fn key(self) -> &'static str {
+        match self {
+            Mood::Funny => "funny",
+            Mood::Wholesome => "wholesome",
+            Mood::Creepy => "creepy",
+            Mood::Dramatic => "dramatic",
+            Mood::Sad => "sad",
+        }
+    }
+}
+
+/// YouTube rejects uploads whose tags add up to more than this many characters.
+const MAX_TAGS_LENGTH: usize = 500;
+
+/// The JSON object the model is asked for by [`generate_metadata`](crate::llm::generate_metadata).
+#[derive(Debug, Clone, Deserialize)]
+pub struct MetadataResponse {
+    pub title: String,
+    pub description: String,
+    pub hashtags: Vec<String>,
+    pub tags: Vec<String>,
+    pub category: StoryCategory,
+    /// Templates that do not ask for a mood leave it unset.
+    #[serde(default)]
+    pub mood: Option<Mood>,
+    pub hook: String,
+}
+
+impl MetadataResponse {
+    /// JSON schema of the response, for backends that constrain their output.
+    pub fn schema() -> serde_json::Value {
+        let categories = StoryCategory::ALL.map(StoryCategory::key);
+        let moods = Mood::ALL.map(Mood::key);
+        json!({
+            "type": "object",
+            "properties": {
+                "title": { "type": "string" },
+                "description": { "type": "string" },
+                "hashtags": { "type": "array", "items": { "type": "string" }, "minItems": 3 },
+                "tags": { "type": "array", "items": { "type": "string" }, "minItems": 3 },
+                "category": { "type": "string", "enum": categories },
+                "mood": { "type": "string", "enum": moods },
+                "hook": { "type": "string" }
+            },
+            "required": ["title", "description", "hashtags", "tags", "category", "mood", "hook"],
+            "additionalProperties": false
+        })
+    }
+
+    /// Parses a model reply, tolerating Markdown code fences or chatter around
+    /// the object.
+    pub fn parse(reply: &str) -> Result<Self, String> {
+        let json = match (reply.find('{'), reply.rfind('}')) {
+            (Some(start), Some(end)) if start < end => &reply[start..=end],
+            _ => return Err("the reply contains no JSON object".to_string()),
+        };
+        serde_json::from_str(json).map_err(|e| e.to_string())
+    }
+
+    /// Checks the fields are filled in and turns them into upload metadata. The
+    /// error explains what to fix, so it can be fed back to the model; the
+    /// content itself is judged by the [`Validator`](crate::validator::Validator).
+    pub fn into_metadata(self) -> Result<VideoMetadata, String> {
+        let mut problems = Vec::new();
+
+        let title = utils::sanitize_title(&self.title, usize::MAX);
+        if title.is_empty() {
+            problems.push("title is empty".to_string());
+        }
+
+        let description = self.description.trim().to_string();
+        if description.is_empty() {
+            problems.push("description is empty".to_string());
+        } else if description.contains('#') {
+            problems.push("description must not contain hashtags".to_string());
+        }
+
+        let mut hashtags = Vec::new();
+        for tag in &self.hashtags {
+            let tag = tag.trim().trim_start_matches('#').to_lowercase();
+            if tag.is_empty() {
+                continue;
+            }
+            if tag.contains(char::is_whitespace) {
+                problems.push(format!("hashtag \"{}\" contains spaces", tag));
+            } else if !hashtags.contains(&tag) {
+                hashtags.push(tag);
+            }
+        }
+        if hashtags.len() < 3 {
+            problems.push("give at least 3 hashtags".to_string());
+        }
+
+        let mut keywords = Vec::new();
+        let mut length = 0;
+        for tag in self
+            .tags
+            .iter()
+            .map(|tag| tag.trim())
+            .filter(|tag| !tag.is_empty())
+        {
+            length += tag.len() + 1;
+            if length > MAX_TAGS_LENGTH {
+                break;
+            }
+            keywords.push(tag.to_string());
+        }
+        if keywords.is_empty() {
+            problems.push("tags is empty".to_string());
+        }
+
+        let hook = self.hook.trim().to_string();
+        if hook.is_empty() {
+            problems.push("hook is empty".to_string());
+        }
+
+        if !problems.is_empty() {
+            return Err(problems.join("; "));
+        }
+        Ok(VideoMetadata {
+            title,
+            description,
+            keywords,
+            hashtags,
+            category: Some(self.category),
+            mood: self.mood,
+            hook: Some(hook),
+            content_warnings: Vec::new(),
+            age_restricted: false,
+            prompt_version: None,
+            style: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(overrides: serde_json::Value) -> String {
+        let mut reply = json!({
+            "title": "I Hid My Brother's Car Keys",
+            "description": "A guilty secret about a set of keys.",
+            "hashtags": ["shorts", "secrets", "family"],
+            "tags": ["confession", "keys"],
+            "category": "family",
+            "mood": "funny",
+            "hook": "He still thinks he lost them."
+        });
+        for (key, value) in overrides.as_object().unwrap() {
+            reply[key] = value.clone();
+        }
+        reply.to_string()
+    }
+
+    #[test]
+    fn parse_strips_code_fences_and_chatter() {
+        let reply = format!(
+            "Sure! Here it is:\n```json\n{}\n```\nEnjoy.",
+            response(json!({}))
+        );
+        let parsed = MetadataResponse::parse(&reply).unwrap();
+        assert_eq!(parsed.title, "I Hid My Brother's Car Keys");
+        assert_eq!(parsed.category, StoryCategory::Family);
+        assert_eq!(parsed.mood, Some(Mood::Funny));
+    }
+
+    #[test]
+    fn parse_reports_missing_objects_and_fields() {
+        assert_eq!(
+            MetadataResponse::parse("I'd rather not.").err().as_deref(),
+            Some("the reply contains no JSON object")
+        );
+        let error = MetadataResponse::parse(r#"{"title": "Only a title"}"#).unwrap_err();
+        assert!(error.contains("missing field"), "{error}");
+        let error =
+            MetadataResponse::parse(&response(json!({ "category": "gossip" }))).unwrap_err();
+        assert!(error.contains("unknown variant"), "{error}");
+    }
+
+    #[test]
+    fn mood_may_be_left_out() {
+        let mut reply: serde_json::Value = serde_json::from_str(&response(json!({}))).unwrap();
+        reply.as_object_mut().unwrap().remove("mood");
+        let parsed = MetadataResponse::parse(&reply.to_string()).unwrap();
+        assert_eq!(parsed.mood, None);
+    }
+
+    #[test]
+    fn hashtags_are_normalized_and_deduplicated() {
+        let reply = response(json!({
+            "hashtags": ["#Shorts", " secrets ", "#shorts", "", "FamilyDrama"],
+            "tags": [" confession ", "", "keys"]
+        }));
+        let metadata = MetadataResponse::parse(&reply)
+            .and_then(MetadataResponse::into_metadata)
+            .unwrap();
+        assert_eq!(metadata.hashtags, ["shorts", "secrets", "familydrama"]);
+        assert_eq!(metadata.keywords, ["confession", "keys"]);
+        assert_eq!(
+            metadata.hook.as_deref(),
+            Some("He still thinks he lost them.")
+        );
+    }
+
+    #[test]
+    fn into_metadata_lists_every_problem() {
+        let reply = response(json!({
+            "title": "  ",
+            "description": "Wild story #shorts",
+            "hashtags": ["shorts", "two words"],
+            "tags": [],
+            "hook": ""
+        }));
+        let problems = MetadataResponse::parse(&reply)
+            .and_then(MetadataResponse::into_metadata)
+            .unwrap_err();
+        assert_eq!(
+            problems,
+            "title is empty; description must not contain hashtags; hashtag \"two words\" \
+             contains spaces; give at least 3 hashtags; tags is empty; hook is empty"
+        );
+    }
+}
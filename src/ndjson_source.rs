@@ -0,0 +1,193 @@
+use rand::prelude::*;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Read};
+use std::sync::{LazyLock, Mutex};
+use std::time::SystemTime;
+
+use crate::confession::{self, Confession};
+use crate::config::{ColumnsConfig, SourceConfig};
+use crate::error::{Error, Result};
+use crate::source::ConfessionSource;
+
+/// Pushshift dumps are compressed with a long window; zstd refuses them without this.
+const ZSTD_WINDOW_LOG_MAX: u32 = 31;
+
+/// How many submissions one pass over the dumps samples for later picks.
+const SAMPLE_SIZE: usize = 256;
+
+/// Submissions of one pass not yet handed out.
+#[derive(Default)]
+struct Sample {
+    /// Size and modification time of each dump, so edited dumps are sampled again.
+    stamps: Vec<Option<(u64, SystemTime)>>,
+    confessions: Vec<Confession>,
+}
+
+/// Samples by the dumps, columns and subreddits they were drawn with. Kept for
+/// the life of the process so later jobs and videos pick from the same pass
+/// instead of streaming the dumps again.
+static SAMPLES: LazyLock<Mutex<HashMap<String, Sample>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Pushshift-style submission dumps with one JSON object per line, plain or
+/// `.zst`-compressed.
+///
+/// Compressed dumps cannot be seeked, so one pass streams all files and keeps
+/// [`SAMPLE_SIZE`] uniformly random usable submissions (reservoir sampling),
+/// which are handed out one per pick, across sources opened with the same
+/// settings, before the next pass.
+pub struct NdjsonSource {
+    config: SourceConfig,
+    key: String,
+}
+
+impl NdjsonSource {
+    pub fn new(config: &SourceConfig) -> Self {
+        let key =
+            serde_json::to_string(&(&config.ndjson_paths, &config.columns, &config.subreddits))
+                .unwrap_or_default();
+        Self {
+            config: config.clone(),
+            key,
+        }
+    }
+
+    fn stamps(&self) -> Vec<Option<(u64, SystemTime)>> {
+        self.config
+            .ndjson_paths
+            .iter()
+            .map(|path| {
+                let metadata = fs::metadata(path).ok()?;
+                Some((metadata.len(), metadata.modified().ok()?))
+            })
+            .collect()
+    }
+
+    /// Streams every dump once and samples up to [`SAMPLE_SIZE`] usable
+    /// submissions, in random order.
+    fn sample(&self) -> Result<Vec<Confession>> {
+        let mut rng = rand::rng();
+        let mut sample = Vec::with_capacity(SAMPLE_SIZE);
+        let mut seen: usize = 0;
+
+        for path in &self.config.ndjson_paths {
+            for line in open_lines(path)?.lines() {
+                let line = line.map_err(Error::io(format!("Failed to read {}", path)))?;
+                let Some(found) = parse_line(&line, &self.config.columns) else {
+                    continue;
+                };
+                if !self.config.accepts_subreddit(found.subreddit.as_deref()) {
+                    continue;
+                }
+
+                seen += 1;
+                if sample.len() < SAMPLE_SIZE {
+                    sample.push(found);
+                } else {
+                    let slot = rng.random_range(0..seen);
+                    if slot < SAMPLE_SIZE {
+                        sample[slot] = found;
+                    }
+                }
+            }
+        }
+
+        sample.shuffle(&mut rng);
+        Ok(sample)
+    }
+}
+
+fn open_lines(path: &str) -> Result<Box<dyn BufRead>> {
+    let file = File::open(path).map_err(Error::io(format!("Failed to open {}", path)))?;
+    let reader: Box<dyn Read> = if path.ends_with(".zst") {
+        let mut decoder = zstd::Decoder::new(file)
+            .map_err(Error::io(format!("Failed to start decompressing {}", path)))?;
+        decoder
+            .window_log_max(ZSTD_WINDOW_LOG_MAX)
+            .map_err(Error::io(format!("Failed to configure zstd for {}", path)))?;
+        Box::new(decoder)
+    } else {
+        Box::new(file)
+    };
+    Ok(Box::new(BufReader::new(reader)))
+}
+
+/// Numbers and booleans are turned into text so every field goes through the
+/// same parsing as CSV cells.
+fn field_value(
+    object: &Map<String, Value>,
+    columns: &ColumnsConfig,
+    field: &str,
+) -> Option<String> {
+    columns
+        .aliases(field)
+        .iter()
+        .find_map(|alias| match object.get(alias.trim())? {
+            Value::String(text) => Some(text.clone()),
+            Value::Number(number) => Some(number.to_string()),
+            Value::Bool(flag) => Some(flag.to_string()),
+            _ => None,
+        })
+}
+
+fn parse_line(line: &str, columns: &ColumnsConfig) -> Option<Confession> {
+    let object = match serde_json::from_str::<Value>(line).ok()? {
+        Value::Object(object) => object,
+        _ => return None,
+    };
+    confession::confession_from_fields(|field| field_value(&object, columns, field))
+}
+
+impl ConfessionSource for NdjsonSource {
+    fn random_confession(&self) -> Result<Confession> {
+        let stamps = self.stamps();
+        let mut samples = SAMPLES.lock().unwrap_or_else(|e| e.into_inner());
+        let sample = samples.entry(self.key.clone()).or_default();
+        if sample.confessions.is_empty() || sample.stamps != stamps {
+            sample.confessions = self.sample()?;
+            sample.stamps = stamps;
+        }
+
+        sample.confessions.pop().ok_or_else(|| {
+            Error::Source(format!(
+                "No valid confession found in {}",
+                self.config.ndjson_paths.join(", ")
+            ))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sources_with_the_same_settings_share_one_sample() {
+        let path =
+            std::env::temp_dir().join(format!("ndjson-shared-{}.ndjson", std::process::id()));
+        let lines: Vec<String> = (1..=3)
+            .map(|n| format!(r#"{{"id":"p{n}","title":"Title {n}","selftext":"Post {n}"}}"#))
+            .collect();
+        fs::write(&path, lines.join("\n")).unwrap();
+        let config = SourceConfig {
+            ndjson_paths: vec![path.to_string_lossy().into_owned()],
+            ..SourceConfig::default()
+        };
+
+        // A second source, as a later job would open, continues the first one's
+        // pass instead of sampling again, so the three posts come out once each.
+        let mut ids = vec![NdjsonSource::new(&config).random_confession().unwrap().id];
+        let later = NdjsonSource::new(&config);
+        ids.push(later.random_confession().unwrap().id);
+        ids.push(later.random_confession().unwrap().id);
+        fs::remove_file(&path).unwrap();
+
+        ids.sort();
+        assert_eq!(
+            ids,
+            [Some("p1"), Some("p2"), Some("p3")].map(|id| id.map(String::from))
+        );
+    }
+}
@@ -0,0 +1,787 @@
+use regex::{Captures, Regex, RegexBuilder};
+use std::sync::LazyLock;
+
+use crate::confession::Confession;
+use crate::config::NormalizeConfig;
+
+/// Named HTML entities Reddit leaves in post bodies. Numeric ones are decoded
+/// generically.
+const ENTITIES: &[(&str, &str)] = &[
+    ("amp", "&"),
+    ("lt", "<"),
+    ("gt", ">"),
+    ("quot", "\""),
+    ("apos", "'"),
+    ("nbsp", " "),
+    ("ndash", "-"),
+    ("mdash", ", "),
+    ("hellip", "..."),
+    ("lsquo", "'"),
+    ("rsquo", "'"),
+    ("ldquo", "\""),
+    ("rdquo", "\""),
+];
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Case {
+    /// `SO` is "significant other" but `so` is just "so".
+    Exact,
+    Any,
+}
+
+/// Reddit shorthand and what the voice should say instead. Entries are applied
+/// longest first so `w/o` wins over `w/`.
+const ABBREVIATIONS: &[(&str, &str, Case)] = &[
+    ("TL;DR", "in short", Case::Any),
+    ("TLDR", "in short", Case::Any),
+    ("WIBTA", "would I be the asshole", Case::Any),
+    ("AITA", "am I the asshole", Case::Any),
+    ("TIFU", "today I fucked up", Case::Any),
+    ("IMHO", "in my humble opinion", Case::Any),
+    ("AFAIK", "as far as I know", Case::Any),
+    ("FWIW", "for what it's worth", Case::Any),
+    ("IMO", "in my opinion", Case::Any),
+    ("TBH", "to be honest", Case::Any),
+    ("IDK", "I don't know", Case::Any),
+    ("NGL", "not gonna lie", Case::Any),
+    ("IRL", "in real life", Case::Any),
+    ("BTW", "by the way", Case::Any),
+    ("ETA", "edited to add", Case::Exact),
+    ("YTA", "you're the asshole", Case::Exact),
+    ("NTA", "not the asshole", Case::Exact),
+    ("ESH", "everyone sucks here", Case::Exact),
+    ("NAH", "no assholes here", Case::Exact),
+    ("LDR", "long-distance relationship", Case::Exact),
+    ("MIL", "mother-in-law", Case::Exact),
+    ("FIL", "father-in-law", Case::Exact),
+    ("SIL", "sister-in-law", Case::Exact),
+    ("BIL", "brother-in-law", Case::Exact),
+    ("DH", "husband", Case::Exact),
+    ("DW", "wife", Case::Exact),
+    ("SO", "significant other", Case::Exact),
+    ("NC", "no contact", Case::Exact),
+    ("OP", "the original poster", Case::Exact),
+    ("bf", "boyfriend", Case::Any),
+    ("gf", "girlfriend", Case::Any),
+    ("ppl", "people", Case::Any),
+    ("b/c", "because", Case::Any),
+    ("w/o", "without", Case::Any),
+    ("w/", "with", Case::Any),
+];
+
+/// Sentences containing any of these are meta-commentary about the post itself.
+const BOILERPLATE_MARKERS: &[&str] = &[
+    "throwaway",
+    "on mobile",
+    "sorry for formatting",
+    "sorry for the formatting",
+    "english is not my first language",
+    "english isn't my first language",
+];
+
+/// Currency symbol, singular and plural name.
+const CURRENCIES: &[(&str, &str, &str)] = &[
+    ("$", "dollar", "dollars"),
+    ("£", "pound", "pounds"),
+    ("€", "euro", "euros"),
+];
+
+const ONES: [&str; 20] = [
+    "zero",
+    "one",
+    "two",
+    "three",
+    "four",
+    "five",
+    "six",
+    "seven",
+    "eight",
+    "nine",
+    "ten",
+    "eleven",
+    "twelve",
+    "thirteen",
+    "fourteen",
+    "fifteen",
+    "sixteen",
+    "seventeen",
+    "eighteen",
+    "nineteen",
+];
+const TENS: [&str; 10] = [
+    "", "", "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
+];
+const SCALES: [(u64, &str); 4] = [
+    (1_000_000_000_000, "trillion"),
+    (1_000_000_000, "billion"),
+    (1_000_000, "million"),
+    (1_000, "thousand"),
+];
+
+fn regex(pattern: &str) -> Regex {
+    Regex::new(pattern).expect("built-in normalizer pattern is valid")
+}
+
+static ENTITY: LazyLock<Regex> = LazyLock::new(|| regex(r"&(#[xX][0-9a-fA-F]+|#\d+|[a-zA-Z]+);"));
+static HTML_TAG: LazyLock<Regex> = LazyLock::new(|| regex(r"</?[a-zA-Z][^<>]*>"));
+static MARKDOWN: LazyLock<Vec<(Regex, &'static str)>> = LazyLock::new(|| {
+    [
+        (r"!\[([^\]]*)\]\([^)]*\)", "$1"),
+        (r"\[([^\]]+)\]\([^)]*\)", "$1"),
+        (r">!(.+?)!<", "$1"),
+        (r"(?m)^[ \t]*#{1,6}[ \t]*", ""),
+        (r"(?m)^[ \t]*(?:>[ \t]?)+", ""),
+        (r"(?m)^[ \t]*(?:[-*_][ \t]*){3,}$", ""),
+        (r"(?m)^[ \t]*[-*+][ \t]+", ""),
+        (r"\*\*(.+?)\*\*", "$1"),
+        (r"__(.+?)__", "$1"),
+        (r"~~(.+?)~~", "$1"),
+        (r"\*(\S(?:[^*]*\S)?)\*", "$1"),
+        (r"(^|[^\w])_(\S(?:[^_]*\S)?)_", "$1$2"),
+        (r"`+([^`]+)`+", "$1"),
+        (r"\^\(([^)]*)\)", "$1"),
+        (r"\^(\S)", "$1"),
+    ]
+    .into_iter()
+    .map(|(pattern, replacement)| (regex(pattern), replacement))
+    .collect()
+});
+static URL: LazyLock<Regex> = LazyLock::new(|| regex(r"(?i)\(?\b(?:https?://|www\.)[^\s)\]]+\)?"));
+static SUBREDDIT: LazyLock<Regex> = LazyLock::new(|| regex(r"/?\b([ru])/(\w+)"));
+static AGE_TAG: LazyLock<Vec<Regex>> = LazyLock::new(|| {
+    vec![
+        regex(r"[(\[]\s*(\d{1,2})\s*/?\s*([MmFf]|NB|nb)\s*[)\]]"),
+        regex(r"[(\[]\s*([MmFf]|NB|nb)\s*/?\s*(\d{1,2})\s*[)\]]"),
+        regex(r"\b(\d{2})([MF])\b"),
+    ]
+});
+static CURRENCY: LazyLock<Regex> = LazyLock::new(|| {
+    regex(r"([$£€])\s?(\d{1,3}(?:,\d{3})+|\d+)(?:\.(\d{1,2}))?(?:\s?([kKmM])\b)?")
+});
+static PERCENT: LazyLock<Regex> = LazyLock::new(|| regex(r"(\d+(?:\.\d+)?)\s?%"));
+static TIME: LazyLock<Regex> = LazyLock::new(|| {
+    regex(r"\b(\d{1,2})(?::(\d{2}))?\s?([AaPp])\.?[Mm]\b\.?|\b(\d{1,2}):(\d{2})\b")
+});
+static ORDINAL: LazyLock<Regex> = LazyLock::new(|| regex(r"\b(\d+)(?:st|nd|rd|th)\b"));
+static NUMBER: LazyLock<Regex> =
+    LazyLock::new(|| regex(r"\b(\d{1,3}(?:,\d{3})+|\d+)(?:\.(\d+))?\b"));
+static SENTENCE: LazyLock<Regex> = LazyLock::new(|| regex(r"[^.!?\n]*(?:[.!?]+|\n|$)"));
+
+/// Turns raw post text into a script the voice can read: markup, entities,
+/// links, emoji and meta-commentary are removed, and shorthand, age tags,
+/// numbers and amounts are spelled out.
+///
+/// Each step is a separate public function over `&str`, driven by the tables
+/// above, so any of them can be run and checked on its own.
+pub struct Normalizer {
+    config: NormalizeConfig,
+    abbreviations: Vec<Abbreviation>,
+}
+
+struct Abbreviation {
+    pattern: Regex,
+    expansion: String,
+    case: Case,
+}
+
+impl Normalizer {
+    pub fn from_config(config: &NormalizeConfig) -> Self {
+        let mut entries = config
+            .abbreviations
+            .iter()
+            .map(|(abbreviation, expansion)| (abbreviation.as_str(), expansion.as_str(), Case::Any))
+            .collect::<Vec<_>>();
+        entries.extend(
+            ABBREVIATIONS
+                .iter()
+                .copied()
+                .filter(|(abbreviation, _, _)| {
+                    !config
+                        .abbreviations
+                        .keys()
+                        .any(|custom| custom.eq_ignore_ascii_case(abbreviation))
+                }),
+        );
+        entries.sort_by_key(|(abbreviation, _, _)| std::cmp::Reverse(abbreviation.len()));
+
+        let abbreviations = entries
+            .into_iter()
+            .filter_map(|(abbreviation, expansion, case)| {
+                abbreviation_pattern(abbreviation, case).map(|pattern| Abbreviation {
+                    pattern,
+                    expansion: expansion.to_string(),
+                    case,
+                })
+            })
+            .collect();
+
+        Self {
+            config: config.clone(),
+            abbreviations,
+        }
+    }
+
+    /// The title and body of `confession` as they should be spoken and captioned.
+    pub fn script(&self, confession: &Confession) -> String {
+        let title = self.normalize(&confession.title);
+        let body = self.normalize(&confession.selftext);
+        let separator = if title.ends_with(['.', '!', '?']) {
+            " "
+        } else {
+            ". "
+        };
+        format!("{}{}{}", title, separator, body)
+    }
+
+    pub fn normalize(&self, text: &str) -> String {
+        if !self.config.enabled {
+            return collapse_whitespace(text);
+        }
+
+        let mut text = decode_entities(text);
+        text = strip_html(&text);
+        text = strip_markdown(&text);
+        text = remove_urls(&text);
+        text = remove_boilerplate(&text);
+        if self.config.remove_emoji {
+            text = remove_emoji(&text);
+        }
+        text = join_lines(&text);
+        if self.config.expand_abbreviations {
+            text = self.expand_abbreviations(&text);
+            text = expand_age_tags(&text);
+        }
+        text = speak_subreddits(&text);
+        if self.config.verbalize_numbers {
+            text = verbalize_numbers(&text);
+        }
+        tidy_punctuation(&collapse_whitespace(&text))
+    }
+
+    /// Case-sensitive entries are left alone inside all-caps shouting, where
+    /// "SO" is just "so", and nothing is expanded in subreddit names like `r/AITA`.
+    pub fn expand_abbreviations(&self, text: &str) -> String {
+        self.abbreviations
+            .iter()
+            .fold(text.to_string(), |text, abbreviation| {
+                abbreviation
+                    .pattern
+                    .replace_all(&text, |caps: &Captures| {
+                        let found = caps.get(0).map_or(0..0, |m| m.range());
+                        let in_name = text[..found.start].ends_with('/');
+                        if in_name
+                            || abbreviation.case == Case::Exact && in_shouting(&text, found.clone())
+                        {
+                            caps[0].to_string()
+                        } else if starts_sentence(&text, found.start) {
+                            capitalize(&abbreviation.expansion)
+                        } else {
+                            abbreviation.expansion.clone()
+                        }
+                    })
+                    .into_owned()
+            })
+    }
+}
+
+fn abbreviation_pattern(abbreviation: &str, case: Case) -> Option<Regex> {
+    let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+    let pattern = format!(
+        "{}{}{}",
+        if is_word(abbreviation.chars().next()) {
+            r"\b"
+        } else {
+            ""
+        },
+        regex::escape(abbreviation),
+        if is_word(abbreviation.chars().last()) {
+            r"\b"
+        } else {
+            ""
+        },
+    );
+    RegexBuilder::new(&pattern)
+        .case_insensitive(case == Case::Any)
+        .build()
+        .ok()
+}
+
+fn in_shouting(text: &str, found: std::ops::Range<usize>) -> bool {
+    let is_shouted = |word: Option<&str>| {
+        word.is_some_and(|word| {
+            let letters = word
+                .chars()
+                .filter(|c| c.is_alphabetic())
+                .collect::<Vec<_>>();
+            letters.len() > 1 && letters.iter().all(|c| c.is_uppercase())
+        })
+    };
+    is_shouted(text[..found.start].split_whitespace().next_back())
+        || is_shouted(text[found.end..].split_whitespace().next())
+}
+
+fn starts_sentence(text: &str, at: usize) -> bool {
+    text[..at]
+        .trim_end()
+        .chars()
+        .next_back()
+        .is_none_or(|c| matches!(c, '.' | '!' | '?' | '\n' | ':'))
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+pub fn decode_entities(text: &str) -> String {
+    ENTITY
+        .replace_all(text, |caps: &Captures| {
+            let name = &caps[1];
+            let decoded = if let Some(hex) = name.strip_prefix("#x").or(name.strip_prefix("#X")) {
+                u32::from_str_radix(hex, 16).ok().and_then(char::from_u32)
+            } else if let Some(decimal) = name.strip_prefix('#') {
+                decimal.parse().ok().and_then(char::from_u32)
+            } else {
+                return ENTITIES
+                    .iter()
+                    .find(|(entity, _)| *entity == name)
+                    .map_or_else(|| caps[0].to_string(), |(_, value)| value.to_string());
+            };
+            // Zero-width spaces and the like decode to nothing worth reading.
+            decoded
+                .filter(|c| !c.is_control() && !matches!(c, '\u{200b}'..='\u{200d}' | '\u{feff}'))
+                .map(String::from)
+                .unwrap_or_default()
+        })
+        .into_owned()
+}
+
+pub fn strip_html(text: &str) -> String {
+    HTML_TAG.replace_all(text, " ").into_owned()
+}
+
+pub fn strip_markdown(text: &str) -> String {
+    MARKDOWN
+        .iter()
+        .fold(text.to_string(), |text, (pattern, replacement)| {
+            pattern.replace_all(&text, *replacement).into_owned()
+        })
+}
+
+pub fn remove_urls(text: &str) -> String {
+    URL.replace_all(text, "").into_owned()
+}
+
+/// Reads `r/name` and `u/name` the way people say them.
+pub fn speak_subreddits(text: &str) -> String {
+    SUBREDDIT
+        .replace_all(text, |caps: &Captures| {
+            let kind = if &caps[1] == "r" {
+                "r slash"
+            } else {
+                "u slash"
+            };
+            format!("{} {}", kind, &caps[2])
+        })
+        .into_owned()
+}
+
+pub fn remove_boilerplate(text: &str) -> String {
+    SENTENCE
+        .find_iter(text)
+        .map(|sentence| sentence.as_str())
+        .filter(|sentence| {
+            let lowercase = sentence.to_lowercase();
+            !BOILERPLATE_MARKERS
+                .iter()
+                .any(|marker| lowercase.contains(marker))
+        })
+        .collect()
+}
+
+fn is_emoji(c: char) -> bool {
+    matches!(c as u32,
+        0x1F000..=0x1FAFF | 0x2600..=0x27BF | 0x2B00..=0x2BFF | 0xFE00..=0xFE0F | 0x200D | 0x20E3)
+}
+
+pub fn remove_emoji(text: &str) -> String {
+    text.chars().filter(|c| !is_emoji(*c)).collect()
+}
+
+/// `I (25F)` becomes `I, a 25-year-old woman,`.
+pub fn expand_age_tags(text: &str) -> String {
+    let describe = |age: &str, gender: &str| {
+        let person = match gender.to_ascii_lowercase().as_str() {
+            "f" => "woman",
+            "m" => "man",
+            _ => "person",
+        };
+        format!(", a {}-year-old {},", age, person)
+    };
+
+    let text = AGE_TAG[0].replace_all(text, |caps: &Captures| describe(&caps[1], &caps[2]));
+    let text = AGE_TAG[1].replace_all(&text, |caps: &Captures| describe(&caps[2], &caps[1]));
+    AGE_TAG[2]
+        .replace_all(&text, |caps: &Captures| describe(&caps[1], &caps[2]))
+        .into_owned()
+}
+
+/// Spells out amounts, percentages, times, ordinals and plain numbers.
+pub fn verbalize_numbers(text: &str) -> String {
+    let text = CURRENCY.replace_all(text, |caps: &Captures| {
+        let Some(amount) = parse_number(&caps[2]) else {
+            return caps[0].to_string();
+        };
+        let (singular, plural) = CURRENCIES
+            .iter()
+            .find(|(symbol, _, _)| *symbol == &caps[1])
+            .map_or(("", ""), |(_, singular, plural)| (*singular, *plural));
+        let multiplier = caps.get(4).map(|m| match m.as_str() {
+            "k" | "K" => " thousand",
+            _ => " million",
+        });
+
+        let mut spoken = number_words(amount);
+        spoken.push_str(multiplier.unwrap_or_default());
+        spoken.push(' ');
+        spoken.push_str(if amount == 1 && multiplier.is_none() {
+            singular
+        } else {
+            plural
+        });
+        if let Some(cents) = caps.get(3) {
+            let cents = format!("{:0<2}", cents.as_str()).parse().unwrap_or(0);
+            if cents > 0 {
+                spoken.push_str(&format!(" and {} cents", number_words(cents)));
+            }
+        }
+        spoken
+    });
+
+    let text = PERCENT.replace_all(&text, |caps: &Captures| {
+        format!("{} percent", verbalize_numbers(&caps[1]))
+    });
+
+    let text = TIME.replace_all(&text, |caps: &Captures| {
+        let (hour, minutes, meridiem) = match caps.get(1) {
+            Some(hour) => (hour.as_str(), caps.get(2), caps.get(3)),
+            None => (&caps[4], caps.get(5), None),
+        };
+        // At most two digits each, so neither overflows.
+        let mut spoken = number_words(parse_number(hour).unwrap_or_default());
+        match minutes.and_then(|m| parse_number(m.as_str())) {
+            Some(0) if meridiem.is_none() => spoken.push_str(" o'clock"),
+            Some(0) | None => {}
+            Some(m) if m < 10 => spoken.push_str(&format!(" oh {}", number_words(m))),
+            Some(m) => spoken.push_str(&format!(" {}", number_words(m))),
+        }
+        if let Some(meridiem) = meridiem {
+            let letter = meridiem.as_str().to_ascii_lowercase();
+            spoken.push_str(if letter == "a" { " a m" } else { " p m" });
+        }
+        spoken
+    });
+
+    let text = ORDINAL.replace_all(&text, |caps: &Captures| {
+        parse_number(&caps[1]).map_or_else(|| caps[0].to_string(), ordinal_words)
+    });
+
+    NUMBER
+        .replace_all(&text, |caps: &Captures| {
+            let digits = &caps[1];
+            let Some(whole) = parse_number(digits) else {
+                return caps[0].to_string();
+            };
+            let mut spoken = if caps.get(2).is_none() && !digits.contains(',') && digits.len() == 4
+            {
+                year_words(whole)
+            } else {
+                number_words(whole)
+            };
+            if let Some(fraction) = caps.get(2) {
+                spoken.push_str(" point");
+                for digit in fraction.as_str().chars() {
+                    spoken.push(' ');
+                    spoken.push_str(ONES[digit.to_digit(10).unwrap_or(0) as usize]);
+                }
+            }
+            spoken
+        })
+        .into_owned()
+}
+
+/// `None` for digit runs too long for a `u64`, which are left as written.
+fn parse_number(digits: &str) -> Option<u64> {
+    digits.replace(',', "").parse().ok()
+}
+
+pub fn number_words(n: u64) -> String {
+    if n < 20 {
+        return ONES[n as usize].to_string();
+    }
+    if n < 100 {
+        let tens = TENS[(n / 10) as usize];
+        return match n % 10 {
+            0 => tens.to_string(),
+            ones => format!("{}-{}", tens, ONES[ones as usize]),
+        };
+    }
+    if n < 1000 {
+        let hundreds = format!("{} hundred", ONES[(n / 100) as usize]);
+        return match n % 100 {
+            0 => hundreds,
+            rest => format!("{} {}", hundreds, number_words(rest)),
+        };
+    }
+
+    let (scale, name) = SCALES
+        .iter()
+        .copied()
+        .find(|(scale, _)| n >= *scale)
+        .unwrap_or((1_000, "thousand"));
+    let head = format!("{} {}", number_words(n / scale), name);
+    match n % scale {
+        0 => head,
+        rest => format!("{} {}", head, number_words(rest)),
+    }
+}
+
+/// Four-digit numbers in the usual year range are read in pairs: 1998 is
+/// "nineteen ninety-eight", 2005 "two thousand five", 2019 "twenty nineteen".
+fn year_words(n: u64) -> String {
+    match n {
+        2000..=2009 => number_words(n),
+        1100..=2099 => {
+            let (high, low) = (n / 100, n % 100);
+            match low {
+                0 => format!("{} hundred", number_words(high)),
+                1..=9 => format!("{} oh {}", number_words(high), number_words(low)),
+                _ => format!("{} {}", number_words(high), number_words(low)),
+            }
+        }
+        _ => number_words(n),
+    }
+}
+
+pub fn ordinal_words(n: u64) -> String {
+    let cardinal = number_words(n);
+    let split = cardinal.rfind([' ', '-']).map_or(0, |i| i + 1);
+    let (head, last) = cardinal.split_at(split);
+    let last = match last {
+        "one" => "first".to_string(),
+        "two" => "second".to_string(),
+        "three" => "third".to_string(),
+        "five" => "fifth".to_string(),
+        "eight" => "eighth".to_string(),
+        "nine" => "ninth".to_string(),
+        "twelve" => "twelfth".to_string(),
+        word if word.ends_with('y') => format!("{}ieth", &word[..word.len() - 1]),
+        word => format!("{}th", word),
+    };
+    format!("{}{}", head, last)
+}
+
+/// Headings, list items and paragraphs become sentences, so the voice pauses
+/// between them instead of running them together.
+pub fn join_lines(text: &str) -> String {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            if line.ends_with(['.', '!', '?', ':', ';', ',']) {
+                line.to_string()
+            } else {
+                format!("{}.", line)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Cleans up the commas and spaces left behind by removed or inserted text.
+fn tidy_punctuation(text: &str) -> String {
+    static SPACE_BEFORE: LazyLock<Regex> = LazyLock::new(|| regex(r"\s+([,.!?;:])"));
+    static REPEATED_COMMA: LazyLock<Regex> = LazyLock::new(|| regex(r",(\s*,)+"));
+    static COMMA_BEFORE_STOP: LazyLock<Regex> = LazyLock::new(|| regex(r",\s*([.!?;:])"));
+    static EMPTY_BRACKETS: LazyLock<Regex> = LazyLock::new(|| regex(r"\(\s*\)|\[\s*\]"));
+
+    let text = EMPTY_BRACKETS.replace_all(text, "");
+    let text = SPACE_BEFORE.replace_all(&text, "$1");
+    let text = REPEATED_COMMA.replace_all(&text, ",");
+    let text = COMMA_BEFORE_STOP.replace_all(&text, "$1");
+    collapse_whitespace(text.trim_start_matches([',', ' ']))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normalizer() -> Normalizer {
+        Normalizer::from_config(&NormalizeConfig::default())
+    }
+
+    #[test]
+    fn decodes_named_and_numeric_entities() {
+        assert_eq!(
+            decode_entities("Tom &amp; Jerry &#39;s &#x41;"),
+            "Tom & Jerry 's A"
+        );
+        assert_eq!(decode_entities("a&#x200b;b"), "ab");
+        assert_eq!(decode_entities("&bogus;"), "&bogus;");
+    }
+
+    #[test]
+    fn strips_markdown_and_html() {
+        assert_eq!(
+            strip_markdown("**bold** and *it* and ~~gone~~"),
+            "bold and it and gone"
+        );
+        assert_eq!(
+            strip_markdown("[a link](https://x.y) >!spoiler!<"),
+            "a link spoiler"
+        );
+        assert_eq!(
+            strip_markdown("# Heading\n> quote\n- item"),
+            "Heading\nquote\nitem"
+        );
+        assert_eq!(strip_html("a<br/>b</p>").trim(), "a b");
+    }
+
+    #[test]
+    fn removes_urls_boilerplate_and_emoji() {
+        assert_eq!(remove_urls("see https://example.com/x?y=1 now"), "see  now");
+        assert_eq!(remove_urls("(www.example.com)"), "");
+        assert_eq!(
+            remove_boilerplate("Throwaway for obvious reasons. I lied."),
+            " I lied."
+        );
+        assert_eq!(remove_emoji("so funny 😂🔥!"), "so funny !");
+    }
+
+    #[test]
+    fn expands_abbreviations() {
+        let normalizer = normalizer();
+        assert_eq!(
+            normalizer.expand_abbreviations("TBH my bf went w/o me"),
+            "To be honest my boyfriend went without me"
+        );
+        assert_eq!(
+            normalizer.expand_abbreviations("my SO said hi"),
+            "my significant other said hi"
+        );
+        assert_eq!(
+            normalizer.expand_abbreviations("I WAS SO ANGRY"),
+            "I WAS SO ANGRY"
+        );
+        assert_eq!(
+            normalizer.expand_abbreviations("posted on r/AITA"),
+            "posted on r/AITA"
+        );
+    }
+
+    #[test]
+    fn custom_abbreviations_override_built_in_ones() {
+        let mut config = NormalizeConfig::default();
+        config
+            .abbreviations
+            .insert("bf".to_string(), "best friend".to_string());
+        let normalizer = Normalizer::from_config(&config);
+        assert_eq!(normalizer.expand_abbreviations("my BF"), "my best friend");
+    }
+
+    #[test]
+    fn expands_age_tags_and_subreddits() {
+        assert_eq!(expand_age_tags("I (25F)"), "I , a 25-year-old woman,");
+        assert_eq!(
+            expand_age_tags("my [M30] boss"),
+            "my , a 30-year-old man, boss"
+        );
+        assert_eq!(
+            speak_subreddits("on r/confessions"),
+            "on r slash confessions"
+        );
+    }
+
+    #[test]
+    fn verbalizes_numbers() {
+        assert_eq!(verbalize_numbers("$5"), "five dollars");
+        assert_eq!(verbalize_numbers("$1"), "one dollar");
+        assert_eq!(verbalize_numbers("£2.50"), "two pounds and fifty cents");
+        assert_eq!(verbalize_numbers("$10k"), "ten thousand dollars");
+        assert_eq!(verbalize_numbers("50%"), "fifty percent");
+        assert_eq!(verbalize_numbers("at 7:05pm"), "at seven oh five p m");
+        assert_eq!(verbalize_numbers("at 9:00"), "at nine o'clock");
+        assert_eq!(verbalize_numbers("my 21st"), "my twenty-first");
+        assert_eq!(verbalize_numbers("in 1998"), "in nineteen ninety-eight");
+        assert_eq!(verbalize_numbers("in 2005"), "in two thousand five");
+        assert_eq!(
+            verbalize_numbers("1,234"),
+            "one thousand two hundred thirty-four"
+        );
+        assert_eq!(verbalize_numbers("3.14"), "three point one four");
+    }
+
+    #[test]
+    fn number_and_ordinal_words() {
+        assert_eq!(number_words(0), "zero");
+        assert_eq!(number_words(115), "one hundred fifteen");
+        assert_eq!(number_words(2_000_001), "two million one");
+        assert_eq!(ordinal_words(12), "twelfth");
+        assert_eq!(ordinal_words(40), "fortieth");
+        assert_eq!(ordinal_words(103), "one hundred third");
+    }
+
+    #[test]
+    fn leaves_overlong_digit_runs_as_written() {
+        let digits = "123456789012345678901234567890";
+        assert_eq!(parse_number(digits), None);
+        assert_eq!(verbalize_numbers(digits), digits);
+        assert_eq!(
+            verbalize_numbers(&format!("${}", digits)),
+            format!("${}", digits)
+        );
+        assert_eq!(
+            verbalize_numbers(&format!("{}th", digits)),
+            format!("{}th", digits)
+        );
+        assert_eq!(parse_number("18,446,744,073,709,551,615"), Some(u64::MAX));
+    }
+
+    #[test]
+    fn joins_lines_into_sentences() {
+        assert_eq!(
+            join_lines("Title\n\nFirst line\nDone."),
+            "Title. First line. Done."
+        );
+    }
+
+    #[test]
+    fn normalizes_a_whole_post() {
+        let normalizer = normalizer();
+        assert_eq!(
+            normalizer
+                .normalize("**TIFU** &amp; lost $20\n\nMy bf (25M) laughed 😂\nOn mobile, sorry."),
+            "Today I fucked up & lost twenty dollars. My boyfriend, a twenty-five-year-old \
+             man, laughed."
+        );
+    }
+
+    #[test]
+    fn disabled_only_collapses_whitespace() {
+        let config = NormalizeConfig {
+            enabled: false,
+            ..NormalizeConfig::default()
+        };
+        assert_eq!(
+            Normalizer::from_config(&config).normalize("  **TBH**\n $5  "),
+            "**TBH** $5"
+        );
+    }
+}
@@ -0,0 +1,149 @@
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::config::LlmConfig;
+use crate::error::{Error, Result};
+use crate::llm::LlmBackend;
+
+/// Any server speaking the OpenAI chat completions API at `llm.base_url`:
+/// llama.cpp server, vLLM, LM Studio or OpenAI itself.
+pub struct OpenAiBackend {
+    client: reqwest::Client,
+    url: String,
+    api_key: Option<String>,
+    model: String,
+    temperature: Option<f32>,
+    max_tokens: Option<u32>,
+    seed: Option<i32>,
+}
+
+#[derive(Serialize)]
+struct ChatRequest<'a> {
+    model: &'a str,
+    messages: [Message<'a>; 2],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<serde_json::Value>,
+}
+
+#[derive(Serialize)]
+struct Message<'a> {
+    role: &'a str,
+    content: &'a str,
+}
+
+#[derive(Deserialize)]
+struct ChatResponse {
+    choices: Vec<Choice>,
+}
+
+#[derive(Deserialize)]
+struct Choice {
+    message: ResponseMessage,
+}
+
+#[derive(Deserialize)]
+struct ResponseMessage {
+    #[serde(default)]
+    content: Option<String>,
+}
+
+impl OpenAiBackend {
+    pub fn new(config: &LlmConfig) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .build()
+            .map_err(|e| Error::Llm(format!("Failed to build HTTP client: {}", e)))?;
+
+        Ok(Self {
+            client,
+            url: format!("{}/chat/completions", config.base_url.trim_end_matches('/')),
+            api_key: config.api_key.clone().filter(|key| !key.is_empty()),
+            model: config.model.clone(),
+            temperature: config.temperature,
+            max_tokens: config.max_tokens,
+            seed: config.seed,
+        })
+    }
+}
+
+impl OpenAiBackend {
+    async fn send(
+        &self,
+        system_prompt: &str,
+        prompt: &str,
+        response_format: Option<serde_json::Value>,
+    ) -> Result<String> {
+        let body = ChatRequest {
+            model: &self.model,
+            messages: [
+                Message {
+                    role: "system",
+                    content: system_prompt,
+                },
+                Message {
+                    role: "user",
+                    content: prompt,
+                },
+            ],
+            temperature: self.temperature,
+            max_tokens: self.max_tokens,
+            seed: self.seed,
+            response_format,
+        };
+
+        let mut request = self.client.post(&self.url).json(&body);
+        if let Some(key) = &self.api_key {
+            request = request.bearer_auth(key);
+        }
+        let failed = |e: reqwest::Error| {
+            Error::Llm(format!("Chat request to {} failed: {}", self.describe(), e))
+        };
+        let response = request
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(failed)?;
+        let response: ChatResponse = response.json().await.map_err(failed)?;
+
+        response
+            .choices
+            .into_iter()
+            .next()
+            .and_then(|choice| choice.message.content)
+            .ok_or_else(|| Error::Llm(format!("{} returned no message", self.describe())))
+    }
+}
+
+impl LlmBackend for OpenAiBackend {
+    fn chat<'a>(
+        &'a self,
+        system_prompt: &'a str,
+        prompt: &'a str,
+    ) -> BoxFuture<'a, Result<String>> {
+        Box::pin(self.send(system_prompt, prompt, None))
+    }
+
+    fn chat_json<'a>(
+        &'a self,
+        system_prompt: &'a str,
+        prompt: &'a str,
+        schema: &'a serde_json::Value,
+    ) -> BoxFuture<'a, Result<String>> {
+        let response_format = serde_json::json!({
+            "type": "json_schema",
+            "json_schema": { "name": "response", "schema": schema },
+        });
+        Box::pin(self.send(system_prompt, prompt, Some(response_format)))
+    }
+
+    fn describe(&self) -> String {
+        format!("{} at {}", self.model, self.url)
+    }
+}
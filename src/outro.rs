@@ -0,0 +1,98 @@
+use rand::prelude::*;
+
+use crate::config::Config;
+use crate::error::Result;
+use crate::llm;
+use crate::prompts::PromptTemplate;
+use crate::utils::SECONDS_PER_WORD;
+use crate::validator::{Field, Validator};
+
+/// Builds the closing segment: a short reaction in the `prompts.persona` voice
+/// from the `prompts.outro` template, followed by one of the `outro.cta` lines
+/// picked at random. Both together fit in `outro.max_seconds`.
+///
+/// Faulty reactions are retried up to `llm.max_retries` times with the problem
+/// fed back; when none is usable, or the model refuses, the outro is just the
+/// call to action. `None` means there is nothing to append.
+pub async fn generate(config: &Config, script: &str) -> Result<Option<String>> {
+    let settings = &config.outro;
+    if !settings.enabled {
+        return Ok(None);
+    }
+
+    let cta = settings
+        .cta
+        .choose(&mut rand::rng())
+        .map(|line| line.trim().to_string())
+        .filter(|line| !line.is_empty());
+    let budget = (settings.max_seconds / SECONDS_PER_WORD).round() as usize;
+    let max_words = budget.saturating_sub(
+        cta.as_deref()
+            .map_or(0, |cta| cta.split_whitespace().count()),
+    );
+
+    let reaction = if settings.reaction && max_words > 0 {
+        react(config, script, max_words).await?
+    } else {
+        None
+    };
+
+    let outro = [reaction, cta].into_iter().flatten().collect::<Vec<_>>();
+    Ok((!outro.is_empty()).then(|| outro.join(" ")))
+}
+
+async fn react(config: &Config, script: &str, max_words: usize) -> Result<Option<String>> {
+    let template = PromptTemplate::load(&config.prompts, &config.prompts.outro)?;
+    let max_words_text = max_words.to_string();
+    let rendered = template.render(&[
+        ("script", script),
+        ("persona", &config.prompts.persona),
+        ("max_words", &max_words_text),
+    ])?;
+
+    let backend = llm::open(&config.llm)?;
+    let validator = Validator::new(config, &rendered.system);
+    let mut prompt = rendered.user.clone();
+
+    for attempt in 1..=config.llm.max_retries {
+        let reply = backend.chat(&rendered.system, &prompt).await?;
+        let reaction = reply.split_whitespace().collect::<Vec<_>>().join(" ");
+
+        let report = validator.check_script(Field::Outro, &reaction, script);
+        if report.refused() {
+            eprintln!(
+                "{} refused to react to the story: {}",
+                backend.describe(),
+                report
+            );
+            return Ok(None);
+        }
+        let mut problems: Vec<String> = report.issues.iter().map(|i| i.to_string()).collect();
+        let words = reaction.split_whitespace().count();
+        if words == 0 {
+            problems.push("it is empty".to_string());
+        } else if words > max_words {
+            problems.push(format!(
+                "it is {} words long, at most {} allowed",
+                words, max_words
+            ));
+        }
+        if problems.is_empty() {
+            backend.commit(&reply);
+            return Ok(Some(reaction));
+        }
+
+        let problem = problems.join("; ");
+        eprintln!(
+            "Outro reaction {} of {} was rejected: {}",
+            attempt, config.llm.max_retries, problem
+        );
+        prompt = format!(
+            "{}\n\nYour previous reaction was:\n{}\n\nIt was rejected: {}. Write it again.",
+            rendered.user, reaction, problem
+        );
+    }
+
+    eprintln!("No usable outro reaction; ending with the call to action only");
+    Ok(None)
+}
@@ -0,0 +1,974 @@
+use futures::future::join_all;
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task;
+
+use crate::classifier::{self, Classifier};
+use crate::confession::Confession;
+use crate::config::{Config, LanguageConfig};
+use crate::error::{Error, Recovery, Result};
+use crate::experiment::{self, Grouping, VariantReport};
+use crate::job::{Job, JobStage, PartUpload, UploadStatus};
+use crate::ledger::Ledger;
+use crate::metadata::VideoMetadata;
+use crate::normalizer::Normalizer;
+use crate::profanity::ProfanityFilter;
+use crate::validator::Field;
+use crate::{llm, localize, outro, rewriter, source, splitter, style, tts, upload, utils, video};
+
+pub const CONFESSION_FILE: &str = "confession.json";
+pub const METADATA_FILE: &str = "metadata.json";
+
+pub fn estimate_duration_from_text(text: &str) -> f32 {
+    let word_count = text.split_whitespace().count();
+    word_count as f32 * utils::SECONDS_PER_WORD
+}
+
+pub async fn generate_metadata(config: &Config, formatted_text: &str) -> Result<VideoMetadata> {
+    let mut metadata = llm::generate_metadata(config, formatted_text).await?;
+    filter_metadata(config, &mut metadata)?;
+    Ok(metadata)
+}
+
+fn filter_metadata(config: &Config, metadata: &mut VideoMetadata) -> Result<()> {
+    ProfanityFilter::from_config(&config.profanity)
+        .filter_metadata(metadata)
+        .map_err(|found| {
+            Error::Llm(format!(
+                "Generated metadata contains profanity: {}",
+                found.join(", ")
+            ))
+        })
+}
+
+pub async fn generate_base_video(config: &Config, formatted_text: &str) -> Result<()> {
+    // video_generator::generate_video_from_args(
+    //     &config.paths.video_input,
+    //         &config.paths.audio_output,
+    //         formatted_text,
+    //         &config.paths.video_output,
+    //         config.video.font_size,
+    //         config.video.bg_color.as_deref(),
+    // )?;
+
+    video::execute_python_video_generator(&video::VideoGeneratorConfig {
+        video_clip_path: &config.paths.video_input,
+        audio_clip_path: &config.paths.audio_output,
+        formatted_text,
+        output_video_path: &config.paths.video_output,
+        subtitle_fontsize: config.video.font_size,
+        subtitle_color: config.video.bg_color.as_deref(),
+        music: config.video.music.as_deref(),
+        music_volume: config.video.music_volume,
+    })?;
+
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    Ok(())
+}
+
+pub async fn upload_video(
+    config: &Config,
+    video_path: &str,
+    metadata: &VideoMetadata,
+    episode: u32,
+    is_part: Option<(usize, usize)>,
+) -> Result<Option<String>> {
+    if config.debug {
+        return Ok(None);
+    }
+
+    let formatted_title = metadata.format_title(&config.upload, episode, is_part);
+    let keywords_joined = metadata.get_keywords_string();
+
+    // Videos with content warnings are never declared as made for kids.
+    let audience = upload::Audience {
+        made_for_kids: (!metadata.content_warnings.is_empty()).then_some(false),
+        age_restricted: metadata.age_restricted,
+    };
+    let video_id = upload::handle_upload(
+        video_path,
+        &formatted_title,
+        &metadata.full_description(),
+        &keywords_joined,
+        &config.upload,
+        audience,
+    )?;
+
+    Ok(video_id)
+}
+
+/// Uploads every part of `job` that is not uploaded yet, persisting each part's
+/// status as soon as it is known so a crash never causes a part to be uploaded twice.
+async fn upload_parts(config: &Config, job: &mut Job) -> Result<()> {
+    let jobs_folder = config.paths.jobs_folder.clone();
+    let metadata = job
+        .metadata
+        .clone()
+        .ok_or_else(|| Error::State(format!("{} has no metadata to upload with", job.id)))?;
+    let episode = match job.episode {
+        Some(episode) => episode,
+        None => {
+            let episode = utils::get_current_episode(&config.paths.episode_file)?;
+            job.episode = Some(episode);
+            episode
+        }
+    };
+
+    let pending: Vec<usize> = job
+        .parts
+        .iter()
+        .enumerate()
+        .filter(|(_, part)| {
+            matches!(
+                part.status,
+                UploadStatus::Pending | UploadStatus::Failed { .. }
+            )
+        })
+        .map(|(index, _)| index)
+        .collect();
+
+    for &index in &pending {
+        job.parts[index].status = UploadStatus::InProgress;
+    }
+    job.save(&jobs_folder)?;
+
+    let shared_job = Arc::new(Mutex::new(job.clone()));
+    let mut upload_tasks = Vec::new();
+
+    for index in pending {
+        // Clone necessary values for the async task
+        let part = job.parts[index].clone();
+        let metadata = metadata.clone();
+        let config = config.clone();
+        let jobs_folder = jobs_folder.clone();
+        let shared_job = Arc::clone(&shared_job);
+
+        // Spawn a new task for each upload
+        let upload_task = task::spawn(async move {
+            let result = upload_video(&config, &part.path, &metadata, episode, part.part).await;
+
+            let mut job = shared_job.lock().unwrap_or_else(|e| e.into_inner());
+            job.parts[index].status = match &result {
+                Ok(video_id) => UploadStatus::Uploaded {
+                    video_id: video_id.clone(),
+                },
+                Err(error) => UploadStatus::Failed {
+                    error: error.to_string(),
+                },
+            };
+            job.save(&jobs_folder)?;
+
+            result.map(|_| ())
+        });
+
+        upload_tasks.push(upload_task);
+    }
+
+    // Wait for all uploads to complete
+    let results = join_all(upload_tasks).await;
+    *job = shared_job.lock().unwrap_or_else(|e| e.into_inner()).clone();
+
+    for result in results {
+        result.map_err(|e| Error::Upload(format!("Upload task panicked: {}", e)))??;
+    }
+
+    Ok(())
+}
+
+/// Advances the episode counter exactly once per job and marks it done.
+fn finish_job(config: &Config, job: &mut Job) -> Result<()> {
+    if !job.all_parts_uploaded() {
+        return Err(Error::State(format!(
+            "{} still has parts left to upload",
+            job.id
+        )));
+    }
+    job.advance(JobStage::Uploaded, &config.paths.jobs_folder)?;
+
+    if !config.debug {
+        utils::increment_episode(&config.paths.episode_file)?;
+    }
+    job.advance(JobStage::Done, &config.paths.jobs_folder)?;
+
+    if !config.debug {
+        if let Err(e) = fs::remove_dir_all(&job.work_dir) {
+            eprintln!("Failed to remove work directory {}: {}", job.work_dir, e);
+        }
+    }
+
+    Ok(())
+}
+
+async fn process_short_video(config: &Config, job: &mut Job, options: RunOptions) -> Result<()> {
+    let job_config = job.scoped_config(config);
+
+    if job.stage < JobStage::Rendered {
+        let confession = job
+            .confession
+            .as_ref()
+            .ok_or_else(|| Error::State(format!("{} has no confession", job.id)))?;
+        let formatted_text = caption_script(&job_config, confession);
+        generate_base_video(&job_config, &formatted_text).await?;
+        job.video_path = Some(job_config.paths.video_output.clone());
+        job.advance(JobStage::Rendered, &config.paths.jobs_folder)?;
+    }
+
+    if job.stage < JobStage::Split {
+        job.parts = vec![PartUpload {
+            path: job_config.paths.video_output.clone(),
+            part: None,
+            status: UploadStatus::Pending,
+        }];
+        job.advance(JobStage::Split, &config.paths.jobs_folder)?;
+    }
+
+    if options.prepare_only {
+        return Ok(());
+    }
+
+    upload_parts(&job_config, job).await?;
+    finish_job(&job_config, job)
+}
+
+async fn process_long_video(config: &Config, job: &mut Job, options: RunOptions) -> Result<()> {
+    let job_config = job.scoped_config(config);
+
+    if job.stage < JobStage::Rendered {
+        let confession = job
+            .confession
+            .as_ref()
+            .ok_or_else(|| Error::State(format!("{} has no confession", job.id)))?;
+        let formatted_confession = caption_script(&job_config, confession);
+        generate_base_video(&job_config, &formatted_confession).await?;
+        job.video_path = Some(job_config.paths.video_output.clone());
+        job.advance(JobStage::Rendered, &config.paths.jobs_folder)?;
+    }
+
+    if job.stage < JobStage::Split {
+        let split_result = splitter::split_media(
+            &job_config.paths.video_output,
+            &job_config.paths.outputs_folder,
+            config.video.max_duration,
+        )?;
+
+        let video_paths: Vec<String> = split_result
+            .video_paths
+            .iter()
+            .map(|path| path.to_string_lossy().into_owned())
+            .filter(|path| !path.contains("original_"))
+            .collect();
+        let total_parts = video_paths.len();
+
+        job.parts = video_paths
+            .into_iter()
+            .enumerate()
+            .map(|(i, path)| PartUpload {
+                path,
+                part: Some((i + 1, total_parts)),
+                status: UploadStatus::Pending,
+            })
+            .collect();
+        job.advance(JobStage::Split, &config.paths.jobs_folder)?;
+    }
+
+    if options.prepare_only {
+        return Ok(());
+    }
+
+    upload_parts(&job_config, job).await?;
+    finish_job(&job_config, job)
+}
+
+async fn notify_with_sound(config: &Config, message: &str, sound_path: &str) -> Result<()> {
+    if let Err(e) = utils::notify(&config.notify.url, message, sound_path).await {
+        match e.recovery() {
+            Recovery::Skip => eprintln!("{}. Continuing...", e),
+            _ => return Err(e),
+        }
+    }
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    Ok(())
+}
+
+/// The confession after the `[normalize]` pass, before any profanity filtering.
+fn normalized_script(config: &Config, confession: &Confession) -> String {
+    Normalizer::from_config(&config.normalize).script(confession)
+}
+
+/// What is narrated: the rewritten narration when there is one, otherwise the
+/// normalized confession.
+fn narrated_script(config: &Config, confession: &Confession) -> String {
+    match &confession.narration {
+        Some(narration) => Normalizer::from_config(&config.normalize).normalize(narration),
+        None => normalized_script(config, confession),
+    }
+}
+
+/// Prepends the `classify.warning` when the confession touches a `classify.warn` topic.
+fn with_content_warning(config: &Config, confession: &Confession, script: String) -> String {
+    match Classifier::from_config(&config.classify).warning_text(&confession.content) {
+        Some(warning) => format!("{} {}", warning, script),
+        None => script,
+    }
+}
+
+/// The narration as it is spoken, with `profanity.speech` applied, any content
+/// warning first and any outro last.
+pub fn spoken_script(config: &Config, confession: &Confession) -> String {
+    let filter = ProfanityFilter::from_config(&config.profanity);
+    let mut script = filter.apply(&narrated_script(config, confession), filter.speech_action());
+    if let Some(outro) = &confession.outro {
+        script = format!("{} {}", script, filter.apply(outro, filter.speech_action()));
+    }
+    with_content_warning(config, confession, script)
+}
+
+/// The narration as it is captioned, with `profanity.captions` applied, any
+/// content warning first and any outro last, after `outro.caption_marker`.
+pub fn caption_script(config: &Config, confession: &Confession) -> String {
+    let filter = ProfanityFilter::from_config(&config.profanity);
+    let mut script = filter.apply(
+        &narrated_script(config, confession),
+        filter.caption_action(),
+    );
+    if let Some(outro) = caption_outro(config, confession) {
+        let marker = config.outro.caption_marker.trim();
+        script = [script.as_str(), marker, outro.as_str()]
+            .iter()
+            .filter(|part| !part.is_empty())
+            .copied()
+            .collect::<Vec<_>>()
+            .join(" ");
+    }
+    with_content_warning(config, confession, script)
+}
+
+/// The outro as it is captioned, with `profanity.captions` applied.
+fn caption_outro(config: &Config, confession: &Confession) -> Option<String> {
+    let filter = ProfanityFilter::from_config(&config.profanity);
+    let outro = confession.outro.as_ref()?;
+    Some(filter.apply(outro, filter.caption_action()))
+}
+
+/// Carries the confession's content warnings over to the upload: the warning
+/// heads the description and the video is flagged as not made for kids.
+pub fn label_metadata(config: &Config, confession: &Confession, metadata: &mut VideoMetadata) {
+    let classifier = Classifier::from_config(&config.classify);
+    metadata.content_warnings = classifier.warnings(&confession.content);
+    metadata.age_restricted = classifier.age_restricted(&confession.content);
+    if let Some(warning) = classifier.warning_text(&confession.content) {
+        if !metadata.description.starts_with(&warning) {
+            metadata.description = format!("{}\n\n{}", warning, metadata.description);
+        }
+    }
+}
+
+/// Runs the full classification, including the LLM when `classify.use_llm` is
+/// set, and names the `classify.block` topics the confession touches.
+async fn classify_confession(
+    config: &Config,
+    confession: &mut Confession,
+) -> Result<Vec<classifier::ContentCategory>> {
+    classifier::classify(config, confession).await?;
+    Ok(Classifier::from_config(&config.classify).blocked(&confession.content))
+}
+
+/// Sets the confession's narration to a hook-first rewrite when `rewrite.enabled`
+/// is on, leaving it narrated as written when no faithful rewrite is produced.
+async fn rewrite_confession(config: &Config, confession: &mut Confession) -> Result<()> {
+    if !config.rewrite.enabled || confession.narration.is_some() {
+        return Ok(());
+    }
+    confession.narration = rewriter::rewrite(config, confession).await?;
+    if confession.narration.is_some() {
+        println!("Narrating a rewrite of \"{}\"", confession.title);
+    }
+    Ok(())
+}
+
+/// Generates the confession's outro when `outro.enabled` is on.
+async fn outro_confession(config: &Config, confession: &mut Confession) -> Result<()> {
+    if !config.outro.enabled || confession.outro.is_some() {
+        return Ok(());
+    }
+    confession.outro = outro::generate(config, &narrated_script(config, confession)).await?;
+    Ok(())
+}
+
+/// Translates the narration, outro and metadata into `language`. `config` is the
+/// config of the original; the translations are checked with the language applied.
+async fn translate(
+    config: &Config,
+    language: &LanguageConfig,
+    confession: &Confession,
+    metadata: &VideoMetadata,
+) -> Result<(Confession, VideoMetadata)> {
+    let mut localized_config = config.clone();
+    localize::apply(language, &mut localized_config);
+
+    let original = with_content_warning(config, confession, narrated_script(config, confession));
+    let mut localized = confession.clone();
+    localized.narration = Some(
+        localize::translate_script(&localized_config, language, Field::Narration, &original)
+            .await?,
+    );
+    if let Some(outro) = &confession.outro {
+        localized.outro = Some(
+            localize::translate_script(&localized_config, language, Field::Outro, outro).await?,
+        );
+    }
+
+    let mut metadata = localize::translate_metadata(&localized_config, language, metadata).await?;
+    filter_metadata(&localized_config, &mut metadata)?;
+    Ok((localized, metadata))
+}
+
+/// Samples confessions until one is estimated to run at least `video.min_duration`.
+pub fn pick_confession(config: &Config) -> Result<Confession> {
+    let source = source::open(config)?;
+    let max_retries = config.llm.max_retries;
+    for attempt in 0..max_retries {
+        let confession_result = source.random_confession()?;
+        let estimated_duration =
+            estimate_duration_from_text(&spoken_script(config, &confession_result));
+
+        if estimated_duration >= config.video.min_duration {
+            println!(
+                "Confession picked on attempt {} with estimated duration {:.2}s",
+                attempt + 1,
+                estimated_duration
+            );
+            return Ok(confession_result);
+        }
+
+        println!(
+            "Estimated confession duration {:.2}s too short (minimum {:.2}s). Retrying... ({}/{})",
+            estimated_duration,
+            config.video.min_duration,
+            attempt + 1,
+            max_retries
+        );
+    }
+
+    Err(Error::Source(
+        "Failed to find a long enough confession after maximum retries".to_string(),
+    ))
+}
+
+/// Generates the metadata and voice-over of a picked confession. `Ok(None)`
+/// means the confession was unusable (blocked or too short) and another should be tried.
+async fn try_confession(
+    config: &Config,
+    mut confession_result: Confession,
+    attempt: usize,
+) -> Result<Option<(Confession, VideoMetadata)>> {
+    // Rewrites never pad, so an original that stays too short even with the
+    // longest outro is skipped before spending any LLM calls on it.
+    let outro_allowance = if config.outro.enabled && confession_result.outro.is_none() {
+        config.outro.max_seconds
+    } else {
+        0.0
+    };
+    let original_duration = estimate_duration_from_text(&spoken_script(config, &confession_result));
+    if original_duration + outro_allowance < config.video.min_duration {
+        println!(
+            "Original confession duration {:.2}s too short (minimum {:.2}s). Retrying...",
+            original_duration, config.video.min_duration
+        );
+        return Ok(None);
+    }
+
+    let blocked = classify_confession(config, &mut confession_result).await?;
+    if !blocked.is_empty() {
+        println!(
+            "Skipping \"{}\": touches blocked topic {}",
+            confession_result.title,
+            blocked
+                .iter()
+                .map(|category| category.key())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        return Ok(None);
+    }
+    rewrite_confession(config, &mut confession_result).await?;
+    outro_confession(config, &mut confession_result).await?;
+    let formatted_confession = spoken_script(config, &confession_result);
+
+    let mut metadata =
+        generate_metadata(config, &normalized_script(config, &confession_result)).await?;
+    label_metadata(config, &confession_result, &mut metadata);
+    style::label(&config.style, &mut metadata);
+
+    // Estimate duration before generating TTS
+    let estimated_duration = estimate_duration_from_text(&formatted_confession);
+    if estimated_duration < config.video.min_duration {
+        println!(
+            "Estimated confession duration {:.2}s too short (minimum {:.2}s). Retrying...",
+            estimated_duration, config.video.min_duration
+        );
+        return Ok(None);
+    }
+
+    println!(
+        "Valid confession found on attempt {} with estimated duration {:.2}s",
+        attempt + 1,
+        estimated_duration
+    );
+
+    // Generate TTS only after we know the estimated duration is acceptable
+    let mut styled = config.clone();
+    if let Some(style) = &metadata.style {
+        style::apply(style, &mut styled);
+    }
+    tts::generate_tts(
+        &styled.audio,
+        &formatted_confession,
+        &config.paths.audio_output,
+    )?;
+
+    Ok(Some((confession_result, metadata)))
+}
+
+pub async fn get_valid_confession_and_metadata(
+    config: &Config,
+) -> Result<(Confession, VideoMetadata)> {
+    let source = source::open(config)?;
+    let max_retries = config.llm.max_retries;
+    for attempt in 0..max_retries {
+        let attempted = match source.random_confession() {
+            Ok(found) => try_confession(config, found, attempt).await,
+            Err(e) => Err(e),
+        };
+        match attempted {
+            Ok(Some(found)) => return Ok(found),
+            Ok(None) => {}
+            Err(e) if e.recovery() == Recovery::Abort => return Err(e),
+            Err(e) => eprintln!("Attempt {} failed: {}", attempt + 1, e),
+        }
+
+        if attempt < max_retries - 1 {
+            println!(
+                "Retrying with new confession... ({}/{})",
+                attempt + 1,
+                max_retries
+            );
+        }
+    }
+
+    Err(Error::Llm(
+        "Failed to find acceptable confession after maximum retries".to_string(),
+    ))
+}
+
+pub fn artifact_path(config: &Config, file_name: &str) -> String {
+    Path::new(&config.paths.outputs_folder)
+        .join(file_name)
+        .to_string_lossy()
+        .into_owned()
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RunOptions {
+    /// Upload again parts whose previous upload was interrupted.
+    pub retry_interrupted: bool,
+    /// Stop once the video is rendered and split, leaving the upload for later.
+    pub prepare_only: bool,
+}
+
+/// Resumes the oldest unfinished job, or starts a new one when there is none.
+pub async fn run_pipeline(config: &Config, options: RunOptions) -> Result<()> {
+    let mut job = match Job::find_unfinished(&config.paths.jobs_folder)? {
+        Some(job) => {
+            println!("Resuming {} from stage {:?}", job.id, job.stage);
+            job
+        }
+        None => {
+            utils::clear_output_folder(&config.paths.outputs_folder).await?;
+            tokio::time::sleep(Duration::from_secs(2)).await;
+            Job::create(config)?
+        }
+    };
+
+    run_job(config, &mut job, options).await?;
+
+    for id in job.translations.clone() {
+        let mut translation = Job::load(&config.paths.jobs_folder, &id)?;
+        if translation.stage != JobStage::Done {
+            run_job(config, &mut translation, options).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Translates the parent's confession and metadata for a `job` in language
+/// `code` and narrates them with the language's voice.
+async fn localize_job(
+    config: &Config,
+    job_config: &Config,
+    job: &Job,
+    code: &str,
+) -> Result<(Confession, VideoMetadata)> {
+    let language = localize::language(config, code).ok_or_else(|| {
+        Error::State(format!(
+            "{} is in language {}, which is no longer in localize.languages",
+            job.id, code
+        ))
+    })?;
+    let (confession, metadata) = match (&job.confession, &job.metadata) {
+        (Some(confession), Some(metadata)) => (confession, metadata),
+        _ => {
+            return Err(Error::State(format!(
+                "{} has no confession and metadata to translate",
+                job.id
+            )))
+        }
+    };
+
+    let (localized, metadata) = translate(config, language, confession, metadata).await?;
+    println!(
+        "Translated \"{}\" into {}",
+        confession.title,
+        localize::name(language)
+    );
+
+    // A new channel starts at episode 1.
+    if !Path::new(&job_config.paths.episode_file).exists() {
+        utils::write_json(
+            &job_config.paths.episode_file,
+            &serde_json::json!({ "episode": 1 }),
+        )?;
+    }
+
+    tts::generate_tts(
+        &job_config.audio,
+        &spoken_script(job_config, &localized),
+        &job_config.paths.audio_output,
+    )?;
+    Ok((localized, metadata))
+}
+
+/// Queues one job per `localize.languages` entry to produce `job` in that language.
+fn queue_translations(config: &Config, job: &mut Job) -> Result<()> {
+    for language in &config.localize.languages {
+        let translation = Job::translation(config, job, language.code.trim())?;
+        println!(
+            "Queued {} to produce {} in {}",
+            translation.id,
+            job.id,
+            localize::name(language)
+        );
+        job.translations.push(translation.id);
+    }
+    job.save(&config.paths.jobs_folder)
+}
+
+pub async fn run_job(config: &Config, job: &mut Job, options: RunOptions) -> Result<()> {
+    if job.stage == JobStage::Done {
+        println!("{} is already done", job.id);
+        return Ok(());
+    }
+
+    let interrupted: Vec<String> = job
+        .interrupted_parts()
+        .iter()
+        .map(|part| part.path.clone())
+        .collect();
+    if !interrupted.is_empty() {
+        if !options.retry_interrupted {
+            return Err(Error::State(format!(
+                "{} was interrupted while uploading {}. Check the channel, then rerun with \
+                 `resume --retry-interrupted` to upload them again",
+                job.id,
+                interrupted.join(", ")
+            )));
+        }
+        for part in &mut job.parts {
+            if part.status == UploadStatus::InProgress {
+                part.status = UploadStatus::Pending;
+            }
+        }
+        job.save(&config.paths.jobs_folder)?;
+    }
+
+    let job_config = job.scoped_config(config);
+    fs::create_dir_all(&job.work_dir)
+        .map_err(Error::io(format!("Failed to create {}", job.work_dir)))?;
+
+    if job.stage < JobStage::AudioReady {
+        notify_with_sound(config, "Gathering Data ...", "data/sounds/Ani_Alert.wav").await?;
+
+        let (confession_result, metadata) = match job.language.clone() {
+            Some(code) => localize_job(config, &job_config, job, &code).await?,
+            None => get_valid_confession_and_metadata(&job_config).await?,
+        };
+
+        // Keep the stage inputs around so a failed render or upload can be rerun on its own.
+        utils::write_json(
+            &artifact_path(&job_config, CONFESSION_FILE),
+            &confession_result,
+        )?;
+        utils::write_json(&artifact_path(&job_config, METADATA_FILE), &metadata)?;
+
+        println!(
+            "Metadata: {} \n {} \n {:?}",
+            metadata.title, metadata.description, metadata.keywords
+        );
+
+        if !config.debug && job.language.is_none() {
+            let mut ledger = Ledger::load(&config.source.ledger_path)?;
+            ledger.record(&confession_result, &job.id);
+            ledger.save()?;
+        }
+
+        job.confession = Some(confession_result);
+        job.metadata = Some(metadata);
+        job.audio_path = Some(job_config.paths.audio_output.clone());
+        job.advance(JobStage::AudioReady, &config.paths.jobs_folder)?;
+
+        notify_with_sound(config, "Audio Created !!!", "data/sounds/Ani_Success.wav").await?;
+    }
+
+    if config.localize.enabled && job.language.is_none() && job.translations.is_empty() {
+        queue_translations(config, job)?;
+    }
+
+    let audio_path = job
+        .audio_path
+        .clone()
+        .ok_or_else(|| Error::State(format!("{} has no audio", job.id)))?;
+    let video_duration = video::get_duration_from_audio(&audio_path)?;
+    println!("Video Duration: {} seconds", video_duration);
+
+    if video_duration <= config.video.max_duration {
+        notify_with_sound(config, "Short Video ...", "data/sounds/Ani_Alert.wav").await?;
+        process_short_video(config, job, options).await?;
+    } else {
+        notify_with_sound(config, "Long Video ...", "data/sounds/Ani_Alert.wav").await?;
+        process_long_video(config, job, options).await?;
+    }
+
+    if options.prepare_only {
+        println!("{} is rendered and queued for upload", job.id);
+        return Ok(());
+    }
+
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    notify_with_sound(
+        config,
+        "Video Created & Uploaded !!!",
+        "data/sounds/Ani_Success.wav",
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Runs the whole pipeline, or any single stage of it, with one validated config.
+///
+/// Built with [`Pipeline::builder`]; every stage method takes the output of the
+/// previous one, so callers can stop, inspect or replace any step.
+#[derive(Debug, Clone)]
+pub struct Pipeline {
+    config: Config,
+    options: RunOptions,
+}
+
+#[derive(Debug, Default)]
+pub struct PipelineBuilder {
+    config: Option<Config>,
+    config_path: Option<String>,
+    overrides: Vec<String>,
+    options: RunOptions,
+}
+
+impl PipelineBuilder {
+    /// Uses `config` as-is instead of loading one. Takes precedence over
+    /// [`config_file`](Self::config_file) and [`set`](Self::set).
+    pub fn config(mut self, config: Config) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    pub fn config_file(mut self, path: impl Into<String>) -> Self {
+        self.config_path = Some(path.into());
+        self
+    }
+
+    /// Adds a `section.key=value` override, applied after the file and environment.
+    pub fn set(mut self, assignment: impl Into<String>) -> Self {
+        self.overrides.push(assignment.into());
+        self
+    }
+
+    pub fn retry_interrupted(mut self, retry_interrupted: bool) -> Self {
+        self.options.retry_interrupted = retry_interrupted;
+        self
+    }
+
+    pub fn prepare_only(mut self, prepare_only: bool) -> Self {
+        self.options.prepare_only = prepare_only;
+        self
+    }
+
+    pub fn build(self) -> Result<Pipeline> {
+        let config = match self.config {
+            Some(config) => {
+                config.validate()?;
+                config
+            }
+            None => Config::load(self.config_path.as_deref(), &self.overrides)?,
+        };
+
+        Ok(Pipeline {
+            config,
+            options: self.options,
+        })
+    }
+}
+
+impl Pipeline {
+    pub fn builder() -> PipelineBuilder {
+        PipelineBuilder::default()
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Picks a random confession long enough for `video.min_duration`, tagged
+    /// with the keyword lexicon's content categories.
+    pub fn pick_confession(&self) -> Result<Confession> {
+        pick_confession(&self.config)
+    }
+
+    /// Adds the LLM's content categories when `classify.use_llm` is set, failing
+    /// when the confession touches a `classify.block` topic.
+    pub async fn classify(&self, confession: &mut Confession) -> Result<()> {
+        let blocked = classify_confession(&self.config, confession).await?;
+        if blocked.is_empty() {
+            return Ok(());
+        }
+        Err(Error::Source(format!(
+            "\"{}\" touches blocked topic {}",
+            confession.title,
+            blocked
+                .iter()
+                .map(|category| category.key())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )))
+    }
+
+    /// Replaces the narration with a hook-first rewrite when `rewrite.enabled` is
+    /// on and the rewrite stays faithful to the confession.
+    pub async fn rewrite(&self, confession: &mut Confession) -> Result<()> {
+        rewrite_confession(&self.config, confession).await
+    }
+
+    /// Adds the closing reaction and call to action when `outro.enabled` is on.
+    pub async fn outro(&self, confession: &mut Confession) -> Result<()> {
+        outro_confession(&self.config, confession).await
+    }
+
+    /// Generates title, description and hashtags, failing on model refusals and
+    /// filtering profanity per `profanity.metadata`.
+    pub async fn generate_metadata(&self, confession: &Confession) -> Result<VideoMetadata> {
+        let mut metadata =
+            generate_metadata(&self.config, &normalized_script(&self.config, confession)).await?;
+        label_metadata(&self.config, confession, &mut metadata);
+        style::label(&self.config.style, &mut metadata);
+        Ok(metadata)
+    }
+
+    /// Translates the narration, outro and metadata into the `localize.languages`
+    /// entry with ISO 639-3 `code`.
+    pub async fn translate(
+        &self,
+        confession: &Confession,
+        metadata: &VideoMetadata,
+        code: &str,
+    ) -> Result<(Confession, VideoMetadata)> {
+        let language = localize::language(&self.config, code)
+            .ok_or_else(|| Error::State(format!("{} is not in localize.languages", code)))?;
+        translate(&self.config, language, confession, metadata).await
+    }
+
+    pub fn generate_tts(&self, confession: &Confession, output_path: &str) -> Result<()> {
+        tts::generate_tts(
+            &self.config.audio,
+            &spoken_script(&self.config, confession),
+            output_path,
+        )
+    }
+
+    pub async fn render(
+        &self,
+        confession: &Confession,
+        audio_path: &str,
+        output_path: &str,
+    ) -> Result<()> {
+        let mut config = self.config.clone();
+        config.paths.audio_output = audio_path.to_string();
+        config.paths.video_output = output_path.to_string();
+        generate_base_video(&config, &caption_script(&config, confession)).await
+    }
+
+    /// Splits a rendered video into parts of at most `video.max_duration` seconds.
+    pub fn split(&self, video_path: &str, output_dir: &str) -> Result<Vec<String>> {
+        let split_result =
+            splitter::split_media(video_path, output_dir, self.config.video.max_duration)?;
+        Ok(split_result
+            .video_paths
+            .iter()
+            .map(|path| path.to_string_lossy().into_owned())
+            .collect())
+    }
+
+    /// Uploads one video and returns its YouTube id when the upload script reports it.
+    /// Uses the episode counter from `paths.episode_file` when `episode` is `None`.
+    pub async fn upload(
+        &self,
+        video_path: &str,
+        metadata: &VideoMetadata,
+        episode: Option<u32>,
+        part: Option<(usize, usize)>,
+    ) -> Result<Option<String>> {
+        let episode = match episode {
+            Some(episode) => episode,
+            None => utils::get_current_episode(&self.config.paths.episode_file)?,
+        };
+        upload_video(&self.config, video_path, metadata, episode, part).await
+    }
+
+    /// Resumes the oldest unfinished job, or runs a new one from scratch.
+    pub async fn run(&self) -> Result<()> {
+        run_pipeline(&self.config, self.options).await
+    }
+
+    pub async fn resume(&self, job_id: &str) -> Result<()> {
+        let mut job = Job::load(&self.config.paths.jobs_folder, job_id)?;
+        run_job(&self.config, &mut job, self.options).await
+    }
+
+    pub fn jobs(&self) -> Result<Vec<Job>> {
+        Job::list(&self.config.paths.jobs_folder)
+    }
+
+    /// How each variant of experiment `name` performed, per the stats file.
+    pub fn experiment_report(&self, name: &str) -> Result<Vec<VariantReport>> {
+        experiment::report(&self.config, name)
+    }
+
+    /// How all videos performed, grouped by mood, category or language.
+    pub fn grouped_report(&self, grouping: Grouping) -> Result<Vec<VariantReport>> {
+        experiment::report_by(&self.config, grouping)
+    }
+
+    /// Runs until Ctrl-C or SIGTERM, uploading at the configured schedule slots.
+    pub async fn run_daemon(&self) -> Result<()> {
+        crate::scheduler::run_daemon(&self.config).await
+    }
+}
@@ -0,0 +1,449 @@
+use regex::{Regex, RegexBuilder};
+use std::collections::BTreeMap;
+
+use crate::confession::Confession;
+use crate::config::{FilterAction, ProfanityConfig, Strictness};
+use crate::metadata::VideoMetadata;
+
+/// How rude a word is. A word counts as profanity when its tier is at or above
+/// the lowest tier the configured [`Strictness`] cares about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Tier {
+    Mild,
+    Moderate,
+    Severe,
+}
+
+/// Word stems per tier. A word matches a stem exactly or with one of
+/// [`SUFFIXES`], so `fuck` also covers `fucking` but `ass` does not cover `assess`.
+const WORDS: &[(&str, Tier)] = &[
+    ("damn", Tier::Mild),
+    ("dammit", Tier::Mild),
+    ("hell", Tier::Mild),
+    ("crap", Tier::Mild),
+    ("piss", Tier::Mild),
+    ("bloody", Tier::Mild),
+    ("bugger", Tier::Mild),
+    ("arse", Tier::Mild),
+    ("ass", Tier::Moderate),
+    ("asshole", Tier::Moderate),
+    ("dumbass", Tier::Moderate),
+    ("jackass", Tier::Moderate),
+    ("shit", Tier::Moderate),
+    ("bullshit", Tier::Moderate),
+    ("horseshit", Tier::Moderate),
+    ("bitch", Tier::Moderate),
+    ("bastard", Tier::Moderate),
+    ("dick", Tier::Moderate),
+    ("prick", Tier::Moderate),
+    ("douche", Tier::Moderate),
+    ("cock", Tier::Moderate),
+    ("tits", Tier::Moderate),
+    ("slut", Tier::Moderate),
+    ("whore", Tier::Moderate),
+    ("fuck", Tier::Severe),
+    ("fuckin", Tier::Severe),
+    ("motherfuck", Tier::Severe),
+    ("cunt", Tier::Severe),
+];
+
+const SUFFIXES: &[&str] = &[
+    "s", "es", "ed", "er", "ers", "ing", "in", "y", "ty", "head", "heads", "hole", "holes",
+];
+
+/// Softer stand-ins for the spoken script, one word for one word so captions
+/// and speech stay aligned. Suffixes carry over: `fucking` becomes `fricking`.
+const SUBSTITUTIONS: &[(&str, &str)] = &[
+    ("damn", "dang"),
+    ("dammit", "dangit"),
+    ("hell", "heck"),
+    ("crap", "crud"),
+    ("piss", "tick"),
+    ("bloody", "very"),
+    ("ass", "butt"),
+    ("asshole", "jerk"),
+    ("dumbass", "dummy"),
+    ("jackass", "jerk"),
+    ("shit", "shoot"),
+    ("shitty", "lousy"),
+    ("bullshit", "nonsense"),
+    ("horseshit", "nonsense"),
+    ("bitch", "jerk"),
+    ("bitching", "whining"),
+    ("bastard", "jerk"),
+    ("dick", "jerk"),
+    ("prick", "jerk"),
+    ("douche", "jerk"),
+    ("fuck", "frick"),
+    ("fuckin", "frickin"),
+    ("motherfucker", "jerk"),
+];
+
+/// Said instead of a profane word that has no substitution.
+const BLEEP: &str = "beep";
+
+/// A match of a profane word inside a text, by byte range.
+struct Hit {
+    start: usize,
+    end: usize,
+    stem: String,
+}
+
+/// Finds profanity and blocked terms, and masks or softens them.
+///
+/// What happens where is configured per surface in `[profanity]`: rejecting a
+/// confession outright, masking captions (`f***`), softening the spoken script,
+/// and masking or rejecting generated titles and descriptions. Each channel's
+/// config file sets its own `level`.
+pub struct ProfanityFilter {
+    config: ProfanityConfig,
+    words: Vec<(String, Tier)>,
+    substitutions: BTreeMap<String, String>,
+    blocked_terms: Vec<(String, Regex)>,
+}
+
+impl ProfanityFilter {
+    pub fn from_config(config: &ProfanityConfig) -> Self {
+        let mut words = WORDS
+            .iter()
+            .map(|(word, tier)| (word.to_string(), *tier))
+            .collect::<Vec<_>>();
+        for (extra, tier) in [
+            (&config.mild_words, Tier::Mild),
+            (&config.moderate_words, Tier::Moderate),
+            (&config.severe_words, Tier::Severe),
+        ] {
+            words.extend(extra.iter().map(|word| (word.trim().to_lowercase(), tier)));
+        }
+        // Longest stems first, so `asshole` is found before `ass`.
+        words.sort_by_key(|(word, _)| std::cmp::Reverse(word.len()));
+
+        let mut substitutions = SUBSTITUTIONS
+            .iter()
+            .map(|(word, soft)| (word.to_string(), soft.to_string()))
+            .collect::<BTreeMap<_, _>>();
+        for (word, soft) in &config.substitutions {
+            substitutions.insert(word.trim().to_lowercase(), soft.clone());
+        }
+
+        // Whole words and phrases only, like the classifier lexicon, so "rape"
+        // does not match "grape".
+        let blocked_terms = config
+            .blocked_terms
+            .iter()
+            .filter(|term| !term.trim().is_empty())
+            .filter_map(|term| {
+                // An escaped literal always compiles.
+                RegexBuilder::new(&format!(r"\b{}\b", regex::escape(term.trim())))
+                    .case_insensitive(true)
+                    .build()
+                    .ok()
+                    .map(|regex| (term.clone(), regex))
+            })
+            .collect();
+
+        Self {
+            config: config.clone(),
+            words,
+            substitutions,
+            blocked_terms,
+        }
+    }
+
+    fn lowest_tier(&self) -> Option<Tier> {
+        match self.config.level {
+            Strictness::Off => None,
+            Strictness::Lenient => Some(Tier::Severe),
+            Strictness::Moderate => Some(Tier::Moderate),
+            Strictness::Strict => Some(Tier::Mild),
+        }
+    }
+
+    fn stem_of(&self, word: &str) -> Option<&str> {
+        let lowest = self.lowest_tier()?;
+        let lowercase = word.to_lowercase();
+        self.words
+            .iter()
+            .filter(|(_, tier)| *tier >= lowest)
+            .find(|(stem, _)| {
+                lowercase
+                    .strip_prefix(stem.as_str())
+                    .is_some_and(|rest| rest.is_empty() || SUFFIXES.contains(&rest))
+            })
+            .map(|(stem, _)| stem.as_str())
+    }
+
+    fn hits(&self, text: &str) -> Vec<Hit> {
+        word_spans(text)
+            .into_iter()
+            .filter_map(|(start, end)| {
+                self.stem_of(&text[start..end]).map(|stem| Hit {
+                    start,
+                    end,
+                    stem: stem.to_string(),
+                })
+            })
+            .collect()
+    }
+
+    /// Profane words in `text` at the configured level, as written.
+    pub fn find(&self, text: &str) -> Vec<String> {
+        self.hits(text)
+            .into_iter()
+            .map(|hit| text[hit.start..hit.end].to_string())
+            .collect()
+    }
+
+    /// Blocked terms (sensitive topics) in `text`; these reject at any level.
+    pub fn blocked_terms(&self, text: &str) -> Vec<String> {
+        self.blocked_terms
+            .iter()
+            .filter(|(_, regex)| regex.is_match(text))
+            .map(|(term, _)| term.clone())
+            .collect()
+    }
+
+    /// Reasons to reject `confession` before anything is generated for it.
+    pub fn check_confession(&self, confession: &Confession) -> Vec<String> {
+        let script = confession.script();
+        let mut reasons = self
+            .blocked_terms(&script)
+            .into_iter()
+            .map(|term| format!("mentions blocked term \"{}\"", term))
+            .collect::<Vec<_>>();
+
+        if self.config.confession == FilterAction::Reject {
+            let found = self.find(&script);
+            if !found.is_empty() {
+                reasons.push(format!("profanity: {}", found.join(", ")));
+            }
+        }
+        reasons
+    }
+
+    /// `fuck` becomes `f***`.
+    pub fn mask(&self, text: &str) -> String {
+        self.replace_hits(text, |word, _| {
+            let mut chars = word.chars();
+            let first = chars.next().map(String::from).unwrap_or_default();
+            first + &"*".repeat(chars.count())
+        })
+    }
+
+    /// Replaces each profane word with a softer one, keeping its capitalization.
+    pub fn soften(&self, text: &str) -> String {
+        self.replace_hits(text, |word, stem| {
+            let lowercase = word.to_lowercase();
+            let soft = self.substitutions.get(&lowercase).cloned().or_else(|| {
+                let suffix = lowercase.strip_prefix(stem).unwrap_or_default();
+                self.substitutions
+                    .get(stem)
+                    .map(|soft| format!("{}{}", soft, suffix))
+            });
+            match_case(word, &soft.unwrap_or_else(|| BLEEP.to_string()))
+        })
+    }
+
+    /// Applies `action` to `text`. `Reject` leaves the text alone; rejecting is
+    /// decided by the caller with [`find`](Self::find).
+    pub fn apply(&self, text: &str, action: FilterAction) -> String {
+        match action {
+            FilterAction::Mask => self.mask(text),
+            FilterAction::Substitute => self.soften(text),
+            FilterAction::Allow | FilterAction::Reject => text.to_string(),
+        }
+    }
+
+    /// Masks the title, description, hook and tags, or returns the offending words
+    /// when `metadata = "reject"`.
+    pub fn filter_metadata(&self, metadata: &mut VideoMetadata) -> Result<(), Vec<String>> {
+        let action = self.config.metadata;
+        if action == FilterAction::Reject {
+            let mut found = self.find(&metadata.title);
+            found.extend(self.find(&metadata.description));
+            found.extend(metadata.keywords.iter().flat_map(|k| self.find(k)));
+            found.extend(metadata.hashtags.iter().flat_map(|h| self.find(h)));
+            found.extend(metadata.hook.iter().flat_map(|hook| self.find(hook)));
+            return if found.is_empty() { Ok(()) } else { Err(found) };
+        }
+
+        metadata.title = self.apply(&metadata.title, action);
+        metadata.description = self.apply(&metadata.description, action);
+        metadata.hook = metadata.hook.as_ref().map(|hook| self.apply(hook, action));
+        // A masked hashtag is useless, so profane ones are dropped instead.
+        if action != FilterAction::Allow {
+            metadata
+                .keywords
+                .retain(|keyword| self.find(keyword).is_empty());
+            metadata
+                .hashtags
+                .retain(|hashtag| self.find(hashtag).is_empty());
+        }
+        Ok(())
+    }
+
+    pub fn caption_action(&self) -> FilterAction {
+        self.config.captions
+    }
+
+    pub fn speech_action(&self) -> FilterAction {
+        self.config.speech
+    }
+
+    fn replace_hits(&self, text: &str, replace: impl Fn(&str, &str) -> String) -> String {
+        let mut result = String::with_capacity(text.len());
+        let mut last = 0;
+        for hit in self.hits(text) {
+            result.push_str(&text[last..hit.start]);
+            result.push_str(&replace(&text[hit.start..hit.end], &hit.stem));
+            last = hit.end;
+        }
+        result.push_str(&text[last..]);
+        result
+    }
+}
+
+/// Byte ranges of the words in `text`. Apostrophes end a word, so `shit's`
+/// matches `shit` and keeps its `'s`.
+fn word_spans(text: &str) -> Vec<(usize, usize)> {
+    let mut spans = Vec::new();
+    let mut start = None;
+    for (i, c) in text
+        .char_indices()
+        .chain(std::iter::once((text.len(), ' ')))
+    {
+        if c.is_alphanumeric() {
+            start.get_or_insert(i);
+        } else if let Some(start) = start.take() {
+            spans.push((start, i));
+        }
+    }
+    spans
+}
+
+fn match_case(original: &str, replacement: &str) -> String {
+    let letters = original
+        .chars()
+        .filter(|c| c.is_alphabetic())
+        .collect::<Vec<_>>();
+    if letters.len() > 1 && letters.iter().all(|c| c.is_uppercase()) {
+        replacement.to_uppercase()
+    } else if original.chars().next().is_some_and(char::is_uppercase) {
+        let mut chars = replacement.chars();
+        chars
+            .next()
+            .map(|first| first.to_uppercase().chain(chars).collect())
+            .unwrap_or_default()
+    } else {
+        replacement.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter() -> ProfanityFilter {
+        ProfanityFilter::from_config(&ProfanityConfig::default())
+    }
+
+    fn metadata(title: &str, hashtags: &[&str]) -> VideoMetadata {
+        serde_json::from_value(serde_json::json!({
+            "title": title,
+            "description": "A shitty week.",
+            "keywords": ["confession", "bullshit"],
+            "hashtags": hashtags,
+            "hook": "What the hell happened?",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn stems_match_whole_words_with_known_suffixes() {
+        let filter = filter();
+        assert_eq!(filter.find("You asshole, kiss my ass."), ["asshole", "ass"]);
+        assert_eq!(
+            filter.find("Fucking shitheads, that's shit's end"),
+            ["Fucking", "shitheads", "shit"]
+        );
+        assert!(filter
+            .find("Assess the class, then pass the cocktail.")
+            .is_empty());
+        // `hell` is mild, below the default moderate level.
+        assert!(filter.find("What the hell").is_empty());
+    }
+
+    #[test]
+    fn level_decides_which_tiers_count() {
+        let strict = ProfanityFilter::from_config(&ProfanityConfig {
+            level: Strictness::Strict,
+            ..ProfanityConfig::default()
+        });
+        let off = ProfanityFilter::from_config(&ProfanityConfig {
+            level: Strictness::Off,
+            ..ProfanityConfig::default()
+        });
+        assert_eq!(strict.find("What the hell, damn it"), ["hell", "damn"]);
+        assert!(off.find("fuck").is_empty());
+    }
+
+    #[test]
+    fn mask_keeps_the_first_letter() {
+        assert_eq!(
+            filter().mask("Well, shit. That bitch's dog."),
+            "Well, s***. That b****'s dog."
+        );
+    }
+
+    #[test]
+    fn soften_keeps_case_and_suffixes() {
+        assert_eq!(
+            filter().soften("FUCK this, Fucking asshole, shitty cunt"),
+            "FRICK this, Fricking jerk, lousy beep"
+        );
+    }
+
+    #[test]
+    fn blocked_terms_match_whole_words_only() {
+        let filter = ProfanityFilter::from_config(&ProfanityConfig {
+            blocked_terms: vec!["rape".to_string(), "self harm".to_string()],
+            ..ProfanityConfig::default()
+        });
+        assert!(filter
+            .blocked_terms("I ate a grape and some drapes.")
+            .is_empty());
+        assert_eq!(
+            filter.blocked_terms("A story about Rape and SELF HARM."),
+            ["rape", "self harm"]
+        );
+    }
+
+    #[test]
+    fn metadata_is_masked_with_profane_tags_dropped() {
+        let mut found = metadata("This shit is wild", &["shorts", "shitty"]);
+        filter().filter_metadata(&mut found).unwrap();
+
+        assert_eq!(found.title, "This s*** is wild");
+        assert_eq!(found.description, "A s***** week.");
+        assert_eq!(found.keywords, ["confession"]);
+        assert_eq!(found.hashtags, ["shorts"]);
+    }
+
+    #[test]
+    fn metadata_is_rejected_when_configured() {
+        let filter = ProfanityFilter::from_config(&ProfanityConfig {
+            metadata: FilterAction::Reject,
+            ..ProfanityConfig::default()
+        });
+        let mut found = metadata("This shit is wild", &["shorts"]);
+        assert_eq!(
+            filter.filter_metadata(&mut found),
+            Err(vec![
+                "shit".to_string(),
+                "shitty".to_string(),
+                "bullshit".to_string()
+            ])
+        );
+        assert_eq!(found.title, "This shit is wild");
+    }
+}
@@ -0,0 +1,214 @@
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+use crate::config::PromptsConfig;
+use crate::error::{Error, Result};
+
+/// Templates shipped with the binary, used when `prompts.directory` has no file
+/// of that name.
+const BUILT_IN: [(&str, &str); 6] = [
+    ("metadata", include_str!("../prompts/metadata.toml")),
+    ("classify", include_str!("../prompts/classify.toml")),
+    ("rewrite", include_str!("../prompts/rewrite.toml")),
+    ("outro", include_str!("../prompts/outro.toml")),
+    ("translate", include_str!("../prompts/translate.toml")),
+    (
+        "translate_metadata",
+        include_str!("../prompts/translate_metadata.toml"),
+    ),
+];
+
+/// A system and user prompt with `{{name}}` placeholders, read from
+/// `<prompts.directory>/<name>.toml`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PromptTemplate {
+    #[serde(skip)]
+    pub name: String,
+    /// Bumped by hand whenever the wording changes.
+    pub version: String,
+    pub system: String,
+    #[serde(default = "default_user")]
+    pub user: String,
+}
+
+fn default_user() -> String {
+    "{{script}}".to_string()
+}
+
+/// A template with every placeholder filled in.
+#[derive(Debug, Clone)]
+pub struct Prompt {
+    pub system: String,
+    pub user: String,
+}
+
+impl PromptTemplate {
+    /// Loads template `name` from `prompts.directory`, falling back to the
+    /// built-in one of the same name.
+    pub fn load(config: &PromptsConfig, name: &str) -> Result<Self> {
+        let path = Path::new(&config.directory).join(format!("{}.toml", name));
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BUILT_IN
+                .iter()
+                .find(|(built_in, _)| *built_in == name)
+                .map(|(_, text)| text.to_string())
+                .ok_or_else(|| {
+                    Error::Llm(format!("Prompt template {} not found", path.display()))
+                })?,
+            Err(e) => {
+                return Err(Error::io(format!(
+                    "Failed to read prompt template {}",
+                    path.display()
+                ))(e))
+            }
+        };
+
+        let mut template: Self = toml::from_str(&text).map_err(|e| {
+            Error::Llm(format!("Invalid prompt template {}: {}", path.display(), e))
+        })?;
+        template.name = name.to_string();
+        template.system = template.system.trim().to_string();
+        template.user = template.user.trim().to_string();
+        Ok(template)
+    }
+
+    /// Identifies exactly which wording produced an output, e.g.
+    /// `metadata@1+5f3c09ab`. The hash catches edits made without bumping
+    /// `version`.
+    pub fn version_id(&self) -> String {
+        // FNV-1a, which unlike `DefaultHasher` is stable across Rust releases.
+        let mut hash: u32 = 0x811c_9dc5;
+        for byte in self.system.bytes().chain([0]).chain(self.user.bytes()) {
+            hash ^= byte as u32;
+            hash = hash.wrapping_mul(0x0100_0193);
+        }
+        format!("{}@{}+{:08x}", self.name, self.version, hash)
+    }
+
+    /// Fills in `{{name}}` placeholders. A placeholder without a value is an
+    /// error rather than being sent to the model as is.
+    pub fn render(&self, vars: &[(&str, &str)]) -> Result<Prompt> {
+        Ok(Prompt {
+            system: self.fill(&self.system, vars)?,
+            user: self.fill(&self.user, vars)?,
+        })
+    }
+
+    fn fill(&self, text: &str, vars: &[(&str, &str)]) -> Result<String> {
+        let mut result = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(start) = rest.find("{{") {
+            let end = rest[start..].find("}}").ok_or_else(|| {
+                Error::Llm(format!("Unclosed {{{{ in prompt template {}", self.name))
+            })?;
+            let key = rest[start + 2..start + end].trim();
+            let value = vars
+                .iter()
+                .find(|(name, _)| *name == key)
+                .map(|(_, value)| *value)
+                .ok_or_else(|| {
+                    Error::Llm(format!(
+                        "Prompt template {} uses unknown placeholder {{{{{}}}}}",
+                        self.name, key
+                    ))
+                })?;
+            result.push_str(&rest[..start]);
+            result.push_str(value);
+            rest = &rest[start + end + 2..];
+        }
+        result.push_str(rest);
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn template(system: &str, user: &str) -> PromptTemplate {
+        PromptTemplate {
+            name: "test".to_string(),
+            version: "1".to_string(),
+            system: system.to_string(),
+            user: user.to_string(),
+        }
+    }
+
+    #[test]
+    fn render_fills_every_placeholder() {
+        let prompt = template(
+            "You are {{ persona }}.",
+            "{{script}}\n\nMax {{max}}, {{max}}.",
+        )
+        .render(&[
+            ("persona", "a narrator"),
+            ("script", "Hi"),
+            ("max", "9"),
+            ("unused", "x"),
+        ])
+        .unwrap();
+        assert_eq!(prompt.system, "You are a narrator.");
+        assert_eq!(prompt.user, "Hi\n\nMax 9, 9.");
+    }
+
+    #[test]
+    fn values_are_not_rendered_again() {
+        let prompt = template("{{a}}", "{{script}}")
+            .render(&[("a", "{{script}}"), ("script", "text")])
+            .unwrap();
+        assert_eq!(prompt.system, "{{script}}");
+    }
+
+    #[test]
+    fn missing_and_unclosed_placeholders_are_errors() {
+        let missing = template("You are {{persona}}.", "{{script}}")
+            .render(&[("script", "Hi")])
+            .unwrap_err();
+        assert!(
+            missing
+                .to_string()
+                .contains("unknown placeholder {{persona}}"),
+            "{missing}"
+        );
+
+        let unclosed = template("You are {{persona.", "{{script}}")
+            .render(&[("persona", "x"), ("script", "Hi")])
+            .unwrap_err();
+        assert!(unclosed.to_string().contains("Unclosed {{"), "{unclosed}");
+    }
+
+    #[test]
+    fn version_id_changes_only_with_the_wording() {
+        let original = template("System", "User");
+        let id = original.version_id();
+        // Stored with every output, so the hash must not change between builds.
+        assert_eq!(id, "test@1+1a2ad9f7");
+        assert_eq!(template("System", "User").version_id(), id);
+        assert_ne!(template("System.", "User").version_id(), id);
+        // The separator keeps text moving between the prompts from hashing the same.
+        assert_ne!(template("SystemU", "ser").version_id(), id);
+
+        let bumped = PromptTemplate {
+            version: "2".to_string(),
+            ..original
+        };
+        assert_eq!(bumped.version_id(), id.replace("@1+", "@2+"));
+    }
+
+    #[test]
+    fn built_in_templates_load_and_unknown_names_fail() {
+        let config = PromptsConfig {
+            directory: "no-such-prompts-dir".to_string(),
+            ..PromptsConfig::default()
+        };
+        for (name, _) in BUILT_IN {
+            let template = PromptTemplate::load(&config, name).unwrap();
+            assert_eq!(template.name, name);
+            assert!(!template.system.is_empty());
+        }
+        assert!(PromptTemplate::load(&config, "missing").is_err());
+    }
+}
@@ -0,0 +1,352 @@
+use std::collections::HashSet;
+
+use crate::classifier::{Classifier, ContentCategory};
+use crate::confession::Confession;
+use crate::config::Config;
+use crate::error::Result;
+use crate::llm;
+use crate::normalizer::{self, Normalizer};
+use crate::prompts::PromptTemplate;
+use crate::utils::SECONDS_PER_WORD;
+use crate::validator::{Field, Validator};
+
+/// Words too common to say anything about whether the rewrite sticks to the story.
+const STOPWORDS: &[&str] = &[
+    "the", "and", "but", "for", "nor", "yet", "was", "were", "are", "is", "been", "being", "have",
+    "has", "had", "did", "does", "doing", "done", "will", "would", "could", "should", "can", "may",
+    "might", "must", "shall", "this", "that", "these", "those", "there", "here", "then", "than",
+    "when", "where", "what", "which", "who", "whom", "whose", "why", "how", "all", "any", "some",
+    "each", "every", "few", "more", "most", "much", "many", "other", "such", "only", "own", "same",
+    "very", "just", "also", "too", "not", "now", "even", "still", "ever", "never", "again", "once",
+    "about", "after", "before", "into", "onto", "from", "with", "without", "over", "under", "out",
+    "off", "our", "your", "their", "his", "her", "its", "him", "them", "they", "she", "you",
+    "myself",
+];
+
+/// Leading characters compared between words, so "apologized" in the rewrite
+/// matches "apologizing" in the story.
+const STEM_CHARS: usize = 5;
+
+/// How many unsupported words are quoted back to the model.
+const MAX_QUOTED_WORDS: usize = 8;
+
+/// Word limits for a rewrite, from `rewrite.target_duration`, `video.min_duration`
+/// and `rewrite.hook_seconds` at the speaking rate the duration estimate uses.
+/// Both durations cover the whole voice-over, so the content warning and the
+/// outro spoken around the narration come out of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Budget {
+    pub min_words: usize,
+    pub max_words: usize,
+    /// Longest allowed first sentence.
+    pub hook_words: usize,
+}
+
+impl Budget {
+    /// A rewrite never runs longer than the original plus its hook, and never
+    /// needs to run longer than the original: it is meant to trim, not pad.
+    /// `content` is the confession's content categories, which decide its warning.
+    pub fn new(config: &Config, content: &[ContentCategory], original_words: usize) -> Self {
+        let words = |seconds: f32| (seconds / SECONDS_PER_WORD).round() as usize;
+        // The outro is written after the rewrite, so its full allowance is kept free.
+        let outro_words = if config.outro.enabled {
+            words(config.outro.max_seconds)
+        } else {
+            0
+        };
+        let warning_words = Classifier::from_config(&config.classify)
+            .warning_text(content)
+            .map_or(0, |warning| warning.split_whitespace().count());
+        let reserved = outro_words + warning_words;
+
+        let hook_words = words(config.rewrite.hook_seconds).max(1);
+        let max_words = words(config.rewrite.target_duration)
+            .saturating_sub(reserved)
+            .min(original_words + hook_words);
+        Self {
+            min_words: words(config.video.min_duration)
+                .saturating_sub(reserved)
+                .min(original_words)
+                .min(max_words),
+            max_words,
+            hook_words,
+        }
+    }
+
+    /// What is wrong with the narration's length, as feedback for the model.
+    pub fn check(&self, narration: &str) -> Vec<String> {
+        let mut problems = Vec::new();
+        let words = narration.split_whitespace().count();
+        if words > self.max_words {
+            problems.push(format!(
+                "it is {} words long, at most {} allowed",
+                words, self.max_words
+            ));
+        } else if words < self.min_words {
+            problems.push(format!(
+                "it is {} words long, at least {} needed",
+                words, self.min_words
+            ));
+        }
+
+        let hook = narration
+            .split_inclusive(['.', '!', '?'])
+            .next()
+            .unwrap_or_default();
+        let hook_length = hook.split_whitespace().count();
+        if hook_length > self.hook_words {
+            problems.push(format!(
+                "the opening sentence is {} words long; make it a hook of at most {} words",
+                hook_length, self.hook_words
+            ));
+        }
+        problems
+    }
+}
+
+/// How closely a rewrite sticks to the original story.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Faithfulness {
+    /// Share of the rewrite's content words that appear in the original.
+    pub overlap: f32,
+    /// Names and numbers the original does not contain.
+    pub invented: Vec<String>,
+    /// Content words the original does not contain.
+    pub unsupported: Vec<String>,
+}
+
+impl Faithfulness {
+    /// Compares `rewrite` against the words of every text in `originals`, e.g.
+    /// the raw and the normalized script, so "22" matches "twenty-two".
+    pub fn measure(originals: &[&str], rewrite: &str) -> Self {
+        let vocabulary: HashSet<String> = originals
+            .iter()
+            .flat_map(|text| words(text))
+            .map(|word| stem(&word))
+            .collect();
+        let supported = |word: &str| {
+            if let Ok(n) = word.parse::<u64>() {
+                return normalizer::number_words(n)
+                    .split(|c: char| !c.is_alphanumeric())
+                    .filter(|part| !part.is_empty())
+                    .all(|part| vocabulary.contains(&stem(part)))
+                    || vocabulary.contains(&stem(word));
+            }
+            vocabulary.contains(&stem(word))
+        };
+
+        let mut invented = Vec::new();
+        for word in entities(rewrite) {
+            let lowercase = word.to_lowercase();
+            if !supported(&lowercase) && !invented.contains(&word) {
+                invented.push(word);
+            }
+        }
+
+        let content: Vec<String> = words(rewrite)
+            .into_iter()
+            .filter(|word| word.chars().count() >= 3 && !STOPWORDS.contains(&word.as_str()))
+            .collect();
+        let mut unsupported = Vec::new();
+        let mut missing = 0;
+        for word in &content {
+            if !supported(word) {
+                missing += 1;
+                if !unsupported.contains(word) {
+                    unsupported.push(word.clone());
+                }
+            }
+        }
+
+        Self {
+            overlap: if content.is_empty() {
+                1.0
+            } else {
+                1.0 - missing as f32 / content.len() as f32
+            },
+            invented,
+            unsupported,
+        }
+    }
+
+    /// What makes the rewrite unfaithful, as feedback for the model.
+    pub fn check(&self, min_overlap: f32) -> Vec<String> {
+        let mut problems = Vec::new();
+        if !self.invented.is_empty() {
+            problems.push(format!(
+                "it mentions {} which the confession does not; do not invent details",
+                self.invented.join(", ")
+            ));
+        }
+        if self.overlap < min_overlap {
+            let quoted: Vec<&str> = self
+                .unsupported
+                .iter()
+                .take(MAX_QUOTED_WORDS)
+                .map(String::as_str)
+                .collect();
+            problems.push(format!(
+                "only {:.0}% of its words come from the confession (words like {}); stay closer \
+                 to the original",
+                self.overlap * 100.0,
+                quoted.join(", ")
+            ));
+        }
+        problems
+    }
+}
+
+/// Lowercase words, apostrophes removed.
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric() && c != '\'' && c != '\u{2019}')
+        .map(|word| {
+            word.trim_end_matches("'s")
+                .trim_end_matches("\u{2019}s")
+                .replace(['\'', '\u{2019}'], "")
+                .to_lowercase()
+        })
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+fn stem(word: &str) -> String {
+    word.chars().take(STEM_CHARS).collect()
+}
+
+/// Capitalized words that do not start a sentence, and numbers: the details a
+/// rewrite most often makes up.
+fn entities(text: &str) -> Vec<String> {
+    let mut found = Vec::new();
+    for sentence in text.split(['.', '!', '?', ':', '"', '\u{201c}', '\u{201d}']) {
+        let tokens = sentence
+            .split(|c: char| !c.is_alphanumeric() && c != '\'' && c != '\u{2019}')
+            .filter(|token| !token.is_empty());
+        for (index, token) in tokens.enumerate() {
+            let token = token
+                .trim_end_matches("'s")
+                .trim_end_matches("\u{2019}s")
+                .trim_matches(['\'', '\u{2019}']);
+            let is_number = token.chars().all(|c| c.is_ascii_digit());
+            let is_name = index > 0
+                && token.chars().next().is_some_and(char::is_uppercase)
+                && token != "I"
+                && !token.starts_with("I'")
+                && !token.starts_with("I\u{2019}");
+            if !token.is_empty() && (is_number || is_name) {
+                found.push(token.to_string());
+            }
+        }
+    }
+    found
+}
+
+/// Rewrites the confession into a hook-first narration with the `prompts.rewrite`
+/// template, within the [`Budget`] and checked for [`Faithfulness`] and by the
+/// [`Validator`]. Faulty rewrites are retried up to `llm.max_retries` times with
+/// the problems fed back. `None` means no acceptable rewrite was produced and the
+/// original should be narrated.
+pub async fn rewrite(config: &Config, confession: &Confession) -> Result<Option<String>> {
+    let original = Normalizer::from_config(&config.normalize).script(confession);
+    let raw = confession.script();
+    let budget = Budget::new(
+        config,
+        &confession.content,
+        original.split_whitespace().count(),
+    );
+
+    let template = PromptTemplate::load(&config.prompts, &config.prompts.rewrite)?;
+    let (min_words, max_words, hook_words) = (
+        budget.min_words.to_string(),
+        budget.max_words.to_string(),
+        budget.hook_words.to_string(),
+    );
+    let rendered = template.render(&[
+        ("script", &original),
+        ("min_words", &min_words),
+        ("max_words", &max_words),
+        ("hook_words", &hook_words),
+    ])?;
+
+    let backend = llm::open(&config.llm)?;
+    let validator = Validator::new(config, &rendered.system);
+    let mut prompt = rendered.user.clone();
+    let mut last_problem = String::new();
+
+    for attempt in 1..=config.llm.max_retries {
+        let reply = backend.chat(&rendered.system, &prompt).await?;
+        let narration = reply.split_whitespace().collect::<Vec<_>>().join(" ");
+
+        let report = validator.check_script(Field::Narration, &narration, &original);
+        if report.refused() {
+            eprintln!(
+                "{} refused to rewrite \"{}\": {}",
+                backend.describe(),
+                confession.title,
+                report
+            );
+            return Ok(None);
+        }
+        let mut problems: Vec<String> = report.issues.iter().map(|i| i.to_string()).collect();
+        problems.extend(budget.check(&narration));
+        problems.extend(
+            Faithfulness::measure(&[&raw, &original], &narration).check(config.rewrite.min_overlap),
+        );
+        if problems.is_empty() {
+            backend.commit(&reply);
+            return Ok(Some(narration));
+        }
+
+        let problem = problems.join("; ");
+        eprintln!(
+            "Rewrite {} of {} was rejected: {}",
+            attempt, config.llm.max_retries, problem
+        );
+        prompt = format!(
+            "{}\n\nYour previous narration was:\n{}\n\nIt was rejected: {}. Write it again.",
+            rendered.user, narration, problem
+        );
+        last_problem = problem;
+    }
+
+    eprintln!(
+        "No acceptable rewrite of \"{}\" after {} attempts ({}); narrating the original",
+        confession.title, config.llm.max_retries, last_problem
+    );
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn budget_leaves_room_for_the_outro_and_warning() {
+        let mut config = Config::default();
+        config.rewrite.enabled = true;
+        let plain = Budget::new(&config, &[], 1000);
+        assert_eq!(plain.max_words, 135);
+
+        config.outro.enabled = true;
+        let with_outro = Budget::new(&config, &[], 1000);
+        assert_eq!(with_outro.max_words, 135 - 15);
+        assert_eq!(with_outro.min_words, 125 - 15);
+
+        let warning = Classifier::from_config(&config.classify)
+            .warning_text(&[ContentCategory::SelfHarm])
+            .expect("self-harm is warned about by default");
+        let warned = Budget::new(&config, &[ContentCategory::SelfHarm], 1000);
+        assert_eq!(
+            warned.max_words,
+            with_outro.max_words - warning.split_whitespace().count()
+        );
+    }
+
+    #[test]
+    fn budget_neither_pads_nor_exceeds_the_original_plus_hook() {
+        let budget = Budget::new(&Config::default(), &[], 40);
+        assert_eq!(budget.max_words, 45);
+        assert_eq!(budget.min_words, 40);
+        let unpadded = format!("It happened. {}", "word ".repeat(38));
+        assert!(budget.check(&unpadded).is_empty());
+    }
+}
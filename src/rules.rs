@@ -0,0 +1,278 @@
+use regex::{Regex, RegexBuilder};
+
+use crate::confession::Confession;
+use crate::config::RulesConfig;
+use crate::error::{Error, Result};
+
+/// Why a confession was rejected; empty when it passed every rule.
+#[derive(Debug, Clone, Default)]
+pub struct Verdict {
+    pub reasons: Vec<String>,
+}
+
+impl Verdict {
+    pub fn passed(&self) -> bool {
+        self.reasons.is_empty()
+    }
+}
+
+/// The `[rules]` section compiled once: keywords lowercased, patterns built.
+pub struct Rules {
+    config: RulesConfig,
+    banned_keywords: Vec<String>,
+    banned_patterns: Vec<Regex>,
+    edit_markers: Vec<String>,
+}
+
+impl Rules {
+    pub fn from_config(config: &RulesConfig) -> Result<Self> {
+        let banned_patterns = config
+            .banned_patterns
+            .iter()
+            .map(|pattern| compile_pattern(pattern))
+            .collect::<Result<Vec<_>>>()?;
+        let lowercase = |values: &[String]| {
+            values
+                .iter()
+                .map(|value| value.trim().to_lowercase())
+                .filter(|value| !value.is_empty())
+                .collect::<Vec<_>>()
+        };
+
+        Ok(Self {
+            config: config.clone(),
+            banned_keywords: lowercase(&config.banned_keywords),
+            banned_patterns,
+            edit_markers: lowercase(&config.edit_markers),
+        })
+    }
+
+    /// Runs every rule and collects all the reasons it fails, not just the first.
+    pub fn check(&self, confession: &Confession) -> Verdict {
+        let config = &self.config;
+        let script = confession.script();
+        let lowercase = script.to_lowercase();
+        let words = script.split_whitespace().collect::<Vec<_>>();
+        let mut reasons = Vec::new();
+
+        if let Some(min) = config.min_words {
+            if words.len() < min {
+                reasons.push(format!("{} words, fewer than {}", words.len(), min));
+            }
+        }
+        if let Some(max) = config.max_words {
+            if words.len() > max {
+                reasons.push(format!("{} words, more than {}", words.len(), max));
+            }
+        }
+
+        if let Some(min) = config.min_score {
+            match confession.score {
+                Some(score) if score < min => {
+                    reasons.push(format!("score {} below {}", score, min));
+                }
+                None if config.require_score => reasons.push("no score".to_string()),
+                _ => {}
+            }
+        }
+
+        if !config.allow_nsfw && confession.over_18 == Some(true) {
+            reasons.push("marked NSFW".to_string());
+        }
+
+        if !config.languages.is_empty() {
+            match whatlang::detect(&script) {
+                Some(info) => {
+                    let code = info.lang().code();
+                    if !config
+                        .languages
+                        .iter()
+                        .any(|lang| lang.trim().eq_ignore_ascii_case(code))
+                    {
+                        reasons.push(format!("language {} not allowed", code));
+                    }
+                }
+                None => reasons.push("language could not be detected".to_string()),
+            }
+        }
+
+        for keyword in &self.banned_keywords {
+            if lowercase.contains(keyword.as_str()) {
+                reasons.push(format!("contains banned keyword \"{}\"", keyword));
+            }
+        }
+        for pattern in &self.banned_patterns {
+            if pattern.is_match(&script) {
+                reasons.push(format!("matches banned pattern /{}/", pattern.as_str()));
+            }
+        }
+
+        if !words.is_empty() {
+            let urls = words.iter().filter(|word| is_url(word)).count();
+            let density = urls as f32 / words.len() as f32;
+            if density > config.max_url_density {
+                reasons.push(format!("{} links in {} words", urls, words.len()));
+            }
+        }
+
+        let letters = script.chars().filter(|c| c.is_alphabetic()).count();
+        if letters > 0 {
+            let capitals = script.chars().filter(|c| c.is_uppercase()).count();
+            let ratio = capitals as f32 / letters as f32;
+            if ratio > config.max_caps_ratio {
+                reasons.push(format!("{:.0}% capital letters", ratio * 100.0));
+            }
+        }
+
+        if config.reject_edit_markers {
+            if let Some(marker) = self
+                .edit_markers
+                .iter()
+                .find(|marker| contains_marker(&lowercase, marker))
+            {
+                reasons.push(format!("contains edit marker \"{}\"", marker));
+            }
+        }
+
+        Verdict { reasons }
+    }
+}
+
+/// Patterns are matched case-insensitively, like keywords.
+fn compile_pattern(pattern: &str) -> Result<Regex> {
+    RegexBuilder::new(pattern)
+        .case_insensitive(true)
+        .build()
+        .map_err(|e| Error::Source(format!("Invalid banned pattern /{}/: {}", pattern, e)))
+}
+
+/// Markers only count at the start of a word, so "edit:" does not match "credit:".
+fn contains_marker(text: &str, marker: &str) -> bool {
+    text.match_indices(marker).any(|(at, _)| {
+        text[..at]
+            .chars()
+            .next_back()
+            .is_none_or(|c| !c.is_alphanumeric())
+    })
+}
+
+fn is_url(word: &str) -> bool {
+    let word = word.trim_start_matches(['(', '[', '<']);
+    word.starts_with("http://") || word.starts_with("https://") || word.starts_with("www.")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::confession;
+
+    fn post(title: &str, body: &str, score: Option<&str>, over_18: bool) -> Confession {
+        confession::confession_from_fields(|field| match field {
+            "title" => Some(title.to_string()),
+            "selftext" => Some(body.to_string()),
+            "score" => score.map(String::from),
+            "over_18" => Some(over_18.to_string()),
+            _ => None,
+        })
+        .unwrap()
+    }
+
+    fn check(config: &RulesConfig, confession: &Confession) -> Vec<String> {
+        Rules::from_config(config)
+            .unwrap()
+            .check(confession)
+            .reasons
+    }
+
+    #[test]
+    fn defaults_pass_an_ordinary_post() {
+        let found = post(
+            "I never told anyone",
+            "Edit: thanks for reading. I ate my sister's cake.",
+            None,
+            false,
+        );
+        assert!(check(&RulesConfig::default(), &found).is_empty());
+    }
+
+    #[test]
+    fn collects_every_failing_rule() {
+        let config = RulesConfig {
+            min_words: Some(20),
+            min_score: Some(10),
+            allow_nsfw: false,
+            banned_keywords: vec!["Cake".to_string()],
+            banned_patterns: vec![r"sister'?s".to_string()],
+            max_caps_ratio: 0.2,
+            ..RulesConfig::default()
+        };
+        let found = post(
+            "I NEVER TOLD ANYONE",
+            "I ate my sister's cake.",
+            Some("3"),
+            true,
+        );
+
+        assert_eq!(
+            check(&config, &found),
+            [
+                "9 words, fewer than 20",
+                "score 3 below 10",
+                "marked NSFW",
+                "contains banned keyword \"cake\"",
+                "matches banned pattern /sister'?s/",
+                "52% capital letters",
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_scores_fail_only_when_required() {
+        let mut config = RulesConfig {
+            min_score: Some(10),
+            ..RulesConfig::default()
+        };
+        let found = post("Title", "Body text.", None, false);
+        assert!(check(&config, &found).is_empty());
+
+        config.require_score = true;
+        assert_eq!(check(&config, &found), ["no score"]);
+    }
+
+    #[test]
+    fn link_heavy_posts_are_rejected() {
+        let found = post(
+            "Links",
+            "see https://a.example and (www.b.example)",
+            None,
+            false,
+        );
+        assert_eq!(
+            check(&RulesConfig::default(), &found),
+            ["2 links in 5 words"]
+        );
+    }
+
+    #[test]
+    fn edit_markers_only_count_when_enabled_and_at_a_word_start() {
+        let config = RulesConfig {
+            reject_edit_markers: true,
+            ..RulesConfig::default()
+        };
+        let edited = post("Title", "It happened. Edit: it got worse.", None, false);
+        let credit = post("Title", "My store credit: ten dollars.", None, false);
+
+        assert_eq!(check(&config, &edited), ["contains edit marker \"edit:\""]);
+        assert!(check(&config, &credit).is_empty());
+        assert!(check(&RulesConfig::default(), &edited).is_empty());
+    }
+
+    #[test]
+    fn invalid_patterns_are_reported() {
+        let config = RulesConfig {
+            banned_patterns: vec!["(unclosed".to_string()],
+            ..RulesConfig::default()
+        };
+        assert!(Rules::from_config(&config).is_err());
+    }
+}
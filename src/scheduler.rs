@@ -0,0 +1,219 @@
+use chrono::{DateTime, Days, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use std::time::Duration;
+use tokio::sync::watch;
+
+use crate::config::{Config, ScheduleConfig};
+use crate::error::{Error, Recovery, Result};
+use crate::job::{Job, JobStage};
+use crate::pipeline::{run_job, RunOptions};
+
+/// Pause after a failed preparation so a persistent failure does not spin.
+const RETRY_DELAY: Duration = Duration::from_secs(60);
+
+pub struct Schedule {
+    timezone: Tz,
+    slots: Vec<NaiveTime>,
+}
+
+impl Schedule {
+    pub fn from_config(config: &ScheduleConfig) -> Result<Self> {
+        let timezone = config
+            .timezone
+            .parse::<Tz>()
+            .map_err(|e| Error::State(format!("Invalid schedule timezone: {}", e)))?;
+
+        let mut slots = if config.slots.is_empty() {
+            let step = 24 * 60 * 60 / config.videos_per_day.max(1) as u32;
+            (0..config.videos_per_day as u32)
+                .filter_map(|i| NaiveTime::from_num_seconds_from_midnight_opt(i * step, 0))
+                .collect::<Vec<_>>()
+        } else {
+            config
+                .slots
+                .iter()
+                .map(|slot| {
+                    NaiveTime::parse_from_str(slot, "%H:%M").map_err(|e| {
+                        Error::State(format!("Invalid schedule slot '{}': {}", slot, e))
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?
+        };
+        slots.sort();
+        slots.dedup();
+
+        if slots.is_empty() {
+            return Err(Error::State("The schedule has no upload slots".to_string()));
+        }
+
+        Ok(Self { timezone, slots })
+    }
+
+    /// The first slot strictly after `now`. Slot times that fall into a DST gap
+    /// on a given day are skipped for that day.
+    pub fn next_slot_after(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let today = now.with_timezone(&self.timezone).date_naive();
+
+        (0..=2)
+            .filter_map(|offset| today.checked_add_days(Days::new(offset)))
+            .flat_map(|date| self.slots.iter().map(move |slot| date.and_time(*slot)))
+            .filter_map(|local| self.timezone.from_local_datetime(&local).earliest())
+            .map(|local| local.with_timezone(&Utc))
+            .find(|slot| *slot > now)
+    }
+
+    pub fn timezone(&self) -> Tz {
+        self.timezone
+    }
+}
+
+/// Jobs that are rendered and split but not uploaded yet, oldest first.
+fn queued_jobs(config: &Config) -> Result<Vec<Job>> {
+    Ok(Job::list(&config.paths.jobs_folder)?
+        .into_iter()
+        .filter(|job| job.stage >= JobStage::Split && job.stage != JobStage::Done)
+        .collect())
+}
+
+/// Continues the oldest job that is not rendered yet, or starts a new one, and
+/// stops it right before the upload.
+async fn prepare_next_job(config: &Config) -> Result<()> {
+    let unprepared = Job::list(&config.paths.jobs_folder)?
+        .into_iter()
+        .find(|job| job.stage < JobStage::Split);
+
+    let mut job = match unprepared {
+        Some(job) => job,
+        None => Job::create(config)?,
+    };
+
+    let options = RunOptions {
+        prepare_only: true,
+        ..RunOptions::default()
+    };
+    run_job(config, &mut job, options).await
+}
+
+/// Uploads the oldest queued job, preparing one on the spot if the queue is empty.
+/// Jobs with an interrupted upload are left alone until resumed by hand.
+async fn upload_next_job(config: &Config) -> Result<()> {
+    let queued = queued_jobs(config)?;
+    for job in queued
+        .iter()
+        .filter(|job| !job.interrupted_parts().is_empty())
+    {
+        eprintln!(
+            "{} was interrupted mid-upload; check the channel and run `resume --job {}`",
+            job.id, job.id
+        );
+    }
+
+    let next = queued
+        .into_iter()
+        .find(|job| job.interrupted_parts().is_empty());
+    let mut job = match next {
+        Some(job) => job,
+        None => {
+            println!("Upload queue is empty, preparing a video now");
+            prepare_next_job(config).await?;
+            queued_jobs(config)?
+                .into_iter()
+                .find(|job| job.interrupted_parts().is_empty())
+                .ok_or_else(|| Error::State("No video is ready to upload".to_string()))?
+        }
+    };
+
+    run_job(config, &mut job, RunOptions::default()).await
+}
+
+fn spawn_shutdown_listener() -> watch::Receiver<bool> {
+    let (sender, receiver) = watch::channel(false);
+    tokio::spawn(async move {
+        wait_for_shutdown_signal().await;
+        println!("Shutdown requested, finishing the current step...");
+        let _ = sender.send(true);
+    });
+    receiver
+}
+
+#[cfg(unix)]
+async fn wait_for_shutdown_signal() {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    match signal(SignalKind::terminate()) {
+        Ok(mut sigterm) => {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {}
+                _ = sigterm.recv() => {}
+            }
+        }
+        Err(e) => {
+            eprintln!("Failed to listen for SIGTERM: {}", e);
+            let _ = tokio::signal::ctrl_c().await;
+        }
+    }
+}
+
+#[cfg(not(unix))]
+async fn wait_for_shutdown_signal() {
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+/// Sleeps for `duration` unless a shutdown is requested first. Returns `false`
+/// when the daemon should stop.
+async fn sleep_unless_shutdown(duration: Duration, shutdown: &mut watch::Receiver<bool>) -> bool {
+    tokio::select! {
+        _ = tokio::time::sleep(duration) => true,
+        _ = shutdown.changed() => false,
+    }
+}
+
+/// Runs until Ctrl-C or SIGTERM. Between upload slots the queue is topped up to
+/// `schedule.queue_size` rendered videos. Preparation is abandoned on shutdown
+/// (the job resumes on the next start), but an upload in progress is always
+/// allowed to finish so no part is left half-uploaded.
+pub async fn run_daemon(config: &Config) -> Result<()> {
+    let schedule = Schedule::from_config(&config.schedule)?;
+    let mut shutdown = spawn_shutdown_listener();
+
+    'daemon: while !*shutdown.borrow() {
+        let next_slot = schedule
+            .next_slot_after(Utc::now())
+            .ok_or_else(|| Error::State("No upcoming upload slot in the schedule".to_string()))?;
+        println!(
+            "Next upload slot: {}",
+            next_slot.with_timezone(&schedule.timezone())
+        );
+
+        while queued_jobs(config)?.len() < config.schedule.queue_size && Utc::now() < next_slot {
+            let result = tokio::select! {
+                result = prepare_next_job(config) => result,
+                _ = shutdown.changed() => break 'daemon,
+            };
+            if let Err(e) = result {
+                eprintln!("Failed to prepare the next video: {}", e);
+                if e.recovery() == Recovery::Abort {
+                    return Err(e);
+                }
+                if !sleep_unless_shutdown(RETRY_DELAY, &mut shutdown).await {
+                    break 'daemon;
+                }
+            }
+        }
+
+        let wait = (next_slot - Utc::now()).to_std().unwrap_or_default();
+        if !sleep_unless_shutdown(wait, &mut shutdown).await {
+            break;
+        }
+
+        if let Err(e) = upload_next_job(config).await {
+            eprintln!("Scheduled upload failed: {}", e);
+            if e.recovery() == Recovery::Abort {
+                return Err(e);
+            }
+        }
+    }
+
+    println!("Daemon stopped");
+    Ok(())
+}
@@ -0,0 +1,374 @@
+use rand::prelude::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::classifier::Classifier;
+use crate::confession::{self, ColumnMap, Confession};
+use crate::config::{Config, Selection, SourceConfig, SourceKind};
+use crate::csv_index::CsvIndex;
+use crate::error::{Error, Result};
+use crate::ledger::Ledger;
+use crate::ndjson_source::NdjsonSource;
+use crate::profanity::ProfanityFilter;
+use crate::rules::Rules;
+use crate::sqlite_source::SqliteSource;
+
+/// Somewhere confessions can be drawn from at random.
+///
+/// Implementations only return confessions with a usable title and body; the
+/// `[rules]`, `[profanity]`, `[classify]` and the used-confession ledger are
+/// applied on top by [`open`].
+pub trait ConfessionSource: Send {
+    fn random_confession(&self) -> Result<Confession>;
+}
+
+/// How many confessions one pick may draw from the source, rejected or not,
+/// before it settles for what it has or gives up.
+const MAX_DRAWS: usize = 50;
+
+/// Opens the source selected by `source.kind`, skipping confessions that are
+/// already in the used-confession ledger or fail the `[rules]`, `[profanity]` or
+/// `[classify]` checks. Returned confessions carry their lexicon categories.
+pub fn open(config: &Config) -> Result<Box<dyn ConfessionSource>> {
+    let source = &config.source;
+    let inner: Box<dyn ConfessionSource> = match source.kind {
+        SourceKind::Csv => Box::new(CsvSource::open(source)?),
+        SourceKind::Ndjson => Box::new(NdjsonSource::new(source)),
+        SourceKind::Directory => Box::new(DirectorySource::new(source)),
+        SourceKind::Sqlite => Box::new(SqliteSource::open(source)?),
+    };
+
+    Ok(Box::new(FilteredSource {
+        inner,
+        ledger: Ledger::load(&source.ledger_path)?,
+        threshold: source.near_duplicate_threshold,
+        rules: Rules::from_config(&config.rules)?,
+        profanity: ProfanityFilter::from_config(&config.profanity),
+        classifier: Classifier::from_config(&config.classify),
+        selection: config.rules.selection,
+        candidates: config.rules.candidates,
+    }))
+}
+
+/// Wraps another source and redraws until it returns a confession that passes
+/// the rules and neither matches nor closely resembles one in the ledger.
+pub struct FilteredSource {
+    inner: Box<dyn ConfessionSource>,
+    ledger: Ledger,
+    threshold: f32,
+    rules: Rules,
+    profanity: ProfanityFilter,
+    classifier: Classifier,
+    selection: Selection,
+    candidates: usize,
+}
+
+impl FilteredSource {
+    /// Labels `found` with its lexicon categories, or logs why it is rejected.
+    fn accept(&self, mut found: Confession) -> Option<Confession> {
+        found.content = self.classifier.lexicon_categories(&found.script());
+
+        let mut verdict = self.rules.check(&found);
+        verdict
+            .reasons
+            .extend(self.profanity.check_confession(&found));
+        for category in self.classifier.blocked(&found.content) {
+            verdict
+                .reasons
+                .push(format!("touches blocked topic {}", category.key()));
+        }
+        if !verdict.passed() {
+            println!(
+                "Skipping \"{}\": {}",
+                found.title,
+                verdict.reasons.join(", ")
+            );
+            return None;
+        }
+
+        match self.ledger.find_duplicate(&found, self.threshold) {
+            None => Some(found),
+            Some((duplicate, entry)) => {
+                println!(
+                    "Skipping \"{}\": already used by {} ({})",
+                    found.title, entry.job_id, duplicate
+                );
+                None
+            }
+        }
+    }
+}
+
+impl ConfessionSource for FilteredSource {
+    /// Draws until it has one passing confession, or `candidates` of them in
+    /// ranked mode, within [`MAX_DRAWS`] draws in total. Ranked mode keeps the
+    /// highest scored of however many it found.
+    fn random_confession(&self) -> Result<Confession> {
+        let wanted = match self.selection {
+            Selection::Uniform => 1,
+            Selection::Ranked => self.candidates.max(1),
+        };
+
+        let mut accepted = Vec::with_capacity(wanted);
+        for _ in 0..MAX_DRAWS {
+            if accepted.len() == wanted {
+                break;
+            }
+            if let Some(found) = self.accept(self.inner.random_confession()?) {
+                accepted.push(found);
+            }
+        }
+
+        accepted
+            .into_iter()
+            .max_by_key(|found| found.score.unwrap_or(i64::MIN))
+            .ok_or_else(|| {
+                Error::Source(format!(
+                    "None of {} drawn confessions passed; the rules may be too strict or \
+                     the source exhausted",
+                    MAX_DRAWS
+                ))
+            })
+    }
+}
+
+/// A CSV dump read through its byte-offset index, so a pick costs one seek.
+pub struct CsvSource {
+    csv_path: String,
+    index_path: String,
+    columns: ColumnMap,
+    index: CsvIndex,
+}
+
+impl CsvSource {
+    /// Resolves the columns from the header row and opens the index, building or
+    /// refreshing it first when needed.
+    pub fn open(config: &SourceConfig) -> Result<Self> {
+        let csv_path = config.csv_path.clone();
+        let index_path = config
+            .index_path
+            .clone()
+            .unwrap_or_else(|| CsvIndex::default_path(&csv_path));
+
+        let headers = CsvIndex::headers(&csv_path)?;
+        let names = headers.iter().collect::<Vec<_>>();
+        let columns = ColumnMap::resolve(&names, &config.columns)?;
+
+        let subreddits = config
+            .subreddits
+            .iter()
+            .map(|name| name.trim().to_lowercase())
+            .collect::<Vec<_>>();
+        let fingerprint = columns.fingerprint(&subreddits);
+        let index = CsvIndex::open_or_build(&csv_path, &index_path, fingerprint, |record| {
+            extract(record, &columns)
+                .is_some_and(|found| config.accepts_subreddit(found.subreddit.as_deref()))
+        })?;
+
+        Ok(Self {
+            csv_path,
+            index_path,
+            columns,
+            index,
+        })
+    }
+}
+
+fn extract(record: &csv::StringRecord, columns: &ColumnMap) -> Option<Confession> {
+    confession::confession_from_fields(|field| {
+        columns
+            .position(field)
+            .and_then(|column| record.get(column))
+            .map(str::to_string)
+    })
+}
+
+impl ConfessionSource for CsvSource {
+    fn random_confession(&self) -> Result<Confession> {
+        if self.index.is_empty() {
+            return Err(Error::Source(format!(
+                "No valid confession found in {}",
+                self.csv_path
+            )));
+        }
+
+        let n = rand::rng().random_range(0..self.index.len());
+        let record = CsvIndex::read_record_at(&self.csv_path, self.index.offset(n)?)?;
+
+        extract(&record, &self.columns).ok_or_else(|| {
+            Error::Source(format!(
+                "Indexed record {} of {} is no longer valid; delete {} to rebuild the index",
+                n, self.csv_path, self.index_path
+            ))
+        })
+    }
+}
+
+/// A folder of hand-written `.txt` or `.md` scripts. The first non-empty line is
+/// the title (a leading Markdown `#` is dropped) and the rest is the body.
+pub struct DirectorySource {
+    directory: String,
+}
+
+impl DirectorySource {
+    pub fn new(config: &SourceConfig) -> Self {
+        Self {
+            directory: config.directory.clone(),
+        }
+    }
+
+    fn scripts(&self) -> Result<Vec<PathBuf>> {
+        let entries = fs::read_dir(&self.directory)
+            .map_err(Error::io(format!("Failed to list {}", self.directory)))?;
+        Ok(entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| {
+                matches!(
+                    path.extension().and_then(|e| e.to_str()),
+                    Some("txt") | Some("md")
+                )
+            })
+            .collect())
+    }
+}
+
+fn read_script(path: &Path) -> Result<Option<Confession>> {
+    let content = fs::read_to_string(path)
+        .map_err(Error::io(format!("Failed to read {}", path.display())))?;
+    let mut lines = content.lines().map(str::trim).skip_while(|l| l.is_empty());
+
+    let title = lines
+        .next()
+        .map(|line| line.trim_start_matches('#').trim().to_string());
+    let body = lines
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let id = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned());
+
+    Ok(confession::confession_from_fields(|field| match field {
+        "title" => title.clone(),
+        "selftext" => Some(body.clone()),
+        "id" => id.clone(),
+        _ => None,
+    }))
+}
+
+impl ConfessionSource for DirectorySource {
+    fn random_confession(&self) -> Result<Confession> {
+        let mut scripts = self.scripts()?;
+        scripts.shuffle(&mut rand::rng());
+
+        for path in scripts {
+            if let Some(found) = read_script(&path)? {
+                return Ok(found);
+            }
+            eprintln!(
+                "Skipping {}: it needs a title line and a body",
+                path.display()
+            );
+        }
+
+        Err(Error::Source(format!(
+            "No usable .txt or .md script found in {}",
+            self.directory
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ClassifyConfig, ProfanityConfig, RulesConfig};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    /// Hands out scored posts in order, repeating the last one, and counts draws.
+    struct Scripted {
+        posts: Vec<(&'static str, i64)>,
+        draws: Arc<AtomicUsize>,
+    }
+
+    impl ConfessionSource for Scripted {
+        fn random_confession(&self) -> Result<Confession> {
+            let draw = self.draws.fetch_add(1, Ordering::SeqCst);
+            let (title, score) = self.posts[draw.min(self.posts.len() - 1)];
+            Ok(confession::confession_from_fields(|field| match field {
+                "title" => Some(title.to_string()),
+                "selftext" => Some("I never told anyone about it.".to_string()),
+                "score" => Some(score.to_string()),
+                _ => None,
+            })
+            .unwrap())
+        }
+    }
+
+    /// Posts titled "rejected" fail the rules.
+    fn filtered(
+        posts: Vec<(&'static str, i64)>,
+        selection: Selection,
+    ) -> (FilteredSource, Arc<AtomicUsize>) {
+        let draws = Arc::new(AtomicUsize::new(0));
+        let rules = RulesConfig {
+            banned_keywords: vec!["rejected".to_string()],
+            ..RulesConfig::default()
+        };
+        let source = FilteredSource {
+            inner: Box::new(Scripted {
+                posts,
+                draws: draws.clone(),
+            }),
+            ledger: Ledger::load("no-such-ledger.json").unwrap(),
+            threshold: 2.0,
+            rules: Rules::from_config(&rules).unwrap(),
+            profanity: ProfanityFilter::from_config(&ProfanityConfig::default()),
+            classifier: Classifier::from_config(&ClassifyConfig::default()),
+            selection,
+            candidates: 3,
+        };
+        (source, draws)
+    }
+
+    #[test]
+    fn uniform_picks_the_first_passing_confession() {
+        let (source, draws) = filtered(
+            vec![("rejected", 90), ("kept", 1), ("later", 50)],
+            Selection::Uniform,
+        );
+        assert_eq!(source.random_confession().unwrap().title, "kept");
+        assert_eq!(draws.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn ranked_keeps_the_best_scored_candidate() {
+        let (source, draws) = filtered(
+            vec![
+                ("low", 1),
+                ("rejected", 90),
+                ("best", 40),
+                ("mid", 20),
+                ("late", 99),
+            ],
+            Selection::Ranked,
+        );
+        assert_eq!(source.random_confession().unwrap().title, "best");
+        assert_eq!(draws.load(Ordering::SeqCst), 4);
+    }
+
+    #[test]
+    fn ranked_settles_for_fewer_candidates_at_the_draw_cap() {
+        let (source, draws) = filtered(vec![("only", 5), ("rejected", 90)], Selection::Ranked);
+        assert_eq!(source.random_confession().unwrap().title, "only");
+        assert_eq!(draws.load(Ordering::SeqCst), MAX_DRAWS);
+    }
+
+    #[test]
+    fn gives_up_after_the_draw_cap() {
+        let (source, draws) = filtered(vec![("rejected", 90)], Selection::Uniform);
+        assert!(source.random_confession().is_err());
+        assert_eq!(draws.load(Ordering::SeqCst), MAX_DRAWS);
+    }
+}
@@ -1,48 +1,47 @@
 use std::path::PathBuf;
 use std::process::Command;
 
-use crate::constants;
+use crate::error::{Error, Result};
 
 pub struct SplitResult {
     pub video_paths: Vec<PathBuf>,
 }
 
-pub fn split_media(
-    video_path: &str,
-    output_dir: &str,
-) -> Result<SplitResult, Box<dyn std::error::Error>> {
+pub fn split_media(video_path: &str, output_dir: &str, max_duration: f32) -> Result<SplitResult> {
     let mut command = Command::new("python");
     command
         .arg("src/media_splitter.py")
         .arg(video_path)
         .arg(output_dir)
-        .arg(constants::MAX_VIDEO_DURATION.to_string());
+        .arg(max_duration.to_string());
 
-    let output = command.output()?;
+    let output = command
+        .output()
+        .map_err(|e| Error::Split(format!("Failed to execute media splitter: {}", e)))?;
 
     if !output.status.success() {
-        return Err(format!(
+        return Err(Error::Split(format!(
             "Media splitting failed: {}",
             String::from_utf8_lossy(&output.stderr)
-        )
-        .into());
+        )));
     }
 
     let output_str = String::from_utf8_lossy(&output.stdout);
     parse_split_output(&output_str, output_dir)
 }
 
-fn parse_split_output(
-    output: &str,
-    output_dir: &str,
-) -> Result<SplitResult, Box<dyn std::error::Error>> {
+fn parse_split_output(output: &str, output_dir: &str) -> Result<SplitResult> {
     let mut video_paths = Vec::new();
 
     for line in output.lines() {
-        if line.starts_with("VIDEO:") {
-            video_paths.push(PathBuf::from(format!("{}/{}", output_dir, &line[6..])));
+        if let Some(file_name) = line.strip_prefix("VIDEO:") {
+            video_paths.push(PathBuf::from(format!("{}/{}", output_dir, file_name)));
         }
     }
 
+    if video_paths.is_empty() {
+        return Err(Error::Split("Media splitter produced no parts".to_string()));
+    }
+
     Ok(SplitResult { video_paths })
 }
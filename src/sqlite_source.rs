@@ -0,0 +1,135 @@
+use rusqlite::types::ValueRef;
+use rusqlite::{params_from_iter, Connection, OpenFlags};
+
+use crate::confession::{self, ColumnMap, Confession, FIELDS};
+use crate::config::SourceConfig;
+use crate::error::{Error, Result};
+use crate::source::ConfessionSource;
+
+/// A SQLite table with one submission per row. Columns are matched by name with
+/// the same aliases as CSV headers, and the random pick happens in SQL.
+pub struct SqliteSource {
+    connection: Connection,
+    query: String,
+    subreddits: Vec<String>,
+    path: String,
+}
+
+fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+fn sqlite_error(path: &str) -> impl Fn(rusqlite::Error) -> Error + '_ {
+    move |e| Error::Source(format!("SQLite error in {}: {}", path, e))
+}
+
+/// Mirrors `confession::is_valid_text` so rows it would reject are never picked.
+fn usable_text_condition(column: &str) -> String {
+    format!(
+        "{column} IS NOT NULL AND length(trim({column})) > 0 \
+         AND instr({column}, '[removed]') = 0 AND instr({column}, '[deleted]') = 0"
+    )
+}
+
+impl SqliteSource {
+    pub fn open(config: &SourceConfig) -> Result<Self> {
+        let path = config.sqlite_path.clone();
+        let connection = Connection::open_with_flags(&path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .map_err(sqlite_error(&path))?;
+
+        let table = quote_identifier(config.sqlite_table.trim());
+        let names = {
+            let statement = connection
+                .prepare(&format!("SELECT * FROM {} LIMIT 0", table))
+                .map_err(sqlite_error(&path))?;
+            statement
+                .column_names()
+                .into_iter()
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        };
+        let columns = ColumnMap::resolve(&names, &config.columns)?;
+
+        let column = |field| {
+            columns
+                .position(field)
+                .map(|position| quote_identifier(&names[position]))
+        };
+        let selected = FIELDS
+            .iter()
+            .map(|field| column(field).unwrap_or_else(|| "NULL".to_string()))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let mut conditions = vec![
+            usable_text_condition(&column("selftext").unwrap_or_default()),
+            usable_text_condition(&column("title").unwrap_or_default()),
+        ];
+        let subreddits = config
+            .subreddits
+            .iter()
+            .map(|name| name.trim().to_lowercase())
+            .collect::<Vec<_>>();
+        if !subreddits.is_empty() {
+            let subreddit = column("subreddit").ok_or_else(|| {
+                Error::Source(format!(
+                    "source.subreddits is set but {} has no subreddit column",
+                    config.sqlite_table
+                ))
+            })?;
+            let placeholders = vec!["?"; subreddits.len()].join(", ");
+            conditions.push(format!("lower({}) IN ({})", subreddit, placeholders));
+        }
+
+        let query = format!(
+            "SELECT {} FROM {} WHERE {} ORDER BY RANDOM() LIMIT 1",
+            selected,
+            table,
+            conditions.join(" AND ")
+        );
+
+        Ok(Self {
+            connection,
+            query,
+            subreddits,
+            path,
+        })
+    }
+}
+
+fn value_text(value: ValueRef<'_>) -> Option<String> {
+    match value {
+        ValueRef::Null | ValueRef::Blob(_) => None,
+        ValueRef::Integer(number) => Some(number.to_string()),
+        ValueRef::Real(number) => Some(number.to_string()),
+        ValueRef::Text(text) => Some(String::from_utf8_lossy(text).into_owned()),
+    }
+}
+
+impl ConfessionSource for SqliteSource {
+    fn random_confession(&self) -> Result<Confession> {
+        let mut statement = self
+            .connection
+            .prepare_cached(&self.query)
+            .map_err(sqlite_error(&self.path))?;
+        let mut rows = statement
+            .query(params_from_iter(&self.subreddits))
+            .map_err(sqlite_error(&self.path))?;
+
+        let row = rows
+            .next()
+            .map_err(sqlite_error(&self.path))?
+            .ok_or_else(|| Error::Source(format!("No valid confession found in {}", self.path)))?;
+
+        let values = (0..FIELDS.len())
+            .map(|i| row.get_ref(i).ok().and_then(value_text))
+            .collect::<Vec<_>>();
+        confession::confession_from_fields(|field| {
+            FIELDS
+                .iter()
+                .position(|candidate| *candidate == field)
+                .and_then(|i| values[i].clone())
+        })
+        .ok_or_else(|| Error::Source(format!("Picked an unusable row from {}", self.path)))
+    }
+}
@@ -0,0 +1,74 @@
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{Config, StyleConfig, StylePool};
+use crate::metadata::VideoMetadata;
+
+/// The assets picked for one video from the `[style]` pools. Unset fields keep
+/// the rest of the config.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VideoStyle {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub background: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub music: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub voice: Option<String>,
+}
+
+/// The pools for the metadata's mood and category, mood first.
+fn pools<'a>(config: &'a StyleConfig, metadata: &VideoMetadata) -> Vec<&'a StylePool> {
+    let mood = metadata.mood.and_then(|mood| config.moods.get(&mood));
+    let category = metadata
+        .category
+        .and_then(|category| config.categories.get(&category));
+    mood.into_iter().chain(category).collect()
+}
+
+/// Picks one of each kind of asset from the first pool that has any.
+pub fn pick(config: &StyleConfig, metadata: &VideoMetadata) -> VideoStyle {
+    let pools = pools(config, metadata);
+    let choose = |list: fn(&StylePool) -> &Vec<String>| {
+        pools
+            .iter()
+            .map(|pool| list(pool))
+            .find(|values| !values.is_empty())
+            .and_then(|values| values.choose(&mut rand::rng()))
+            .map(|value| value.trim().to_string())
+    };
+    VideoStyle {
+        background: choose(|pool| &pool.backgrounds),
+        music: choose(|pool| &pool.music),
+        voice: choose(|pool| &pool.voices),
+    }
+}
+
+/// Adds the hashtags of the mood and category pools to the metadata and picks
+/// its [`VideoStyle`], when `style.enabled` is on.
+pub fn label(config: &StyleConfig, metadata: &mut VideoMetadata) {
+    if !config.enabled {
+        return;
+    }
+    for pool in pools(config, metadata) {
+        for tag in &pool.hashtags {
+            let tag = tag.trim().trim_start_matches('#').to_lowercase();
+            if !metadata.hashtags.contains(&tag) {
+                metadata.hashtags.push(tag);
+            }
+        }
+    }
+    metadata.style = Some(pick(config, metadata));
+}
+
+/// Renders and narrates with the picked assets.
+pub fn apply(style: &VideoStyle, config: &mut Config) {
+    if let Some(background) = &style.background {
+        config.paths.video_input = background.clone();
+    }
+    if let Some(music) = &style.music {
+        config.video.music = Some(music.clone());
+    }
+    if let Some(voice) = &style.voice {
+        config.audio.voice = Some(voice.clone());
+    }
+}
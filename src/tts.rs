@@ -1,33 +1,497 @@
-use std::path::Path;
-use std::process::Command;
-
-pub fn generate_tts(
-    text: &str,
-    output_path: &str,
-    voice: Option<&str>,
-    lang_code: Option<&str>,
-) -> Result<(), String> {
-    let script_path = Path::new("src/tts_generator.py");
-
-    let mut cmd = Command::new("python");
-    cmd.arg(script_path).arg(text).arg(output_path);
-
-    if let Some(v) = voice {
-        cmd.arg(v);
-        if let Some(l) = lang_code {
-            cmd.arg(l);
-        }
-    }
-
-    match cmd.output() {
-        Ok(output) => {
-            if output.status.success() {
-                Ok(())
-            } else {
-                let error = String::from_utf8_lossy(&output.stderr);
-                Err(format!("TTS generation failed: {}", error))
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{LazyLock, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::config::{AudioConfig, TtsEngineKind};
+use crate::error::{Error, Result};
+use crate::utils::SECONDS_PER_WORD;
+
+/// Something that narrates text into a WAV file.
+pub trait TtsEngine: Send + Sync {
+    /// Writes `text` spoken with `voice` to `output_path`. Engines use their
+    /// own default voice when `voice` is `None`; only Kokoro reads `lang_code`.
+    fn synthesize(
+        &self,
+        text: &str,
+        output_path: &str,
+        voice: Option<&str>,
+        lang_code: Option<&str>,
+    ) -> Result<()>;
+
+    /// Identifies the engine in logs, e.g. `kokoro (src/tts_generator.py)`.
+    fn describe(&self) -> String;
+}
+
+/// Opens the engine selected by `audio.engine`.
+pub fn open(config: &AudioConfig) -> Result<Box<dyn TtsEngine>> {
+    Ok(match config.engine {
+        TtsEngineKind::Kokoro => Box::new(KokoroEngine::new(config)),
+        TtsEngineKind::Piper => Box::new(BinaryEngine::piper(config)),
+        TtsEngineKind::Espeak => Box::new(BinaryEngine::espeak(config)),
+        TtsEngineKind::Silent => Box::new(SilentEngine::new(config)),
+    })
+}
+
+/// Narrates `text` to `output_path` with the `[audio]` engine, voice and language code.
+pub fn generate_tts(config: &AudioConfig, text: &str, output_path: &str) -> Result<()> {
+    open(config)?.synthesize(
+        text,
+        output_path,
+        config.voice.as_deref(),
+        config.lang_code.as_deref(),
+    )
+}
+
+/// One line of the worker protocol of `tts_generator.py --worker`.
+#[derive(Debug, Serialize)]
+struct WorkerRequest<'a> {
+    text: &'a str,
+    output: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    voice: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    lang_code: Option<&'a str>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WorkerReply {
+    ok: bool,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+impl WorkerReply {
+    fn parse(line: &str) -> Result<Self> {
+        serde_json::from_str(line.trim()).map_err(|e| {
+            Error::Tts(format!(
+                "Invalid reply from the TTS worker '{}': {}",
+                line.trim(),
+                e
+            ))
+        })
+    }
+}
+
+/// A running `tts_generator.py --worker`: one JSON request per line on stdin,
+/// one JSON reply per line on stdout. The model stays loaded between requests.
+///
+/// Replies are read on a thread of their own and handed over through a
+/// channel, so a worker that hangs can be given up on after a deadline.
+struct KokoroWorker {
+    child: Child,
+    stdin: ChildStdin,
+    replies: Receiver<io::Result<String>>,
+}
+
+impl KokoroWorker {
+    fn spawn(python: &str, script: &str) -> Result<Self> {
+        let mut child = Command::new(python)
+            .arg(script)
+            .arg("--worker")
+            .env("PYTHONIOENCODING", "utf8")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .map_err(|e| Error::Tts(format!("Failed to start {} --worker: {}", script, e)))?;
+        let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+            return Err(Error::Tts(format!("{} --worker has no pipes", script)));
+        };
+
+        // Ends when the worker closes its output or the worker is dropped.
+        let (sender, replies) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Self {
+            child,
+            stdin,
+            replies,
+        })
+    }
+
+    /// Sends one request and waits up to `timeout` for its reply. An error means
+    /// the worker broke the protocol or hung and should not be used again.
+    fn request(&mut self, request: &WorkerRequest, timeout: Duration) -> Result<WorkerReply> {
+        let line = serde_json::to_string(request)
+            .map_err(|e| Error::Tts(format!("Failed to encode TTS request: {}", e)))?;
+        writeln!(self.stdin, "{}", line)
+            .and_then(|_| self.stdin.flush())
+            .map_err(|e| Error::Tts(format!("TTS worker stopped accepting requests: {}", e)))?;
+
+        match self.replies.recv_timeout(timeout) {
+            Ok(Ok(reply)) => WorkerReply::parse(&reply),
+            Ok(Err(e)) => Err(Error::Tts(format!(
+                "Failed to read from the TTS worker: {}",
+                e
+            ))),
+            Err(RecvTimeoutError::Timeout) => Err(Error::Tts(format!(
+                "TTS worker gave no reply within {}s",
+                timeout.as_secs_f32()
+            ))),
+            Err(RecvTimeoutError::Disconnected) => {
+                let status = self
+                    .child
+                    .try_wait()
+                    .ok()
+                    .flatten()
+                    .map_or_else(|| "closed its output".to_string(), |s| s.to_string());
+                Err(Error::Tts(format!("TTS worker exited: {}", status)))
+            }
+        }
+    }
+}
+
+/// Workers by interpreter and script, started on first use and kept for the
+/// life of the process.
+static KOKORO_WORKERS: LazyLock<Mutex<HashMap<(String, String), KokoroWorker>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Kokoro through `audio.kokoro_script`, run as a long-lived worker so the model
+/// is loaded once and the text never goes through the command line.
+pub struct KokoroEngine {
+    python: String,
+    script: String,
+    timeout: Duration,
+}
+
+impl KokoroEngine {
+    pub fn new(config: &AudioConfig) -> Self {
+        Self {
+            python: config.python.clone(),
+            script: config.kokoro_script.clone(),
+            timeout: Duration::from_secs(config.timeout_secs),
+        }
+    }
+}
+
+impl TtsEngine for KokoroEngine {
+    fn synthesize(
+        &self,
+        text: &str,
+        output_path: &str,
+        voice: Option<&str>,
+        lang_code: Option<&str>,
+    ) -> Result<()> {
+        let request = WorkerRequest {
+            text,
+            output: output_path,
+            voice,
+            lang_code,
+        };
+        let key = (self.python.clone(), self.script.clone());
+        let mut workers = KOKORO_WORKERS.lock().unwrap_or_else(|e| e.into_inner());
+        if !workers.contains_key(&key) {
+            workers.insert(
+                key.clone(),
+                KokoroWorker::spawn(&self.python, &self.script)?,
+            );
+        }
+        let Some(worker) = workers.get_mut(&key) else {
+            return Err(Error::Tts("TTS worker disappeared".to_string()));
+        };
+
+        match worker.request(&request, self.timeout) {
+            Ok(reply) if reply.ok => Ok(()),
+            Ok(reply) => Err(Error::Tts(format!(
+                "TTS generation failed: {}",
+                reply.error.unwrap_or_else(|| "no reason given".to_string())
+            ))),
+            Err(e) => {
+                // Replaced by a fresh worker on the next request.
+                if let Some(mut worker) = workers.remove(&key) {
+                    let _ = worker.child.kill();
+                    let _ = worker.child.wait();
+                }
+                Err(e)
             }
         }
-        Err(e) => Err(format!("Failed to execute TTS script: {}", e)),
+    }
+
+    fn describe(&self) -> String {
+        format!("kokoro ({})", self.script)
+    }
+}
+
+/// A local Piper or espeak-ng executable, given the text on stdin.
+pub struct BinaryEngine {
+    kind: TtsEngineKind,
+    binary: String,
+}
+
+impl BinaryEngine {
+    pub fn piper(config: &AudioConfig) -> Self {
+        Self {
+            kind: TtsEngineKind::Piper,
+            binary: config.binary.clone().unwrap_or_else(|| "piper".to_string()),
+        }
+    }
+
+    pub fn espeak(config: &AudioConfig) -> Self {
+        Self {
+            kind: TtsEngineKind::Espeak,
+            binary: config
+                .binary
+                .clone()
+                .unwrap_or_else(|| "espeak-ng".to_string()),
+        }
+    }
+
+    fn command(&self, output_path: &str, voice: Option<&str>) -> Result<Command> {
+        let mut command = Command::new(&self.binary);
+        match self.kind {
+            TtsEngineKind::Piper => {
+                let model = voice.ok_or_else(|| {
+                    Error::Tts("Piper needs audio.voice set to a .onnx voice model".to_string())
+                })?;
+                command
+                    .arg("--model")
+                    .arg(model)
+                    .arg("--output_file")
+                    .arg(output_path);
+            }
+            _ => {
+                if let Some(voice) = voice {
+                    command.arg("-v").arg(voice);
+                }
+                command.arg("-w").arg(output_path).arg("--stdin");
+            }
+        }
+        Ok(command)
+    }
+}
+
+impl TtsEngine for BinaryEngine {
+    fn synthesize(
+        &self,
+        text: &str,
+        output_path: &str,
+        voice: Option<&str>,
+        _lang_code: Option<&str>,
+    ) -> Result<()> {
+        let mut child = self
+            .command(output_path, voice)?
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| Error::Tts(format!("Failed to execute {}: {}", self.binary, e)))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(text.as_bytes()).map_err(|e| {
+                Error::Tts(format!("Failed to send text to {}: {}", self.binary, e))
+            })?;
+        }
+
+        let output = child
+            .wait_with_output()
+            .map_err(|e| Error::Tts(format!("Failed to wait for {}: {}", self.binary, e)))?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            let error = String::from_utf8_lossy(&output.stderr);
+            Err(Error::Tts(format!("TTS generation failed: {}", error)))
+        }
+    }
+
+    fn describe(&self) -> String {
+        self.binary.clone()
+    }
+}
+
+/// Sample rate of the silence written by [`SilentEngine`], Kokoro's rate.
+const SILENT_SAMPLE_RATE: u32 = 24_000;
+
+/// Writes `audio.fixture_path`, or silence as long as the text would take to
+/// speak, so everything after the TTS step runs without a voice model.
+pub struct SilentEngine {
+    fixture: Option<String>,
+}
+
+impl SilentEngine {
+    pub fn new(config: &AudioConfig) -> Self {
+        Self {
+            fixture: config.fixture_path.clone(),
+        }
+    }
+}
+
+impl TtsEngine for SilentEngine {
+    fn synthesize(
+        &self,
+        text: &str,
+        output_path: &str,
+        _voice: Option<&str>,
+        _lang_code: Option<&str>,
+    ) -> Result<()> {
+        if let Some(fixture) = &self.fixture {
+            return fs::copy(fixture, output_path)
+                .map(|_| ())
+                .map_err(Error::io(format!(
+                    "Failed to copy TTS fixture {} to {}",
+                    fixture, output_path
+                )));
+        }
+
+        let seconds = text.split_whitespace().count() as f32 * SECONDS_PER_WORD;
+        let samples = (seconds * SILENT_SAMPLE_RATE as f32).round() as u32;
+        fs::write(output_path, silent_wav(samples))
+            .map_err(Error::io(format!("Failed to write {}", output_path)))
+    }
+
+    fn describe(&self) -> String {
+        match &self.fixture {
+            Some(fixture) => format!("silent ({})", fixture),
+            None => "silent".to_string(),
+        }
+    }
+}
+
+/// A 16-bit mono PCM WAV file of `samples` zero samples.
+fn silent_wav(samples: u32) -> Vec<u8> {
+    let data_len = samples * 2;
+    let mut wav = Vec::with_capacity(44 + data_len as usize);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    wav.extend_from_slice(&1u16.to_le_bytes()); // mono
+    wav.extend_from_slice(&SILENT_SAMPLE_RATE.to_le_bytes());
+    wav.extend_from_slice(&(SILENT_SAMPLE_RATE * 2).to_le_bytes());
+    wav.extend_from_slice(&2u16.to_le_bytes());
+    wav.extend_from_slice(&16u16.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+    wav.resize(44 + data_len as usize, 0);
+    wav
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn silent_wav_has_a_pcm_header_and_the_samples() {
+        let wav = silent_wav(SILENT_SAMPLE_RATE);
+        let u32_at = |at: usize| u32::from_le_bytes(wav[at..at + 4].try_into().unwrap());
+        let u16_at = |at: usize| u16::from_le_bytes(wav[at..at + 2].try_into().unwrap());
+
+        assert_eq!(wav.len(), 44 + 2 * SILENT_SAMPLE_RATE as usize);
+        assert_eq!(&wav[..4], b"RIFF");
+        assert_eq!(u32_at(4) as usize, wav.len() - 8);
+        assert_eq!(&wav[8..16], b"WAVEfmt ");
+        assert_eq!((u16_at(20), u16_at(22)), (1, 1));
+        assert_eq!(u32_at(24), SILENT_SAMPLE_RATE);
+        assert_eq!(u32_at(28), SILENT_SAMPLE_RATE * 2);
+        assert_eq!((u16_at(32), u16_at(34)), (2, 16));
+        assert_eq!(&wav[36..40], b"data");
+        assert_eq!(u32_at(40), 2 * SILENT_SAMPLE_RATE);
+        assert!(wav[44..].iter().all(|&byte| byte == 0));
+        assert_eq!(silent_wav(0).len(), 44);
+    }
+
+    #[test]
+    fn silent_engine_writes_the_spoken_length() {
+        let path = std::env::temp_dir().join(format!("silent-{}.wav", std::process::id()));
+        let path = path.to_string_lossy().into_owned();
+        SilentEngine { fixture: None }
+            .synthesize("one two three four five", &path, None, None)
+            .unwrap();
+        let written = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        // Five words at 0.4 seconds each.
+        assert_eq!(written.len(), 44 + 2 * 2 * SILENT_SAMPLE_RATE as usize);
+    }
+
+    #[test]
+    fn worker_replies_parse() {
+        let ok = WorkerReply::parse("{\"ok\": true}\n").unwrap();
+        assert!(ok.ok && ok.error.is_none());
+
+        let failed = WorkerReply::parse(r#"{"ok": false, "error": "unknown voice"}"#).unwrap();
+        assert!(!failed.ok);
+        assert_eq!(failed.error.as_deref(), Some("unknown voice"));
+
+        let garbage = WorkerReply::parse("Loading model...").unwrap_err();
+        assert!(
+            garbage.to_string().contains("'Loading model...'"),
+            "{garbage}"
+        );
+        assert!(WorkerReply::parse(r#"{"error": "no ok"}"#).is_err());
+    }
+
+    /// A worker run by `sh` instead of Python, following the worker protocol.
+    #[cfg(unix)]
+    fn fake_worker(name: &str, script: &str) -> (KokoroWorker, std::path::PathBuf) {
+        let path = std::env::temp_dir().join(format!("{name}-{}.sh", std::process::id()));
+        fs::write(&path, script).unwrap();
+        let worker = KokoroWorker::spawn("sh", &path.to_string_lossy()).unwrap();
+        (worker, path)
+    }
+
+    #[cfg(unix)]
+    fn request() -> WorkerRequest<'static> {
+        WorkerRequest {
+            text: "Hello there.",
+            output: "unused.wav",
+            voice: None,
+            lang_code: None,
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn worker_answers_one_reply_per_request() {
+        let (mut worker, path) = fake_worker(
+            "tts-echo",
+            "while read line; do echo '{\"ok\": true}'; done\n",
+        );
+        for _ in 0..2 {
+            let reply = worker.request(&request(), Duration::from_secs(10)).unwrap();
+            assert!(reply.ok);
+        }
+        let _ = worker.child.kill();
+        let _ = worker.child.wait();
+        fs::remove_file(path).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn a_hung_worker_times_out() {
+        let (mut worker, path) = fake_worker("tts-hung", "read line\nsleep 30\n");
+        let error = worker
+            .request(&request(), Duration::from_millis(200))
+            .unwrap_err();
+        assert!(
+            error.to_string().contains("no reply within 0.2s"),
+            "{error}"
+        );
+        let _ = worker.child.kill();
+        let _ = worker.child.wait();
+        fs::remove_file(path).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn a_worker_that_exits_is_reported() {
+        let (mut worker, path) = fake_worker("tts-exit", "read line\nexit 3\n");
+        let error = worker
+            .request(&request(), Duration::from_secs(10))
+            .unwrap_err();
+        assert!(error.to_string().contains("TTS worker exited"), "{error}");
+        let _ = worker.child.wait();
+        fs::remove_file(path).unwrap();
     }
 }
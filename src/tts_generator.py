@@ -1,13 +1,23 @@
 #!C:/Users/THROATPIES/Documents/Development/ipynb_env_3.9/python.exe
 
+import contextlib
+import json
 import sys
 from kokoro import KPipeline
 import torch
 import soundfile as sf
 
+pipelines = {}
+
+def get_pipeline(lang_code):
+    """Loads the Kokoro pipeline for a language once per process."""
+    if lang_code not in pipelines:
+        pipelines[lang_code] = KPipeline(lang_code=lang_code)
+    return pipelines[lang_code]
+
 def generate_tts(text, output_audio_path, voice='af_bella', lang_code='a'):
     """Generates TTS audio using Kokoro pipeline."""
-    pipeline = KPipeline(lang_code=lang_code) 
+    pipeline = get_pipeline(lang_code)
 
     generator = pipeline(
         text, voice=voice, 
@@ -26,10 +36,39 @@ def generate_tts(text, output_audio_path, voice='af_bella', lang_code='a'):
         print(f"TTS audio generated successfully at: {output_audio_path}")
     else:
         print("No audio generated.")
+    return bool(all_audio)
+
+def run_worker():
+    """Serves requests from the Rust pipeline: one JSON object per line on stdin
+    ({"text", "output", "voice"?, "lang_code"?}), one {"ok", "error"?} line back
+    on stdout. Everything else printed goes to stderr so it can't break the protocol."""
+    protocol = sys.stdout
+    for line in sys.stdin:
+        if not line.strip():
+            continue
+        try:
+            request = json.loads(line)
+            with contextlib.redirect_stdout(sys.stderr):
+                generated = generate_tts(
+                    request["text"],
+                    request["output"],
+                    request.get("voice") or 'af_bella',
+                    request.get("lang_code") or 'a',
+                )
+            reply = {"ok": True} if generated else {"ok": False, "error": "No audio generated."}
+        except Exception as e:
+            reply = {"ok": False, "error": str(e)}
+        protocol.write(json.dumps(reply) + "\n")
+        protocol.flush()
 
 if __name__ == "__main__":
+    if len(sys.argv) == 2 and sys.argv[1] == "--worker":
+        run_worker()
+        sys.exit(0)
+
     if len(sys.argv) < 3:
         print("Usage: python tts_generator.py <text_to_speak> <output_audio_path> [voice] [lang_code]")
+        print("       python tts_generator.py --worker")
         print("  - <text_to_speak>: The text to convert to speech.")
         print("  - <output_audio_path>: Path to save the output audio file (.wav).")
         print("  - [voice] (optional): Voice to use (default: af_bella).")
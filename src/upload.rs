@@ -3,41 +3,28 @@ use std::{
     process::{Command, Stdio},
 };
 
-#[derive(Debug)]
-pub enum UploadError {
-    CommandFailed(String),
-    NoErrorMessage,
-    IoError(String),
-}
+use crate::config::UploadConfig;
+use crate::error::{Error, Result};
 
-impl std::fmt::Display for UploadError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Self::CommandFailed(msg) => write!(f, "Upload failed: {msg}"),
-            Self::NoErrorMessage => write!(f, "Upload failed with no error message"),
-            Self::IoError(msg) => write!(f, "IO Error: {msg}"),
-        }
-    }
+/// Who the video is declared for. YouTube leaves both to the channel defaults
+/// unless they are set.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Audience {
+    pub made_for_kids: Option<bool>,
+    /// Only a hint: the API cannot age-restrict, so the upload script reminds
+    /// to do it in YouTube Studio.
+    pub age_restricted: bool,
 }
 
-impl std::error::Error for UploadError {}
-
 pub fn handle_upload(
     file_path: &str,
     title: &str,
     description: &str,
     keywords: &str,
-    category: &str,
-    privacy_status: &str,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let child = create_upload_process(
-        file_path,
-        title,
-        description,
-        keywords,
-        category,
-        privacy_status,
-    )?;
+    upload: &UploadConfig,
+    audience: Audience,
+) -> Result<Option<String>> {
+    let child = create_upload_process(file_path, title, description, keywords, upload, audience)?;
 
     process_upload(child)
 }
@@ -47,10 +34,11 @@ fn create_upload_process(
     title: &str,
     description: &str,
     keywords: &str,
-    category: &str,
-    privacy_status: &str,
-) -> Result<std::process::Child, UploadError> {
-    Command::new("python")
+    upload: &UploadConfig,
+    audience: Audience,
+) -> Result<std::process::Child> {
+    let mut command = Command::new("python");
+    command
         .arg("src/upload_handler.py")
         .arg("--file")
         .arg(file_path)
@@ -61,32 +49,58 @@ fn create_upload_process(
         .arg("--keywords")
         .arg(keywords)
         .arg("--category")
-        .arg(category)
+        .arg(&upload.category)
         .arg("--privacyStatus")
-        .arg(privacy_status)
+        .arg(&upload.privacy)
         .arg("--playlistId")
-        .arg(crate::constants::YOUTUBE_PLAYLIST_ID)
+        .arg(&upload.playlist_id);
+    if let Some(credentials) = &upload.credentials {
+        command.arg("--credentials").arg(credentials);
+    }
+    if let Some(made_for_kids) = audience.made_for_kids {
+        command.arg("--madeForKids").arg(made_for_kids.to_string());
+    }
+    if audience.age_restricted {
+        command.arg("--ageRestricted");
+    }
+    command
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
         .spawn()
-        .map_err(|e| UploadError::IoError(e.to_string()))
+        .map_err(|e| Error::Upload(format!("Failed to start upload script: {e}")))
 }
 
-fn process_upload(mut child: std::process::Child) -> Result<(), Box<dyn std::error::Error>> {
-    handle_stdout(&mut child);
-    handle_process_completion(child)
+/// Returns the YouTube video id reported by the upload script, if it printed one.
+fn process_upload(mut child: std::process::Child) -> Result<Option<String>> {
+    let video_id = handle_stdout(&mut child);
+    handle_process_completion(child)?;
+    Ok(video_id)
 }
 
-fn handle_stdout(child: &mut std::process::Child) {
+fn handle_stdout(child: &mut std::process::Child) -> Option<String> {
+    let mut video_id = None;
     if let Some(stdout) = child.stdout.take() {
         let reader = BufReader::new(stdout);
         for line in reader.lines() {
             match line {
-                Ok(content) => log_output(&content),
+                Ok(content) => {
+                    log_output(&content);
+                    if let Some(id) = parse_video_id(&content) {
+                        video_id = Some(id);
+                    }
+                }
                 Err(e) => eprintln!("Error reading stdout: {e}"),
             }
         }
     }
+    video_id
+}
+
+/// Matches the `Video id '<id>' was successfully uploaded.` line of upload_handler.py.
+fn parse_video_id(line: &str) -> Option<String> {
+    line.strip_prefix("Video id '")
+        .and_then(|rest| rest.split_once('\''))
+        .map(|(id, _)| id.to_string())
 }
 
 fn log_output(line: &str) {
@@ -97,10 +111,10 @@ fn log_output(line: &str) {
     }
 }
 
-fn handle_process_completion(
-    mut child: std::process::Child,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let status = child.wait()?;
+fn handle_process_completion(mut child: std::process::Child) -> Result<()> {
+    let status = child
+        .wait()
+        .map_err(|e| Error::Upload(format!("Failed to wait for upload script: {e}")))?;
 
     if !status.success() {
         return handle_error(child);
@@ -110,17 +124,19 @@ fn handle_process_completion(
     Ok(())
 }
 
-fn handle_error(child: std::process::Child) -> Result<(), Box<dyn std::error::Error>> {
+fn handle_error(child: std::process::Child) -> Result<()> {
     match child.stderr {
         Some(stderr) => {
             let reader = BufReader::new(stderr);
             let error_message = reader
                 .lines()
-                .filter_map(Result::ok)
+                .map_while(std::result::Result::ok)
                 .collect::<Vec<String>>()
                 .join("\n");
-            Err(UploadError::CommandFailed(error_message).into())
+            Err(Error::Upload(format!("Upload failed: {error_message}")))
         }
-        None => Err(UploadError::NoErrorMessage.into()),
+        None => Err(Error::Upload(
+            "Upload failed with no error message".to_string(),
+        )),
     }
 }
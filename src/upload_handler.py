@@ -64,7 +64,7 @@ def get_authenticated_service(args):
     flow = flow_from_clientsecrets(
         CLIENT_SECRETS_FILE, scope=YOUTUBE_UPLOAD_SCOPE, message=MISSING_CLIENT_SECRETS_MESSAGE
     )
-    storage = Storage("%s-oauth2.json" % sys.argv[0])
+    storage = Storage(args.credentials or "%s-oauth2.json" % sys.argv[0])
     credentials = storage.get()
 
     if credentials is None or credentials.invalid:
@@ -88,6 +88,11 @@ def initialize_upload(youtube, options):
         ),
         status=dict(privacyStatus=options.privacyStatus),
     )
+    if options.madeForKids is not None:
+        body["status"]["selfDeclaredMadeForKids"] = options.madeForKids == "true"
+    if options.ageRestricted:
+        # The Data API cannot set an age restriction; it has to be done in YouTube Studio.
+        print("Age restriction requested: set it for this video in YouTube Studio")
 
     insert_request = youtube.videos().insert(
         part=",".join(body.keys()),
@@ -165,6 +170,15 @@ def main():
         help="Video privacy status.",
     )
     argparser.add_argument("--playlistId", help="Playlist ID to add the video to")
+    argparser.add_argument(
+        "--madeForKids", choices=["true", "false"], help="Declare the video as made for kids or not."
+    )
+    argparser.add_argument(
+        "--credentials", help="OAuth token file of the channel to upload to; created on first use."
+    )
+    argparser.add_argument(
+        "--ageRestricted", action="store_true", help="Remind to age-restrict the video after upload."
+    )
     args = argparser.parse_args()
 
     if not os.path.exists(args.file):
@@ -1,87 +1,79 @@
-use reqwest;
+use serde::{de::DeserializeOwned, Serialize};
 use serde_json::json;
 use std::fs;
 
-use crate::constants;
+use crate::error::{Error, Result};
 
-#[derive(Debug)]
-pub struct NotificationError(String);
+/// Average speaking rate is about 150 words per minute
+/// So each word takes approximately 0.4 seconds
+pub const SECONDS_PER_WORD: f32 = 0.4;
 
-impl std::error::Error for NotificationError {}
-impl std::fmt::Display for NotificationError {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "Notification error: {}", self.0)
-    }
-}
-
-pub async fn notify(message: &str, sound: &str) -> Result<(), Box<dyn std::error::Error>> {
+pub async fn notify(url: &str, message: &str, sound: &str) -> Result<()> {
     let client = reqwest::Client::new();
     let data = json!({
         "message": message,
         "sound": sound,
     });
 
-    match client
-        .post(constants::NOTIFICATION_URL)
-        .json(&data)
-        .send()
-        .await
-    {
+    match client.post(url).json(&data).send().await {
         Ok(resp) => {
             if resp.status() == reqwest::StatusCode::NOT_FOUND {
                 println!("Notification system not available (404).");
-                println!("Would have sent: Message: '{}', Sound: '{}'", message, sound);
+                println!(
+                    "Would have sent: Message: '{}', Sound: '{}'",
+                    message, sound
+                );
                 Ok(())
             } else if !resp.status().is_success() {
-                Err(Box::new(NotificationError(format!(
+                Err(Error::Notify(format!(
                     "Failed to send notification: {:?}",
                     resp
-                ))))
+                )))
             } else {
                 Ok(())
             }
         }
         Err(e) => {
-            println!("Failed to connect to notification system: {}. Continuing...", e);
-            println!("Would have sent: Message: '{}', Sound: '{}'", message, sound);
+            println!(
+                "Failed to connect to notification system: {}. Continuing...",
+                e
+            );
+            println!(
+                "Would have sent: Message: '{}', Sound: '{}'",
+                message, sound
+            );
             Ok(())
         }
     }
 }
 
-pub async fn clear_output_folder(folder_path: &str) -> Result<(), std::io::Error> {
-    let entries = fs::read_dir(folder_path)?;
+pub async fn clear_output_folder(folder_path: &str) -> Result<()> {
+    let entries =
+        fs::read_dir(folder_path).map_err(Error::io(format!("Failed to list {}", folder_path)))?;
     for entry in entries.flatten() {
         let path = entry.path();
         if path.is_file() {
-            fs::remove_file(&path)?;
+            fs::remove_file(&path)
+                .map_err(Error::io(format!("Failed to remove {}", path.display())))?;
         }
     }
     Ok(())
 }
 
-pub fn get_current_episode() -> Result<u32, Box<dyn std::error::Error>> {
-    let content = fs::read_to_string(constants::EPISODE_FILE_PATH)?;
-    let json: serde_json::Value = serde_json::from_str(&content)?;
+pub fn get_current_episode(episode_file: &str) -> Result<u32> {
+    let json: serde_json::Value = read_json(episode_file)?;
     json["episode"]
         .as_u64()
-        .ok_or_else(|| "Invalid episode number format".into())
+        .ok_or_else(|| Error::State(format!("Invalid episode number format in {}", episode_file)))
         .map(|n| n as u32)
 }
 
-pub fn increment_episode() -> Result<(), Box<dyn std::error::Error>> {
-    let current = get_current_episode()?;
-    let new_content = json!({ "episode": current + 1 });
-
-    fs::write(
-        constants::EPISODE_FILE_PATH,
-        serde_json::to_string_pretty(&new_content)?,
-    )?;
-
-    Ok(())
+pub fn increment_episode(episode_file: &str) -> Result<()> {
+    let current = get_current_episode(episode_file)?;
+    write_json(episode_file, &json!({ "episode": current + 1 }))
 }
 
-pub fn sanitize_title(title: &str) -> String {
+pub fn sanitize_title(title: &str, max_length: usize) -> String {
     let sanitized = title
         .trim()
         .replace(|c: char| !c.is_ascii() && !c.is_alphanumeric(), " ")
@@ -89,9 +81,22 @@ pub fn sanitize_title(title: &str) -> String {
         .trim()
         .to_string();
 
-    if sanitized.len() > constants::MAX_TITLE_LENGTH {
-        return sanitized[..constants::MAX_TITLE_LENGTH].trim().to_string();
+    if sanitized.len() > max_length {
+        return sanitized[..max_length].trim().to_string();
     }
 
     sanitized
 }
+
+pub fn read_json<T: DeserializeOwned>(path: &str) -> Result<T> {
+    let content =
+        fs::read_to_string(path).map_err(Error::io(format!("Failed to read {}", path)))?;
+    serde_json::from_str(&content)
+        .map_err(|e| Error::State(format!("Failed to parse {}: {}", path, e)))
+}
+
+pub fn write_json<T: Serialize>(path: &str, value: &T) -> Result<()> {
+    let content = serde_json::to_string_pretty(value)
+        .map_err(|e| Error::State(format!("Failed to serialize {}: {}", path, e)))?;
+    fs::write(path, content).map_err(Error::io(format!("Failed to write {}", path)))
+}
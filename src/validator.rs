@@ -0,0 +1,576 @@
+use regex::{Regex, RegexBuilder};
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::LazyLock;
+
+use crate::config::Config;
+use crate::metadata::VideoMetadata;
+
+/// A generated piece of metadata.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Reply,
+    Title,
+    Description,
+    Hook,
+    Narration,
+    Outro,
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Field::Reply => "reply",
+            Field::Title => "title",
+            Field::Description => "description",
+            Field::Hook => "hook",
+            Field::Narration => "narration",
+            Field::Outro => "outro",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// One thing wrong with generated metadata. Displayed as an instruction the
+/// model can act on when it is asked again.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Issue {
+    /// The model declined the task; asking again about the same confession
+    /// rarely helps.
+    Refusal {
+        field: Field,
+        phrase: String,
+    },
+    /// "Here is a title:" and similar talk about the answer.
+    MetaCommentary {
+        field: Field,
+        phrase: String,
+    },
+    Quoted {
+        field: Field,
+    },
+    /// Part of the instructions repeated back.
+    LeakedPrompt {
+        field: Field,
+        phrase: String,
+    },
+    WrongLanguage {
+        field: Field,
+        detected: String,
+        expected: String,
+    },
+    TooLong {
+        field: Field,
+        length: usize,
+        max: usize,
+        unit: &'static str,
+    },
+    MissingHashtag(String),
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Issue::Refusal { field, phrase } => {
+                write!(f, "{} is a refusal (\"{}\")", field, phrase)
+            }
+            Issue::MetaCommentary { field, phrase } => write!(
+                f,
+                "{} talks about the answer (\"{}\"); give only the text itself",
+                field, phrase
+            ),
+            Issue::Quoted { field } => write!(f, "{} must not be wrapped in quotes", field),
+            Issue::LeakedPrompt { field, phrase } => {
+                write!(f, "{} repeats the instructions (\"{}\")", field, phrase)
+            }
+            Issue::WrongLanguage {
+                field,
+                detected,
+                expected,
+            } => write!(
+                f,
+                "{} is written in {} instead of {}",
+                field, detected, expected
+            ),
+            Issue::TooLong {
+                field,
+                length,
+                max,
+                unit,
+            } => write!(
+                f,
+                "{} is {} {}, at most {} allowed",
+                field, length, unit, max
+            ),
+            Issue::MissingHashtag(tag) => write!(f, "hashtags must include #{}", tag),
+        }
+    }
+}
+
+/// Everything wrong with one reply; empty when it can be used as is.
+#[derive(Debug, Clone, Default)]
+pub struct Report {
+    pub issues: Vec<Issue>,
+}
+
+impl Report {
+    pub fn passed(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// The model refused, so another confession should be tried rather than
+    /// asking again.
+    pub fn refused(&self) -> bool {
+        self.issues
+            .iter()
+            .any(|issue| matches!(issue, Issue::Refusal { .. }))
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let issues = self
+            .issues
+            .iter()
+            .map(|issue| issue.to_string())
+            .collect::<Vec<_>>();
+        write!(f, "{}", issues.join("; "))
+    }
+}
+
+static REFUSAL: LazyLock<Regex> = LazyLock::new(|| {
+    RegexBuilder::new(
+        r"\bi(?: cannot| can'?t| can not| won'?t| will not|'m unable to| am unable to|'m not able to| am not able to) (?:help|assist|create|provide|generate|write|comply|fulfill|do that|engage|produce)|cannot create content|unable to process|against (?:my|the) (?:guidelines|polic(?:y|ies))|\bas an ai\b|\bi'?m sorry,? but\b",
+    )
+    .case_insensitive(true)
+    .build()
+    .expect("refusal pattern compiles")
+});
+
+static META_COMMENTARY: LazyLock<Regex> = LazyLock::new(|| {
+    RegexBuilder::new(
+        r"^(?:(?:sure|certainly|of course|okay|ok)\b\s*[,!.]|here(?: is|'s| are)\b[^:.!]*:|(?:the )?(?:title|description|hook|hashtags|narration|script|outro)\s*:)|\b(?:i hope (?:this|you)|let me know if)\b",
+    )
+    .case_insensitive(true)
+    .build()
+    .expect("meta-commentary pattern compiles")
+});
+
+/// Quote pairs that wrap a whole field.
+const QUOTES: [(char, char); 5] = [
+    ('"', '"'),
+    ('\'', '\''),
+    ('\u{201c}', '\u{201d}'),
+    ('\u{2018}', '\u{2019}'),
+    ('\u{ab}', '\u{bb}'),
+];
+
+/// How many consecutive prompt words in a field count as a leak.
+const LEAK_WORDS: usize = 5;
+
+/// Whatlang needs some text before its guess means anything.
+const MIN_LANGUAGE_WORDS: usize = 6;
+
+/// Checks generated metadata against the `[validate]` settings.
+pub struct Validator {
+    language: Option<String>,
+    required_hashtags: Vec<String>,
+    max_title_length: usize,
+    max_description_words: usize,
+    max_hook_words: usize,
+    extra_refusals: Vec<String>,
+    prompt_shingles: HashSet<String>,
+}
+
+impl Validator {
+    /// `system_prompt` is the prompt the metadata was generated with, so leaks
+    /// of it can be recognised.
+    pub fn new(config: &Config, system_prompt: &str) -> Self {
+        let settings = &config.validate;
+        let lowercase = |values: &[String]| {
+            values
+                .iter()
+                .map(|value| value.trim().trim_start_matches('#').to_lowercase())
+                .filter(|value| !value.is_empty())
+                .collect::<Vec<_>>()
+        };
+
+        Self {
+            language: Some(settings.language.trim().to_lowercase()).filter(|l| !l.is_empty()),
+            required_hashtags: lowercase(&settings.required_hashtags),
+            max_title_length: config.upload.max_title_length,
+            max_description_words: settings.max_description_words,
+            max_hook_words: settings.max_hook_words,
+            extra_refusals: lowercase(&settings.refusal_phrases),
+            prompt_shingles: shingles(system_prompt).collect(),
+        }
+    }
+
+    /// Looks for a refusal in a raw reply, which often is not JSON at all.
+    pub fn check_reply(&self, reply: &str) -> Report {
+        Report {
+            issues: self.refusal(Field::Reply, reply, "").into_iter().collect(),
+        }
+    }
+
+    pub fn check(&self, metadata: &VideoMetadata) -> Report {
+        let mut issues = Vec::new();
+        let hook = metadata.hook.as_deref().unwrap_or_default();
+
+        for (field, text) in [
+            (Field::Title, metadata.title.as_str()),
+            (Field::Description, metadata.description.as_str()),
+            (Field::Hook, hook),
+        ] {
+            self.check_text(field, text, "", &mut issues);
+        }
+
+        if let Some(expected) = &self.language {
+            for (field, text) in [
+                (Field::Description, metadata.description.as_str()),
+                (Field::Hook, hook),
+            ] {
+                issues.extend(wrong_language(field, text, expected));
+            }
+        }
+
+        let title_length = metadata.title.chars().count();
+        if title_length > self.max_title_length {
+            issues.push(Issue::TooLong {
+                field: Field::Title,
+                length: title_length,
+                max: self.max_title_length,
+                unit: "characters",
+            });
+        }
+        for (field, text, max) in [
+            (
+                Field::Description,
+                metadata.description.as_str(),
+                self.max_description_words,
+            ),
+            (Field::Hook, hook, self.max_hook_words),
+        ] {
+            let words = text.split_whitespace().count();
+            if words > max {
+                issues.push(Issue::TooLong {
+                    field,
+                    length: words,
+                    max,
+                    unit: "words",
+                });
+            }
+        }
+
+        for tag in &self.required_hashtags {
+            if !metadata
+                .hashtags
+                .iter()
+                .any(|hashtag| hashtag.trim_start_matches('#').eq_ignore_ascii_case(tag))
+            {
+                issues.push(Issue::MissingHashtag(tag.clone()));
+            }
+        }
+
+        Report { issues }
+    }
+
+    /// Checks generated spoken text, a narration or outro, for the same faults
+    /// as metadata text, including the language. `source` is the text it was
+    /// written from; refusal phrases the story itself contains don't count.
+    pub fn check_script(&self, field: Field, text: &str, source: &str) -> Report {
+        let mut issues = Vec::new();
+        self.check_text(field, text, source, &mut issues);
+        if let Some(expected) = &self.language {
+            issues.extend(wrong_language(field, text, expected));
+        }
+        Report { issues }
+    }
+
+    fn check_text(&self, field: Field, text: &str, source: &str, issues: &mut Vec<Issue>) {
+        issues.extend(self.refusal(field, text, source));
+        // A narrator may well say "I hope you..." mid-story, so spoken text is
+        // only checked for a chatty opening.
+        let spoken = matches!(field, Field::Narration | Field::Outro);
+        if let Some(found) = META_COMMENTARY
+            .find(text)
+            .filter(|found| !spoken || found.start() == 0)
+        {
+            issues.push(Issue::MetaCommentary {
+                field,
+                phrase: found.as_str().trim().to_string(),
+            });
+        }
+        if is_quoted(text) {
+            issues.push(Issue::Quoted { field });
+        }
+        if let Some(phrase) = shingles(text).find(|s| self.prompt_shingles.contains(s)) {
+            issues.push(Issue::LeakedPrompt { field, phrase });
+        }
+    }
+
+    /// Spoken text is first-person storytelling ("I won't do that again"), so
+    /// there only a refusal opening the text counts, and never one the
+    /// `source` it was written from already says.
+    fn refusal(&self, field: Field, text: &str, source: &str) -> Option<Issue> {
+        let spoken = matches!(field, Field::Narration | Field::Outro);
+        let lowercase = text.to_lowercase();
+        let source = source.to_lowercase();
+
+        let built_in = REFUSAL
+            .find_iter(&lowercase)
+            .map(|found| (found.start(), found.end()));
+        let extra = self.extra_refusals.iter().filter_map(|phrase| {
+            lowercase
+                .find(phrase.as_str())
+                .map(|start| (start, start + phrase.len()))
+        });
+        let (start, end) = built_in.chain(extra).find(|&(start, end)| {
+            let phrase = &lowercase[start..end];
+            // "I can't help but laugh"
+            let idiom = lowercase[end..].starts_with(" but ");
+            (!spoken || start == 0) && !idiom && !source.contains(phrase)
+        })?;
+        Some(Issue::Refusal {
+            field,
+            phrase: lowercase[start..end].to_string(),
+        })
+    }
+}
+
+fn is_quoted(text: &str) -> bool {
+    let text = text.trim();
+    let mut chars = text.chars();
+    match (chars.next(), chars.next_back()) {
+        (Some(first), Some(last)) => QUOTES.contains(&(first, last)),
+        _ => false,
+    }
+}
+
+/// Runs of [`LEAK_WORDS`] lowercase words, punctuation stripped.
+fn shingles(text: &str) -> impl Iterator<Item = String> {
+    let words = text
+        .split_whitespace()
+        .map(|word| {
+            word.trim_matches(|c: char| !c.is_alphanumeric())
+                .to_lowercase()
+        })
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>();
+    let count = words.len().saturating_sub(LEAK_WORDS - 1);
+    (0..count).map(move |i| words[i..i + LEAK_WORDS].join(" "))
+}
+
+fn wrong_language(field: Field, text: &str, expected: &str) -> Option<Issue> {
+    if text.split_whitespace().count() < MIN_LANGUAGE_WORDS {
+        return None;
+    }
+    let info = whatlang::detect(text).filter(|info| info.is_reliable())?;
+    let detected = info.lang().code();
+    (detected != expected).then(|| Issue::WrongLanguage {
+        field,
+        detected: info.lang().eng_name().to_string(),
+        expected: whatlang::Lang::from_code(expected)
+            .map_or_else(|| expected.to_string(), |lang| lang.eng_name().to_string()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SYSTEM_PROMPT: &str =
+        "You write titles for short videos that retell anonymous Reddit confessions.";
+
+    fn metadata(title: &str, description: &str, hook: &str, hashtags: &[&str]) -> VideoMetadata {
+        serde_json::from_value(serde_json::json!({
+            "title": title,
+            "description": description,
+            "keywords": ["confession"],
+            "hashtags": hashtags,
+            "hook": hook,
+        }))
+        .expect("sample metadata deserializes")
+    }
+
+    fn good() -> VideoMetadata {
+        metadata(
+            "I Hid My Brother's Car Keys For A Week",
+            "A guilty secret about a missing set of keys and the brother who never found out.",
+            "My brother still thinks he lost his keys that week.",
+            &["shorts", "RedditConfessions"],
+        )
+    }
+
+    fn validator(config: &Config) -> Validator {
+        Validator::new(config, SYSTEM_PROMPT)
+    }
+
+    #[test]
+    fn clean_metadata_passes() {
+        let report = validator(&Config::default()).check(&good());
+        assert!(report.passed(), "{}", report);
+    }
+
+    #[test]
+    fn refusals_are_reported_as_such() {
+        let validator = validator(&Config::default());
+        let mut refused = good();
+        refused.title = "I'm sorry, but I can't help with this request".to_string();
+        let report = validator.check(&refused);
+        assert!(report.refused());
+        assert!(report.issues.contains(&Issue::Refusal {
+            field: Field::Title,
+            phrase: "i'm sorry, but".to_string(),
+        }));
+
+        assert!(validator
+            .check_reply("As an AI language model I cannot write this.")
+            .refused());
+        assert!(!validator.check_reply("{\"title\": \"Fine\"}").refused());
+    }
+
+    #[test]
+    fn configured_refusal_phrases_count() {
+        let mut config = Config::default();
+        config.validate.refusal_phrases = vec!["Not Appropriate".to_string()];
+        let mut refused = good();
+        refused.description = "This story is not appropriate for a video, sorry about that.".into();
+        assert!(validator(&config).check(&refused).refused());
+    }
+
+    #[test]
+    fn meta_commentary_and_quotes_are_flagged() {
+        let validator = validator(&Config::default());
+        let mut chatty = good();
+        chatty.title = "Here is a title: I Hid The Keys".to_string();
+        chatty.hook = Some("\"My brother still thinks he lost them.\"".to_string());
+        let report = validator.check(&chatty);
+        assert!(report.issues.iter().any(|issue| matches!(
+            issue,
+            Issue::MetaCommentary {
+                field: Field::Title,
+                ..
+            }
+        )));
+        assert!(report
+            .issues
+            .contains(&Issue::Quoted { field: Field::Hook }));
+        assert!(!report.refused());
+    }
+
+    #[test]
+    fn repeated_instructions_are_a_leak() {
+        let mut leaked = good();
+        leaked.description =
+            "Short videos that retell anonymous Reddit confessions, like this one.".to_string();
+        let report = validator(&Config::default()).check(&leaked);
+        assert!(report.issues.contains(&Issue::LeakedPrompt {
+            field: Field::Description,
+            phrase: "short videos that retell anonymous".to_string(),
+        }));
+    }
+
+    #[test]
+    fn wrong_language_is_flagged_and_can_be_turned_off() {
+        let mut spanish = good();
+        spanish.description =
+            "Escondí las llaves del coche de mi hermano durante una semana entera y nunca lo supo."
+                .to_string();
+        let report = validator(&Config::default()).check(&spanish);
+        assert!(report.issues.iter().any(|issue| matches!(
+            issue,
+            Issue::WrongLanguage {
+                field: Field::Description,
+                ..
+            }
+        )));
+
+        let mut config = Config::default();
+        config.validate.language = String::new();
+        assert!(validator(&config).check(&spanish).passed());
+    }
+
+    #[test]
+    fn lengths_are_limited() {
+        let mut config = Config::default();
+        config.upload.max_title_length = 10;
+        config.validate.max_hook_words = 3;
+        let report = validator(&config).check(&good());
+        assert!(report.issues.contains(&Issue::TooLong {
+            field: Field::Title,
+            length: good().title.chars().count(),
+            max: 10,
+            unit: "characters",
+        }));
+        assert!(report.issues.iter().any(|issue| matches!(
+            issue,
+            Issue::TooLong {
+                field: Field::Hook,
+                max: 3,
+                ..
+            }
+        )));
+    }
+
+    #[test]
+    fn required_hashtags_match_case_insensitively() {
+        let validator = validator(&Config::default());
+        let mut tagged = good();
+        tagged.hashtags = vec!["#Shorts".to_string(), "redditconfessions".to_string()];
+        assert!(validator.check(&tagged).passed());
+
+        tagged.hashtags = vec!["shorts".to_string()];
+        assert_eq!(
+            validator.check(&tagged).issues,
+            vec![Issue::MissingHashtag("redditconfessions".to_string())]
+        );
+    }
+
+    #[test]
+    fn story_phrases_are_not_refusals_in_spoken_text() {
+        let validator = validator(&Config::default());
+        for narration in [
+            "My roommate sings in the shower and I can't help but laugh every time.",
+            "I lied to my boss about the car. I won't do that again, trust me.",
+            "I lost my job last spring and I can't provide for my kids anymore.",
+            "I told her everything. I'm sorry, but I had to.",
+        ] {
+            let report = validator.check_script(Field::Narration, narration, "");
+            assert!(!report.refused(), "{}: {}", narration, report);
+        }
+    }
+
+    #[test]
+    fn opening_refusals_are_caught_in_spoken_text() {
+        let validator = validator(&Config::default());
+        for reply in [
+            "I'm sorry, but I can't rewrite this story.",
+            "I cannot help with that request.",
+            "As an AI, I don't retell stories like this.",
+        ] {
+            assert!(
+                validator.check_script(Field::Outro, reply, "").refused(),
+                "{}",
+                reply
+            );
+        }
+    }
+
+    #[test]
+    fn refusal_phrases_from_the_source_are_ignored() {
+        let source = "I'm sorry, but I can't help you move, I told my brother.";
+        let narration =
+            "I'm sorry, but I can't help you move, I told my brother. He never forgave me.";
+        let report =
+            validator(&Config::default()).check_script(Field::Narration, narration, source);
+        assert!(!report.refused(), "{}", report);
+    }
+
+    #[test]
+    fn refusals_anywhere_in_a_reply_are_caught() {
+        let report = validator(&Config::default())
+            .check_reply("Here you go. I cannot create content like this.");
+        assert!(report.refused());
+    }
+}
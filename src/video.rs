@@ -4,48 +4,58 @@ use symphonia::core::{
     probe::Hint,
 };
 
+use crate::error::{Error, Result};
+
 const PYTHON_ENCODING: &str = "utf8";
-type ResultString<T> = Result<T, String>;
-
-struct VideoGeneratorConfig<'a> {
-    video_clip_path: &'a str,
-    audio_clip_path: &'a str,
-    formatted_text: &'a str,
-    output_video_path: &'a str,
-    subtitle_fontsize: Option<i32>,
-    subtitle_color: Option<&'a str>,
+/// Environment variables with the music bed to mix under the voice-over and its
+/// volume relative to it. `src/vid_generator.py` lives outside this repository;
+/// a version that does not read them renders without music.
+const MUSIC_ENV: &str = "MUSIC_BED";
+const MUSIC_VOLUME_ENV: &str = "MUSIC_VOLUME";
+
+/// What the render script is given for one video.
+pub struct VideoGeneratorConfig<'a> {
+    pub video_clip_path: &'a str,
+    pub audio_clip_path: &'a str,
+    pub formatted_text: &'a str,
+    pub output_video_path: &'a str,
+    pub subtitle_fontsize: Option<i32>,
+    pub subtitle_color: Option<&'a str>,
+    pub music: Option<&'a str>,
+    pub music_volume: f32,
 }
 
-fn execute_command(cmd: &mut Command) -> ResultString<()> {
+fn execute_command(cmd: &mut Command) -> Result<()> {
     let output = cmd
         .output()
-        .map_err(|e| format!("Failed to execute video generation script: {}", e))?;
+        .map_err(|e| Error::Render(format!("Failed to execute video generation script: {}", e)))?;
 
     if output.status.success() {
         Ok(())
     } else {
         let error = String::from_utf8_lossy(&output.stderr);
-        Err(format!("Video generation failed: {}", error))
+        Err(Error::Render(format!("Video generation failed: {}", error)))
     }
 }
 
-pub fn get_duration_from_audio(audio_clip_path: &str) -> ResultString<f32> {
+pub fn get_duration_from_audio(audio_clip_path: &str) -> Result<f32> {
     let file = open_audio_file(audio_clip_path)?;
     let mss = MediaSourceStream::new(Box::new(file), Default::default());
     let format = probe_audio_format(mss)?;
-    let track = find_audio_track(&format)?;
+    let track = find_audio_track(format.as_ref())?;
     let duration = calculate_duration(&track)?;
 
     Ok(duration as f32)
 }
 
-fn open_audio_file(path: &str) -> ResultString<File> {
-    File::open(Path::new(path)).map_err(|e| format!("Failed to open audio file: {}", e))
+fn open_audio_file(path: &str) -> Result<File> {
+    File::open(Path::new(path))
+        .map_err(|e| Error::Render(format!("Failed to open audio file: {}", e)))
 }
 
 fn probe_audio_format(
     mss: MediaSourceStream,
-) -> ResultString<Box<dyn symphonia::core::formats::FormatReader>> {
+) -> Result<Box<dyn symphonia::core::formats::FormatReader>> {
     let mut hint = Hint::new();
     hint.with_extension("wav");
 
@@ -56,22 +66,22 @@ fn probe_audio_format(
             &FormatOptions::default(),
             &MetadataOptions::default(),
         )
-        .map_err(|e| format!("Probe error: {}", e))
+        .map_err(|e| Error::Render(format!("Probe error: {}", e)))
         .map(|probed| probed.format)
 }
 
 fn find_audio_track(
-    format: &Box<dyn symphonia::core::formats::FormatReader>,
-) -> ResultString<symphonia::core::formats::Track> {
+    format: &dyn symphonia::core::formats::FormatReader,
+) -> Result<symphonia::core::formats::Track> {
     format
         .tracks()
         .iter()
         .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
-        .ok_or("No supported audio tracks".to_string())
-        .map(|track| track.clone())
+        .ok_or_else(|| Error::Render("No supported audio tracks".to_string()))
+        .cloned()
 }
 
-fn calculate_duration(track: &symphonia::core::formats::Track) -> ResultString<f64> {
+fn calculate_duration(track: &symphonia::core::formats::Track) -> Result<f64> {
     track
         .codec_params
         .time_base
@@ -81,27 +91,11 @@ fn calculate_duration(track: &symphonia::core::formats::Track) -> ResultString<f
                 .n_frames
                 .map(|frames| (frames as f64 * time_base.numer as f64) / time_base.denom as f64)
         })
-        .ok_or("Could not calculate duration".to_string())
+        .ok_or_else(|| Error::Render("Could not calculate duration".to_string()))
 }
 
-pub fn execute_python_video_generator(
-    video_clip_path: &str,
-    audio_clip_path: &str,
-    formatted_text: &str,
-    output_video_path: &str,
-    subtitle_fontsize: Option<i32>,
-    subtitle_color: Option<&str>,
-) -> ResultString<()> {
-    let config = VideoGeneratorConfig {
-        video_clip_path,
-        audio_clip_path,
-        formatted_text,
-        output_video_path,
-        subtitle_fontsize,
-        subtitle_color,
-    };
-
-    let mut cmd = build_python_command(&config);
+pub fn execute_python_video_generator(config: &VideoGeneratorConfig) -> Result<()> {
+    let mut cmd = build_python_command(config);
     execute_command(&mut cmd)
 }
 
@@ -113,6 +107,10 @@ fn build_python_command(config: &VideoGeneratorConfig) -> Command {
         .arg(config.audio_clip_path)
         .arg(config.formatted_text.replace("'", "\\'"))
         .arg(config.output_video_path);
+    if let Some(music) = config.music {
+        cmd.env(MUSIC_ENV, music)
+            .env(MUSIC_VOLUME_ENV, config.music_volume.to_string());
+    }
 
     if let Some(size) = config.subtitle_fontsize {
         cmd.arg(size.to_string());